@@ -1,18 +1,29 @@
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Deref;
+use std::sync::{Arc, Condvar, Mutex, PoisonError, RwLock, RwLockWriteGuard, TryLockError};
 use std::time::{Duration, Instant};
 use crate::file_mgr::*;
 use crate::log_mgr::*;
+use crate::error::{DbError, DbResult};
 use log::{warn, info};
+pub mod replacement;
+#[cfg(test)]
 pub mod test;
+pub use replacement::{ReplacementPolicy, ReplacementStrategy};
 pub struct Buffer {
     fm:  Arc<Mutex<FileMgr>>,
     lm:  Arc<Mutex<LogMgr>>,
     page_buf: Vec<u8>,
     blk: BlockId,
-    pins: i32,
     tx_num: i32,
-    lsn: i32,
+    //lsn of the latest log record changing the page, None if there is none
+    lsn: Option<u64>,
+    /*
+    false until the block is read, a thread pinning the block while it is
+    read by another thread checks it after taking the buffer lock
+    */
+    valid: bool,
 }
 
 impl Buffer {
@@ -23,13 +34,13 @@ impl Buffer {
             lm,
             page_buf: vec![0u8; block_size as usize],
             blk: BlockId::new("notexist.txt", 0),
-            pins: 0,
             tx_num: -1,
-            lsn: -1,
+            lsn: None,
+            valid: false,
         }
     }
 
-    pub fn contents(&mut self) -> Page {
+    pub fn contents(&mut self) -> Page<'_> {
         Page::from_buffer(&mut self.page_buf)
     }
 
@@ -37,9 +48,9 @@ impl Buffer {
         self.blk.clone()
     }
 
-    pub fn set_modified(&mut self, tx_num: i32, lsn: i32) {
+    pub fn set_modified(&mut self, tx_num: i32, lsn: Option<u64>) {
         self.tx_num = tx_num;
-        if lsn >= 0 {
+        if lsn.is_some() {
             self.lsn = lsn;
         }
     }
@@ -48,67 +59,172 @@ impl Buffer {
         self.tx_num
     }
 
-    pub fn is_pinned(&self) -> bool {
-        self.pins > 0
-    }
-
-    pub fn assign_to_block(&mut self, b: BlockId) {
-        self.flush();
+    pub fn assign_to_block(&mut self, b: BlockId) -> DbResult<()> {
+        self.flush()?;
         self.blk = b.clone();
+        self.valid = false;
         let mut p = Page::from_buffer(&mut self.page_buf);
         /*
-        we don't have unwrap for read_write since the given file may not
-        have the given block then we read nothing from the file
+        the given file may not have the given block yet, then we read
+        nothing from the file and start with an empty page, other errors
+        like disk failure are returned to the caller
         */
-        let result = self.fm.lock().unwrap().read_write(&self.blk, &mut p, false);
+        let result = self.fm.lock().unwrap().read_with_lsn(&self.blk, &mut p);
         match result {
-            Ok(bytes_read) => {
+            Ok((bytes_read, page_lsn)) => {
                 info!("buffer assign with block: {:?}, with bytes read: {}", self.blk.clone(), bytes_read);
+                //the page keeps its lsn when it is written back without new log records
+                self.lsn = (page_lsn > 0).then_some(page_lsn);
             },
-            Err(err) => {
+            Err(err @ DbError::PageOverflow { .. }) => {
                 warn!("buffer assign with block: {:?}, with err: {}", self.blk.clone(), err);
-            }
+                p.contents().fill(0);
+                self.lsn = None;
+            },
+            Err(err) => return Err(err),
         }
-        self.pins = 0;
+        self.valid = true;
+        Ok(())
     }
 
-    pub fn flush(&mut self) {
+    pub fn flush(&mut self) -> DbResult<()> {
         if self.tx_num > 0 {
-            self.lm.lock().unwrap().flush(self.lsn as u64);
+            //write ahead, the log records of the page reach the disk first
+            let ticket = self.lm.lock().unwrap().flush(self.lsn.unwrap_or(u64::MAX))?;
+            ticket.wait()?;
             let mut p = Page::from_buffer(&mut self.page_buf);
-            self.fm.lock().unwrap().read_write(&self.blk, &mut p, true).unwrap();
+            self.fm.lock().unwrap().write_with_lsn(&self.blk, &mut p, self.lsn.unwrap_or(0))?;
             self.tx_num = -1;
         }
+        Ok(())
     }
 
-    pub fn pin(&mut self)  {
-        self.pins += 1;
-        
+    //forget the block without writing it back, its file is being removed
+    fn discard(&mut self) {
+        self.tx_num = -1;
+        self.lsn = None;
+        self.valid = false;
     }
 
-    pub fn unpin(&mut self) {
-        self.pins -= 1;
+}
+
+/*
+how long a thread waits for an available buffer if the caller does not
+give a time out
+*/
+pub const MAX_WAIT: Duration = Duration::from_secs(10);
+
+/*
+bookkeeping of the pool, the mutex guarding it is only held for short
+operations, reading and writing blocks happens outside of it with only
+the lock of the buffer involved
+*/
+struct PoolState {
+    num_available: u32,
+    /*
+    pin count of each buffer, kept here rather than in the buffer so that
+    pinning and unpinning never wait for the lock of a buffer, which may
+    be held for disk io or a page operation
+    */
+    pin_counts: Vec<u32>,
+    /*
+    tickets of waiting threads in arrival order, only the thread at the
+    head may take the next available buffer, that keeps a late comer
+    from taking the buffer away from those waiting longer
+    */
+    waiters: VecDeque<u64>,
+    next_ticket: u64,
+    /*
+    modified blocks taken out of the page table whose buffers are being
+    written back, pinning one of them waits until it is on disk
+    */
+    evicting: HashSet<BlockId>,
+}
+
+/*
+a buffer taken for a new block, its write lock is held until the block
+is read. evicted is the modified block the buffer held before, it is
+written back after the pool is unlocked
+*/
+struct Loading<'a> {
+    buf_guard: RwLockWriteGuard<'a, Buffer>,
+    evicted: Option<BlockId>,
+}
+
+/*
+a pinned buffer, the buffer is unpinned when the guard is dropped, even
+if the thread panics or returns early while the buffer is pinned. Page is
+only a view borrowed from the buffer, therefore the guard derefs to the
+lock of the buffer and the page is reached by buf.write().contents()
+*/
+pub struct PinnedBuffer {
+    bm: BufferMgr,
+    //index of the buffer in the pool, the pin is given back to it
+    idx: usize,
+    buffer: Arc<RwLock<Buffer>>,
+}
+
+impl PinnedBuffer {
+    pub fn block(&self) -> BlockId {
+        self.read().unwrap_or_else(PoisonError::into_inner).block()
     }
 
-    pub fn pin_count(&self) -> i32 {
-        self.pins
+    /*
+    the buffer itself, it is still there after the guard is dropped but
+    may be holding another block by then
+    */
+    pub fn buffer(&self) -> Arc<RwLock<Buffer>> {
+        Arc::clone(&self.buffer)
     }
+}
+
+impl Deref for PinnedBuffer {
+    type Target = RwLock<Buffer>;
+
+    fn deref(&self) -> &RwLock<Buffer> {
+        &self.buffer
+    }
+}
 
+impl Drop for PinnedBuffer {
+    fn drop(&mut self) {
+        self.bm.unpin(self.idx);
+    }
 }
 
+/*
+BufferMgr is shared by worker threads with Arc<BufferMgr>, all methods
+take &self and synchronize internally. Every field is shared, a clone
+is another handle of the same pool, that is what a PinnedBuffer keeps
+to unpin itself
+*/
+#[derive(Clone)]
 pub struct BufferMgr {
     /*
     several threads may access the same buffer at the same time,
     that's why we need to have Arc<Mutex<Buffer>>> as Vec element
     */
     buffer_pool: Arc<Vec<Arc<RwLock<Buffer>>>>,
-    num_available: Arc<Mutex<u32>>,
-    //telling all threads waiting for buffers to wake up
-    wake_up: Arc<AtomicBool>,
+    /*
+    map block to the index of the buffer holding it, then we don't need
+    to go through the whole pool and lock every buffer to find a block
+    */
+    page_table: Arc<Mutex<HashMap<BlockId, usize>>>,
+    pool_state: Arc<Mutex<PoolState>>,
+    //signaled when a buffer turns unpinned or the head waiter leaves
+    buffer_released: Arc<Condvar>,
+    //decide which unpinned buffer is replaced by a new block
+    policy: Arc<Mutex<Box<dyn ReplacementPolicy>>>,
+    //pins served by a buffer already holding the block
+    hits: Arc<AtomicU64>,
+    //pins that need to read the block from disk
+    misses: Arc<AtomicU64>,
+    fm: Arc<Mutex<FileMgr>>,
 }
 
 impl BufferMgr {
-    pub fn new(fm:  Arc<Mutex<FileMgr>>, lm:  Arc<Mutex<LogMgr>>, num_buffers: u32) -> Self {
+    pub fn new(fm:  Arc<Mutex<FileMgr>>, lm:  Arc<Mutex<LogMgr>>, num_buffers: u32,
+        strategy: ReplacementStrategy) -> Self {
         let mut buf_vec = Vec::with_capacity(num_buffers as usize);
         for _ in 0..num_buffers {
             let buf = Buffer::new(fm.clone(), lm.clone());
@@ -116,158 +232,337 @@ impl BufferMgr {
         }
         BufferMgr {
             buffer_pool: Arc::new(buf_vec),
-            num_available: Arc::new(Mutex::new(num_buffers)),
-            wake_up: Arc::new(AtomicBool::new(false)),
+            page_table: Arc::new(Mutex::new(HashMap::new())),
+            pool_state: Arc::new(Mutex::new(PoolState {
+                num_available: num_buffers,
+                pin_counts: vec![0; num_buffers as usize],
+                waiters: VecDeque::new(),
+                next_ticket: 0,
+                evicting: HashSet::new(),
+            })),
+            buffer_released: Arc::new(Condvar::new()),
+            policy: Arc::new(Mutex::new(strategy.build(num_buffers as usize))),
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+            fm,
         }
     }
 
-    /*
-    explain Ordering::Relaxex:
-    use std::sync::atomic::{AtomicBool, Ordering};
-    use std::thread;
-
-    let data = 0;
-    let ready = AtomicBool::new(false);
-
-    // Thread A
-    thread::spawn(|| {
-        data = 42; // Non-atomic write
-        ready.store(true, Ordering::Relaxed); // No guarantee data is visible to Thread B
-    });
-
-    // Thread B
-    thread::spawn(|| {
-        while !ready.load(Ordering::Relaxed) {} // Might loop forever even if `ready` is true
-        println!("{}", data); // Could see `0` or `42` (undefined behavior!)
-    });
-    */
-
-    fn wait(&self) {
-        /*
-        wait at most 10 seconds to check whether there are buffer 
-        avaialbe
-        */
-        let total_wait = Duration::from_secs(10);
-        let check_interval = Duration::from_millis(100);
-        let mut waited = Duration::from_secs(0);
-        while waited < total_wait && !self.wake_up.load(Ordering::Relaxed) {
-            /*
-            the calling thread need to wait totally 10 secs, but it will 
-            wake up for every 100 milli-second to check whether buffer is available 
-            */
-            std::thread::sleep(check_interval);
-            waited += check_interval;
-        }
+    pub fn available(&self) -> u32 {
+        self.pool_state.lock().unwrap().num_available
     }
 
-    fn notify_all(&mut self) {
-        self.wake_up.store(true, Ordering::Relaxed);
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::SeqCst)
     }
 
-    pub fn available(&self) -> u32 {
-        *self.num_available.lock().unwrap()
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::SeqCst)
     }
 
-    pub fn flush_all(&mut self, tx_num: i32)  {
+    /*
+    write the buffers modified by the transaction and sync their files,
+    undo only recovery can't bring back a committed change that is lost
+    from the os cache, the pages must be on disk before the COMMIT record
+    */
+    pub fn flush_all(&self, tx_num: i32) -> DbResult<()> {
+       let mut files = HashSet::new();
        for buf_lock in  self.buffer_pool.iter() {
           let mut buf = buf_lock.write().unwrap();
           if buf.modifing_tx() == tx_num {
-              buf.flush();
+              buf.flush()?;
+              files.insert(buf.block().file_name());
           }
        }
+       /*
+       a thread looking for a victim may have skipped the buffers while they
+       were locked, taking the pool lock first makes sure it is waiting by
+       the time it is notified
+       */
+       drop(self.pool_state.lock().unwrap());
+       self.buffer_released.notify_all();
+       let fm = self.fm.lock().unwrap();
+       if fm.sync_policy() != SyncPolicy::Never {
+           for file_name in files {
+               fm.sync(&file_name)?;
+           }
+       }
+       Ok(())
     }
 
-   fn increase_availabe_buff(&mut self, buffer_lock : Arc<RwLock<Buffer>>) {
-        let mut num_available = self.num_available.lock().unwrap();
-        let mut buf = buffer_lock.write().unwrap();
-        //change here
-        buf.unpin();
-        if !buf.is_pinned() {
-            *num_available += 1;
-        } 
-   }
-
-    pub fn unpin(&mut self ,  buffer_lock: Arc<RwLock<Buffer>>) {
-        self.increase_availabe_buff(buffer_lock);
-        self.notify_all();
+    /*
+    drop the blocks of the file from the pool without writing them back,
+    then close and delete the file. A block still being written back is
+    waited for, the write would bring the file back. A pinned block means
+    the file is still in use and it is kept. The caller makes sure nobody
+    pins a block of the file afterwards
+    */
+    pub fn remove_file(&self, file_name: &str) -> DbResult<()> {
+        let in_file = |blk: &BlockId| blk.file_name() == file_name;
+        let mut state = self.pool_state.lock().unwrap();
+        loop {
+            let mut busy = state.evicting.iter().any(in_file);
+            let mut page_table = self.page_table.lock().unwrap();
+            let blocks: Vec<(BlockId, usize)> = page_table
+                .iter()
+                .filter(|(blk, _)| in_file(blk))
+                .map(|(blk, idx)| (blk.clone(), *idx))
+                .collect();
+            for (blk, idx) in blocks {
+                if state.pin_counts[idx] > 0 {
+                    return Err(DbError::FileInUse(file_name.to_string()));
+                }
+                //the buffer lock is only tried under the pool lock, a busy buffer is tried again
+                let mut buf = match self.buffer_pool[idx].try_write() {
+                    Ok(buf) => buf,
+                    Err(TryLockError::WouldBlock) => {
+                        busy = true;
+                        continue;
+                    }
+                    Err(TryLockError::Poisoned(err)) => err.into_inner(),
+                };
+                buf.discard();
+                page_table.remove(&blk);
+            }
+            drop(page_table);
+            if !busy {
+                break;
+            }
+            state = self.buffer_released.wait_timeout(state, Duration::from_millis(10)).unwrap().0;
+        }
+        drop(state);
+        self.fm.lock().unwrap().remove_file(file_name)
+    }
+
+    //pins of the buffer holding the block, 0 if the block is not in the pool
+    pub fn pin_count(&self, blk: &BlockId) -> u32 {
+        let state = self.pool_state.lock().unwrap();
+        self.find_existing_buffer(blk).map_or(0, |idx| state.pin_counts[idx])
+    }
+
+    /*
+    called by PinnedBuffer on drop, maybe while the thread is panicking
+    with the locks poisoned. The pin count must be given back anyway,
+    otherwise the buffer is lost to the pool forever. The buffer itself
+    is not locked, the thread may still hold a guard of it, clearing the
+    poison needs no lock
+    */
+    fn unpin(&self, idx: usize) {
+        let mut state = self.pool_state.lock().unwrap_or_else(PoisonError::into_inner);
+        self.buffer_pool[idx].clear_poison();
+        self.release_pin(&mut state, idx);
+    }
+
+    //give back one pin of the buffer to the pool
+    fn release_pin(&self, state: &mut PoolState, idx: usize) {
+        state.pin_counts[idx] -= 1;
+        if state.pin_counts[idx] == 0 {
+            state.num_available += 1;
+            self.buffer_released.notify_all();
+        }
+    }
+
+    fn find_existing_buffer(&self, blk: &BlockId) -> Option<usize> {
+        self.page_table.lock().unwrap().get(blk).copied()
     }
 
-    fn wait_too_long(&self, start_time: Instant) -> bool {
-        start_time.elapsed() >= Duration::from_secs(10)
+    /*
+    pinning a block held by a pinned buffer takes no buffer from the
+    waiting threads, such request needs not to wait in the queue
+    */
+    fn is_pinned_in_pool(&self, state: &PoolState, blk: &BlockId) -> bool {
+        match self.find_existing_buffer(blk) {
+            Some(idx) => state.pin_counts[idx] > 0,
+            None => false,
+        }
     }
 
-    fn find_existing_buffer(&mut self,blk : BlockId) -> Option<usize>{
-        for (i,buf) in  self.buffer_pool.iter().enumerate() {
-            let b = buf.read().unwrap().block();
-            if b == blk {
-                return Some(i);
+    /*
+    an unpinned buffer and its write lock. The lock is only tried, the pool
+    is locked and must not wait for a thread flushing the buffer or still
+    holding a guard of it after unpinning, such buffer is skipped
+    */
+    fn choose_unpin_buffer<'a>(&'a self, state: &PoolState) -> Option<(usize, RwLockWriteGuard<'a, Buffer>)> {
+        let mut busy = HashSet::new();
+        loop {
+            let is_pinned = |i: usize| state.pin_counts[i] > 0 || busy.contains(&i);
+            let idx = self.policy.lock().unwrap().choose_victim(&is_pinned)?;
+            match self.buffer_pool[idx].try_write() {
+                Ok(buf_guard) => return Some((idx, buf_guard)),
+                Err(TryLockError::WouldBlock) => {
+                    busy.insert(idx);
+                }
+                Err(TryLockError::Poisoned(err)) => panic!("{}", err),
             }
         }
-       None
     }
 
-    fn choose_unpin_buffer(&self) -> Option<usize> {
-        for (i,buf) in self.buffer_pool.iter().enumerate() {
-            let buf_guard = buf.read().unwrap();
-            if !buf_guard.is_pinned() {
-                return Some(i);
+    /*
+    called with the pool locked, the block is either found in the pool or
+    assigned to an unpinned buffer. For a new block the buffer's write
+    lock is taken before the pool is unlocked and returned, the caller
+    reads the block with it, any thread pinning the same block meanwhile
+    waits on the buffer lock until the block is read.
+
+    the victim leaves the page table at once, if it holds a modified block
+    the block is written back by the caller as well. Until that is done the
+    block is marked as evicting and nobody can pin it, otherwise another
+    thread may read the old version of the block from disk
+    */
+    fn try_to_pin<'a>(&'a self, state: &mut PoolState, blk: &BlockId) -> Option<(usize, Option<Loading<'a>>)> {
+        if state.evicting.contains(blk) {
+            return None;
+        }
+        if let Some(idx) = self.find_existing_buffer(blk) {
+            self.hits.fetch_add(1, Ordering::SeqCst);
+            self.policy.lock().unwrap().record_access(idx);
+            if state.pin_counts[idx] == 0 {
+                state.num_available -= 1;
             }
+            state.pin_counts[idx] += 1;
+            return Some((idx, None));
+        }
 
+        let (idx, buf_guard) = self.choose_unpin_buffer(state)?;
+        let old_blk = buf_guard.block();
+        let mut page_table = self.page_table.lock().unwrap();
+        if page_table.get(&old_blk) == Some(&idx) {
+            page_table.remove(&old_blk);
         }
+        page_table.insert(blk.clone(), idx);
+        drop(page_table);
+        let evicted = (buf_guard.modifing_tx() > 0).then(|| {
+            state.evicting.insert(old_blk.clone());
+            old_blk
+        });
+
+        let mut policy = self.policy.lock().unwrap();
+        policy.record_load(idx);
+        policy.record_access(idx);
+        drop(policy);
+        self.misses.fetch_add(1, Ordering::SeqCst);
+        state.num_available -= 1;
+        state.pin_counts[idx] = 1;
+        Some((idx, Some(Loading { buf_guard, evicted })))
+    }
 
-        None
+    /*
+    write back the modified block the buffer held before, pinning that
+    block is possible again when this is done. If the write fails the
+    buffer goes back to the old block and our pin is given back
+    */
+    fn write_back(&self, buf: &mut Buffer, idx: usize, evicted: &BlockId, blk: &BlockId) -> DbResult<()> {
+        let flushed = buf.flush();
+        let mut state = self.pool_state.lock().unwrap();
+        state.evicting.remove(evicted);
+        self.buffer_released.notify_all();
+        if flushed.is_err() {
+            let mut page_table = self.page_table.lock().unwrap();
+            if page_table.get(blk) == Some(&idx) {
+                page_table.remove(blk);
+            }
+            page_table.insert(evicted.clone(), idx);
+            drop(page_table);
+            self.release_pin(&mut state, idx);
+        }
+        flushed
     }
 
-    fn try_to_pin(&mut self ,blk : BlockId) ->Option<usize> {
-        let mut i = self.find_existing_buffer(blk.clone());
-        
-        if i.is_none() {
-            i = self.choose_unpin_buffer();
-            if i.is_none() {
-                return None;
+    /*
+    finish pinning after the pool is unlocked, disk io happens here. None
+    if the block was found in the pool but the thread reading it failed,
+    the caller pins it again
+    */
+    fn complete_pin(&self, idx: usize, loading: Option<Loading>, blk: &BlockId) -> DbResult<Option<PinnedBuffer>> {
+        let buffer = &self.buffer_pool[idx];
+        match loading {
+            None => {
+                let buf = buffer.read().unwrap();
+                if !buf.valid || buf.block() != *blk {
+                    drop(buf);
+                    let mut state = self.pool_state.lock().unwrap();
+                    self.release_pin(&mut state, idx);
+                    return Ok(None);
+                }
             }
-            let idx = i.unwrap();
-            let mut buf_guard = self.buffer_pool[idx].write().unwrap();
-            buf_guard.assign_to_block(blk);
-        } 
-
-        let mut buf_guard = self.buffer_pool[i.unwrap()].write().unwrap();
-        if !buf_guard.is_pinned() {
-            let mut num_available = self.num_available.lock().unwrap();
-            *num_available -= 1;
-            if *num_available == 0 {
-                /*
-                buffer pool is empty, then thread request for buffer
-                need to wait
-                */
-                self.wake_up.store(false, Ordering::Relaxed);
+            Some(Loading { mut buf_guard, evicted }) => {
+                if let Some(evicted) = evicted {
+                    self.write_back(&mut buf_guard, idx, &evicted, blk)?;
+                }
+                if let Err(err) = buf_guard.assign_to_block(blk.clone()) {
+                    /*
+                    the buffer holds no valid block, take it out of the
+                    page table and give back our pin, threads that found
+                    the block meanwhile hold pins of their own
+                    */
+                    drop(buf_guard);
+                    let mut state = self.pool_state.lock().unwrap();
+                    let mut page_table = self.page_table.lock().unwrap();
+                    if page_table.get(blk) == Some(&idx) {
+                        page_table.remove(blk);
+                    }
+                    drop(page_table);
+                    self.release_pin(&mut state, idx);
+                    return Err(err);
+                }
             }
         }
-        
-        buf_guard.pin();
-        i
+        Ok(Some(PinnedBuffer {
+            bm: self.clone(),
+            idx,
+            buffer: Arc::clone(buffer),
+        }))
+    }
+
+    pub fn pin(&self, blk: BlockId) -> DbResult<PinnedBuffer> {
+        self.pin_with_timeout(blk, MAX_WAIT)
     }
 
-    pub fn pin(&mut self, blk: BlockId) -> Option<Arc<RwLock<Buffer>>> {
-        let time_stamp = Instant::now();
-        let mut i = self.try_to_pin(blk.clone());
-        while i.is_none() && !self.wait_too_long(time_stamp) {
-            /*
-            no buffer available and waiting time is not longer than 10 secs
-            then continue to wait,if there is a buffer available, then all
-            waiting threads are wake up, and current thread may loss the race
-            to gain the buffer, then it will go to wait again
-            */
-            self.wait();
-            i = self.try_to_pin(blk.clone());
+    /*
+    wait at most max_wait for an available buffer, waiting threads are
+    served in the order they come, BufferAbort tells the caller to
+    abort its transaction and release the buffers it holds
+    */
+    pub fn pin_with_timeout(&self, blk: BlockId, max_wait: Duration) -> DbResult<PinnedBuffer> {
+        let start = Instant::now();
+        loop {
+            let (idx, loading) = self.reserve_buffer(&blk, start, max_wait)?;
+            if let Some(pinned) = self.complete_pin(idx, loading, &blk)? {
+                return Ok(pinned);
+            }
         }
+    }
 
-        if i.is_none() {
-            return None;
+    //the buffer for the block, waiting in the queue until one is available
+    fn reserve_buffer(&self, blk: &BlockId, start: Instant, max_wait: Duration)
+        -> DbResult<(usize, Option<Loading<'_>>)> {
+        let mut state = self.pool_state.lock().unwrap();
+        if state.waiters.is_empty() || self.is_pinned_in_pool(&state, blk) {
+            if let Some(reserved) = self.try_to_pin(&mut state, blk) {
+                return Ok(reserved);
+            }
         }
 
-        let idx = i.unwrap();
-        let buffer_arc = Arc::clone(&self.buffer_pool[idx]);
-        Some(buffer_arc)
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        state.waiters.push_back(ticket);
+        loop {
+            if state.waiters.front() == Some(&ticket) {
+                if let Some(reserved) = self.try_to_pin(&mut state, blk) {
+                    //leave the queue and let the next waiter check again
+                    state.waiters.pop_front();
+                    self.buffer_released.notify_all();
+                    return Ok(reserved);
+                }
+            }
+
+            let waited = start.elapsed();
+            if waited >= max_wait {
+                state.waiters.retain(|t| *t != ticket);
+                self.buffer_released.notify_all();
+                return Err(DbError::BufferAbort);
+            }
+            state = self.buffer_released.wait_timeout(state, max_wait - waited).unwrap().0;
+        }
     }
-}
\ No newline at end of file
+}
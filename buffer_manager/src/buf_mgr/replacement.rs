@@ -0,0 +1,181 @@
+use std::collections::VecDeque;
+
+/*
+decide which unpinned buffer gives up its block when the buffer manager
+needs room for a new block. Buffers are identified by their index in
+the buffer pool, the buffer manager tells the policy whenever a buffer
+is pinned or loaded with a new block
+*/
+pub trait ReplacementPolicy: Send {
+    //the buffer is pinned, either for a block it already holds or a new one
+    fn record_access(&mut self, frame: usize);
+
+    //the buffer is assigned to a new block, history of the old block is useless
+    fn record_load(&mut self, _frame: usize) {}
+
+    //choose an unpinned buffer to be replaced, None if all are pinned
+    fn choose_victim(&mut self, is_pinned: &dyn Fn(usize) -> bool) -> Option<usize>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplacementStrategy {
+    //the first unpinned buffer in the pool
+    Naive,
+    //least recently used
+    Lru,
+    //second chance
+    Clock,
+    //largest backward distance to the k-th latest access
+    LruK(usize),
+}
+
+impl ReplacementStrategy {
+    pub fn build(self, num_buffers: usize) -> Box<dyn ReplacementPolicy> {
+        match self {
+            ReplacementStrategy::Naive => Box::new(NaivePolicy::new(num_buffers)),
+            ReplacementStrategy::Lru => Box::new(LruPolicy::new(num_buffers)),
+            ReplacementStrategy::Clock => Box::new(ClockPolicy::new(num_buffers)),
+            ReplacementStrategy::LruK(k) => Box::new(LruKPolicy::new(num_buffers, k)),
+        }
+    }
+}
+
+pub struct NaivePolicy {
+    num_buffers: usize,
+}
+
+impl NaivePolicy {
+    pub fn new(num_buffers: usize) -> Self {
+        NaivePolicy { num_buffers }
+    }
+}
+
+impl ReplacementPolicy for NaivePolicy {
+    fn record_access(&mut self, _frame: usize) {}
+
+    fn choose_victim(&mut self, is_pinned: &dyn Fn(usize) -> bool) -> Option<usize> {
+        (0..self.num_buffers).find(|frame| !is_pinned(*frame))
+    }
+}
+
+pub struct LruPolicy {
+    //logical time of the latest access for each buffer
+    last_access: Vec<u64>,
+    clock: u64,
+}
+
+impl LruPolicy {
+    pub fn new(num_buffers: usize) -> Self {
+        LruPolicy {
+            last_access: vec![0; num_buffers],
+            clock: 0,
+        }
+    }
+}
+
+impl ReplacementPolicy for LruPolicy {
+    fn record_access(&mut self, frame: usize) {
+        self.clock += 1;
+        self.last_access[frame] = self.clock;
+    }
+
+    fn choose_victim(&mut self, is_pinned: &dyn Fn(usize) -> bool) -> Option<usize> {
+        (0..self.last_access.len())
+            .filter(|frame| !is_pinned(*frame))
+            .min_by_key(|frame| self.last_access[*frame])
+    }
+}
+
+pub struct ClockPolicy {
+    //reference bit is set on access and cleared when the hand passes by
+    referenced: Vec<bool>,
+    hand: usize,
+}
+
+impl ClockPolicy {
+    pub fn new(num_buffers: usize) -> Self {
+        ClockPolicy {
+            referenced: vec![false; num_buffers],
+            hand: 0,
+        }
+    }
+}
+
+impl ReplacementPolicy for ClockPolicy {
+    fn record_access(&mut self, frame: usize) {
+        self.referenced[frame] = true;
+    }
+
+    fn choose_victim(&mut self, is_pinned: &dyn Fn(usize) -> bool) -> Option<usize> {
+        let num_buffers = self.referenced.len();
+        /*
+        the first round clears reference bits, the second round is sure
+        to find an unpinned buffer if there is any
+        */
+        for _ in 0..2 * num_buffers {
+            let frame = self.hand;
+            self.hand = (self.hand + 1) % num_buffers;
+            if is_pinned(frame) {
+                continue;
+            }
+            if self.referenced[frame] {
+                self.referenced[frame] = false;
+                continue;
+            }
+            return Some(frame);
+        }
+        None
+    }
+}
+
+pub struct LruKPolicy {
+    k: usize,
+    //logical time of the latest k accesses for each buffer, latest at back
+    history: Vec<VecDeque<u64>>,
+    clock: u64,
+}
+
+impl LruKPolicy {
+    pub fn new(num_buffers: usize, k: usize) -> Self {
+        LruKPolicy {
+            k: k.max(1),
+            history: vec![VecDeque::new(); num_buffers],
+            clock: 0,
+        }
+    }
+}
+
+impl ReplacementPolicy for LruKPolicy {
+    fn record_access(&mut self, frame: usize) {
+        self.clock += 1;
+        let history = &mut self.history[frame];
+        history.push_back(self.clock);
+        if history.len() > self.k {
+            history.pop_front();
+        }
+    }
+
+    fn record_load(&mut self, frame: usize) {
+        self.history[frame].clear();
+    }
+
+    /*
+    buffers accessed less than k times have infinite backward distance and
+    go first, ties among them are broken by least recent access, that is
+    what keeps a one-time scan from flushing the frequently used blocks
+    */
+    fn choose_victim(&mut self, is_pinned: &dyn Fn(usize) -> bool) -> Option<usize> {
+        (0..self.history.len())
+            .filter(|frame| !is_pinned(*frame))
+            .min_by_key(|frame| {
+                let history = &self.history[*frame];
+                let kth_access = history.front().copied().unwrap_or(0);
+                let last_access = history.back().copied().unwrap_or(0);
+                if history.len() < self.k {
+                    (0, last_access)
+                } else {
+                    (1, kth_access)
+                }
+            })
+    }
+}
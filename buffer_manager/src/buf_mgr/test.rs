@@ -1,54 +1,544 @@
 use crate::buf_mgr::*;
-use crate::file_mgr::*;
-use crate::log_mgr::*;
 
 #[test]
 fn test_buffer_manager() {
-    let  file_mgr = FileMgr::new("buffermgrtest".to_string(), 400);
+    let  file_mgr = FileMgr::new("buffermgrtest".to_string(), 400).unwrap();
     let file_mgr_lock = Arc::new(Mutex::new(file_mgr));
-    let log_mgr = LogMgr::new(file_mgr_lock.clone(), "buffermgrtest".to_string());
+    let log_mgr = LogMgr::new(file_mgr_lock.clone(), "buffermgrtest".to_string()).unwrap();
     let log_mgr_lock = Arc::new(Mutex::new(log_mgr));
     //create buffer manager with only 3 buffers
-    let mut buf_mgr = BufferMgr::new(file_mgr_lock, log_mgr_lock, 3);
+    let buf_mgr = BufferMgr::new(file_mgr_lock, log_mgr_lock, 3, ReplacementStrategy::Naive);
 
    
     let  buf_block0 = buf_mgr.pin(BlockId::new("testfile.txt", 0)).unwrap();
-    assert_eq!(buf_block0.read().unwrap().pin_count(), 1);
+    assert_eq!(buf_mgr.pin_count(&BlockId::new("testfile.txt", 0)), 1);
    
     let  buf_block1 = buf_mgr.pin(BlockId::new("testfile.txt", 1)).unwrap();
-    assert_eq!(buf_block1.read().unwrap().pin_count(), 1);
+    assert_eq!(buf_mgr.pin_count(&BlockId::new("testfile.txt", 1)), 1);
 
     //here run out all availabe buffer
     let  buf_block2 = buf_mgr.pin(BlockId::new("testfile.txt", 2)).unwrap();
-    assert_eq!(buf_block2.read().unwrap().pin_count(), 1);
+    assert_eq!(buf_mgr.pin_count(&BlockId::new("testfile.txt", 2)), 1);
 
     //have one available buffer now, 
-    buf_mgr.unpin(buf_block1.clone());
-    assert_eq!(buf_block1.read().unwrap().pin_count(), 0);
+    drop(buf_block1);
+    assert_eq!(buf_mgr.pin_count(&BlockId::new("testfile.txt", 1)), 0);
     
 
     //should increase the pin of buffer0
-    let _ = buf_mgr.pin(BlockId::new("testfile.txt", 0)).unwrap();
-    assert_eq!(buf_block0.read().unwrap().pin_count(), 2);
+    let _buf_block0_again = buf_mgr.pin(BlockId::new("testfile.txt", 0)).unwrap();
+    assert_eq!(buf_mgr.pin_count(&buf_block0.block()), 2);
 
 
     let  buf_block1 = buf_mgr.pin(BlockId::new("testfile.txt", 1)).unwrap();
      //we pin block1 before and then unpin it, therefore this time 
     //pin number for block1 still 1
-    assert_eq!(buf_block1.read().unwrap().pin_count(), 1);
+    assert_eq!(buf_mgr.pin_count(&buf_block1.block()), 1);
 
     //we run out all buffers, if we pin buffer now, we will
     //wait for 10 secs
     let start = Instant::now();
     let pin_result = buf_mgr.pin(BlockId::new("testfile.txt", 3));
     let wait_long_enough = start.elapsed() >= Duration::from_secs(10);
-    assert_eq!(wait_long_enough, true);
-    assert_eq!(pin_result.is_none(), true);
+    assert!(wait_long_enough);
+    assert!(matches!(pin_result, Err(DbError::BufferAbort)));
 
     //unpin one buffer then can pin for block 3
-    buf_mgr.unpin(buf_block2.clone());
-    assert_eq!(buf_block2.read().unwrap().pin_count(), 0);
+    let buffer2 = buf_block2.buffer();
+    drop(buf_block2);
+    assert_eq!(buf_mgr.pin_count(&BlockId::new("testfile.txt", 2)), 0);
 
-    let _ = buf_mgr.pin(BlockId::new("testfile.txt", 3)).unwrap();
-    assert_eq!(buf_block2.read().unwrap().pin_count(), 1);
-}
\ No newline at end of file
+    let buf_block3 = buf_mgr.pin(BlockId::new("testfile.txt", 3)).unwrap();
+    assert!(Arc::ptr_eq(&buffer2, &buf_block3.buffer()));
+    assert_eq!(buf_mgr.pin_count(&BlockId::new("testfile.txt", 3)), 1);
+}
+
+fn hit_ratio(directory: &str, strategy: ReplacementStrategy, num_buffers: u32, workload: &[u64]) -> f64 {
+    if std::path::Path::new(directory).exists() {
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+    let file_mgr_lock = Arc::new(Mutex::new(FileMgr::new(directory.to_string(), 400).unwrap()));
+    let log_mgr = LogMgr::new(file_mgr_lock.clone(), "replacementlog".to_string()).unwrap();
+    let buf_mgr = BufferMgr::new(file_mgr_lock, Arc::new(Mutex::new(log_mgr)), num_buffers, strategy);
+    for blk_num in workload {
+        buf_mgr.pin(BlockId::new("testfile", *blk_num)).unwrap();
+    }
+    std::fs::remove_dir_all(directory).unwrap();
+    buf_mgr.hits() as f64 / (buf_mgr.hits() + buf_mgr.misses()) as f64
+}
+
+/*
+a small hot set is used over and over again, between the uses a
+one-time scan passes over as many cold blocks as the pool can hold
+*/
+fn hot_set_with_scan() -> Vec<u64> {
+    let mut workload = Vec::new();
+    for round in 0..50 {
+        workload.extend([0, 1, 2, 0, 1, 2]);
+        workload.extend((0..4).map(|i| 100 + round * 4 + i));
+    }
+    workload
+}
+
+#[test]
+fn test_replacement_hot_set_with_scan() {
+    let workload = hot_set_with_scan();
+    let lru = hit_ratio("replacementtest_lru", ReplacementStrategy::Lru, 4, &workload);
+    let clock = hit_ratio("replacementtest_clock", ReplacementStrategy::Clock, 4, &workload);
+    let lru2 = hit_ratio("replacementtest_lru2", ReplacementStrategy::LruK(2), 4, &workload);
+    //the scan flushes the hot set out of lru and clock but not lru-2
+    assert!(lru2 > lru + 0.2);
+    assert!(lru2 > clock + 0.2);
+}
+
+#[test]
+fn test_replacement_hot_set_fits() {
+    let workload: Vec<u64> = (0..100).map(|i| i % 3).collect();
+    for (i, strategy) in [ReplacementStrategy::Lru, ReplacementStrategy::Clock,
+        ReplacementStrategy::LruK(2)].into_iter().enumerate() {
+        let ratio = hit_ratio(&format!("replacementtest_fits_{}", i), strategy, 4, &workload);
+        //only the first access of each block misses
+        assert_eq!(ratio, 0.97);
+    }
+
+    /*
+    naive policy always gives the first buffer to the new block, since
+    every block is unpinned at once, all blocks fight for buffer 0
+    */
+    let naive = hit_ratio("replacementtest_fits_naive", ReplacementStrategy::Naive, 4, &workload);
+    assert_eq!(naive, 0.0);
+}
+
+#[test]
+fn test_page_table_follows_replacement() {
+    let directory = "pagetabletest";
+    if std::path::Path::new(directory).exists() {
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+    let file_mgr_lock = Arc::new(Mutex::new(FileMgr::new(directory.to_string(), 400).unwrap()));
+    let log_mgr = LogMgr::new(file_mgr_lock.clone(), "pagetablelog".to_string()).unwrap();
+    let buf_mgr = BufferMgr::new(file_mgr_lock, Arc::new(Mutex::new(log_mgr)), 2, ReplacementStrategy::Lru);
+
+    let buf0 = buf_mgr.pin(BlockId::new("testfile", 0)).unwrap();
+    let buf1 = buf_mgr.pin(BlockId::new("testfile", 1)).unwrap();
+    //the same block is served by the same buffer
+    let buf0_again = buf_mgr.pin(BlockId::new("testfile", 0)).unwrap();
+    assert!(Arc::ptr_eq(&buf0.buffer(), &buf0_again.buffer()));
+    assert_eq!(buf_mgr.hits(), 1);
+    let buffer0 = buf0.buffer();
+    drop(buf0_again);
+    drop(buf0);
+
+    //block 2 takes the buffer of block 0
+    let buf2 = buf_mgr.pin(BlockId::new("testfile", 2)).unwrap();
+    assert!(Arc::ptr_eq(&buffer0, &buf2.buffer()));
+    assert_eq!(buf2.block(), BlockId::new("testfile", 2));
+    drop(buf1);
+    drop(buf2);
+
+    //block 0 is no longer in the pool and must be read again
+    let misses = buf_mgr.misses();
+    let buf0 = buf_mgr.pin(BlockId::new("testfile", 0)).unwrap();
+    assert_eq!(buf_mgr.misses(), misses + 1);
+    assert_eq!(buf0.block(), BlockId::new("testfile", 0));
+    //block 2 is still there
+    let hits = buf_mgr.hits();
+    let _buf2 = buf_mgr.pin(BlockId::new("testfile", 2)).unwrap();
+    assert_eq!(buf_mgr.hits(), hits + 1);
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_pin_with_timeout() {
+    let directory = "pintimeouttest";
+    if std::path::Path::new(directory).exists() {
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+    let file_mgr_lock = Arc::new(Mutex::new(FileMgr::new(directory.to_string(), 400).unwrap()));
+    let log_mgr = LogMgr::new(file_mgr_lock.clone(), "pintimeoutlog".to_string()).unwrap();
+    let buf_mgr = BufferMgr::new(file_mgr_lock, Arc::new(Mutex::new(log_mgr)), 1, ReplacementStrategy::Lru);
+
+    let buf0 = buf_mgr.pin(BlockId::new("testfile", 0)).unwrap();
+    let start = Instant::now();
+    let result = buf_mgr.pin_with_timeout(BlockId::new("testfile", 1), Duration::from_millis(200));
+    let waited = start.elapsed();
+    assert!(matches!(result, Err(DbError::BufferAbort)));
+    assert!(waited >= Duration::from_millis(200));
+    assert!(waited < Duration::from_secs(2));
+
+    //the block held by a pinned buffer can be pinned again without waiting
+    let buf0_again = buf_mgr.pin_with_timeout(BlockId::new("testfile", 0), Duration::from_millis(0)).unwrap();
+    assert_eq!(buf_mgr.pin_count(&BlockId::new("testfile", 0)), 2);
+    drop(buf0_again);
+    drop(buf0);
+
+    let buf1 = buf_mgr.pin_with_timeout(BlockId::new("testfile", 1), Duration::from_millis(200)).unwrap();
+    assert_eq!(buf1.block(), BlockId::new("testfile", 1));
+    drop(buf1);
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+fn new_buffer_mgr(directory: &str, num_buffers: u32) -> BufferMgr {
+    if std::path::Path::new(directory).exists() {
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+    let file_mgr_lock = Arc::new(Mutex::new(FileMgr::new(directory.to_string(), 400).unwrap()));
+    let log_mgr = LogMgr::new(file_mgr_lock.clone(), "buffermgrlog".to_string()).unwrap();
+    BufferMgr::new(file_mgr_lock, Arc::new(Mutex::new(log_mgr)), num_buffers, ReplacementStrategy::Lru)
+}
+
+#[test]
+fn test_buffer_mgr_is_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<BufferMgr>();
+}
+
+#[test]
+fn test_waiting_threads_served_in_order() {
+    let directory = "buffifotest";
+    let buf_mgr = new_buffer_mgr(directory, 1);
+    let order = Mutex::new(Vec::new());
+
+    let buf0 = buf_mgr.pin(BlockId::new("testfile", 0)).unwrap();
+    std::thread::scope(|s| {
+        let mut waiters = Vec::new();
+        for i in 1..=3u64 {
+            let buf_mgr = &buf_mgr;
+            let order = &order;
+            waiters.push(s.spawn(move || {
+                let buf = buf_mgr.pin(BlockId::new("testfile", i)).unwrap();
+                order.lock().unwrap().push(i);
+                std::thread::sleep(Duration::from_millis(50));
+                drop(buf);
+            }));
+            //make sure the threads queue up one after another
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        drop(buf0);
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+    });
+
+    assert_eq!(*order.lock().unwrap(), vec![1, 2, 3]);
+    assert_eq!(buf_mgr.available(), 1);
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_concurrent_pin_unpin_stress() {
+    let directory = "bufstresstest";
+    let buf_mgr = new_buffer_mgr(directory, 4);
+    let num_threads = 8;
+    let rounds = 200;
+    let num_blocks = 10;
+
+    std::thread::scope(|s| {
+        for t in 0..num_threads {
+            let buf_mgr = &buf_mgr;
+            s.spawn(move || {
+                for r in 0..rounds {
+                    let blk = BlockId::new("testfile", ((t * 7 + r) % num_blocks) as u64);
+                    let buf = buf_mgr.pin(blk.clone()).unwrap();
+                    {
+                        let mut buf_guard = buf.write().unwrap();
+                        assert_eq!(buf_guard.block(), blk);
+                        //count the pins on each block inside the block itself
+                        let mut page = buf_guard.contents();
+                        let count = page.get_int(0).unwrap();
+                        page.set_int(0, count + 1).unwrap();
+                        buf_guard.set_modified(1, None);
+                    }
+                }
+            });
+        }
+    });
+
+    assert_eq!(buf_mgr.available(), 4);
+    //every increment survives being written out and read back
+    let mut total = 0;
+    for blk_num in 0..num_blocks {
+        let buf = buf_mgr.pin(BlockId::new("testfile", blk_num as u64)).unwrap();
+        total += buf.write().unwrap().contents().get_int(0).unwrap();
+    }
+    assert_eq!(total, num_threads * rounds);
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_pinned_buffer_unpins_on_drop() {
+    let directory = "pinguardtest";
+    let buf_mgr = new_buffer_mgr(directory, 2);
+    {
+        let buf0 = buf_mgr.pin(BlockId::new("testfile", 0)).unwrap();
+        let _buf0_again = buf_mgr.pin(BlockId::new("testfile", 0)).unwrap();
+        assert_eq!(buf_mgr.pin_count(&buf0.block()), 2);
+        assert_eq!(buf_mgr.available(), 1);
+    }
+    assert_eq!(buf_mgr.available(), 2);
+
+    //an early return through ? gives the buffer back as well
+    let pin_and_fail = || -> DbResult<()> {
+        let buf = buf_mgr.pin(BlockId::new("testfile", 1))?;
+        buf.write().unwrap().contents().get_int(400)?;
+        Ok(())
+    };
+    assert!(matches!(pin_and_fail(), Err(DbError::PageOverflow { .. })));
+    assert_eq!(buf_mgr.available(), 2);
+
+    //the pin goes back to the buffer it was taken from, even if its block has left the page table
+    let buf = buf_mgr.pin(BlockId::new("testfile", 0)).unwrap();
+    buf_mgr.page_table.lock().unwrap().clear();
+    drop(buf);
+    assert_eq!(buf_mgr.available(), 2);
+
+    //unpinning does not lock the buffer, a guard taken through another pin may still be held
+    let bm = buf_mgr.clone();
+    let (tx, rx) = std::sync::mpsc::channel();
+    let handle = std::thread::spawn(move || {
+        let buf1 = bm.pin(BlockId::new("testfile", 1)).unwrap();
+        let buf1_again = bm.pin(BlockId::new("testfile", 1)).unwrap();
+        let mut guard = buf1.write().unwrap();
+        drop(buf1_again);
+        guard.contents().set_int(0, 1).unwrap();
+        tx.send(bm.pin_count(&BlockId::new("testfile", 1))).unwrap();
+    });
+    assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(1));
+    handle.join().unwrap();
+    assert_eq!(buf_mgr.available(), 2);
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_busy_victim_is_skipped() {
+    let directory = "busyvictimtest";
+    let buf_mgr = new_buffer_mgr(directory, 2);
+    let buf0 = buf_mgr.pin(BlockId::new("testfile", 0)).unwrap();
+    let buffer0 = buf0.buffer();
+    drop(buf0);
+
+    //buffer 0 is unpinned but still locked, block 1 and block 2 go to buffer 1 instead of waiting for it
+    let buf_guard = buffer0.read().unwrap();
+    let bm = buf_mgr.clone();
+    let (tx, rx) = std::sync::mpsc::channel();
+    let handle = std::thread::spawn(move || {
+        for blk_num in [1, 2] {
+            let buf = bm.pin(BlockId::new("testfile", blk_num)).unwrap();
+            tx.send(buf.block()).unwrap();
+        }
+    });
+    assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(BlockId::new("testfile", 1)));
+    assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(BlockId::new("testfile", 2)));
+    handle.join().unwrap();
+    assert_eq!(buf_guard.block(), BlockId::new("testfile", 0));
+    drop(buf_guard);
+    assert_eq!(buf_mgr.available(), 2);
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_available_recovers_after_panic() {
+    let directory = "pinpanictest";
+    let buf_mgr = new_buffer_mgr(directory, 2);
+
+    //panic with only the pin held
+    let result = std::thread::scope(|s| {
+        s.spawn(|| {
+            let _buf = buf_mgr.pin(BlockId::new("testfile", 0)).unwrap();
+            panic!("panic while the buffer is pinned");
+        })
+        .join()
+    });
+    assert!(result.is_err());
+    assert_eq!(buf_mgr.available(), 2);
+
+    //panic in the middle of writing the page poisons the buffer lock
+    let result = std::thread::scope(|s| {
+        s.spawn(|| {
+            let buf = buf_mgr.pin(BlockId::new("testfile", 1)).unwrap();
+            let mut buf_guard = buf.write().unwrap();
+            buf_guard.contents().set_int(0, 1).unwrap();
+            panic!("panic while the buffer is locked");
+        })
+        .join()
+    });
+    assert!(result.is_err());
+    assert_eq!(buf_mgr.available(), 2);
+
+    //every buffer can still be pinned and used
+    let buf0 = buf_mgr.pin(BlockId::new("testfile", 0)).unwrap();
+    let buf1 = buf_mgr.pin(BlockId::new("testfile", 1)).unwrap();
+    assert_eq!(buf1.write().unwrap().contents().get_int(0).unwrap(), 1);
+    assert_eq!(buf_mgr.available(), 0);
+    drop(buf0);
+    drop(buf1);
+    assert_eq!(buf_mgr.available(), 2);
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_failed_read_gives_back_pins() {
+    let directory = "pinreadfailtest";
+    if std::path::Path::new(directory).exists() {
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+    let file_mgr_lock = Arc::new(Mutex::new(FileMgr::with_checksums(directory.to_string(), 400).unwrap()));
+    let log_mgr = LogMgr::new(file_mgr_lock.clone(), "pinreadfaillog".to_string()).unwrap();
+    let buf_mgr = BufferMgr::new(file_mgr_lock, Arc::new(Mutex::new(log_mgr)), 3, ReplacementStrategy::Lru);
+    //bytes that don't match their checksum
+    std::fs::write(format!("{}/corruptfile", directory), vec![0xab; 412]).unwrap();
+    let blk = BlockId::new("corruptfile", 0);
+
+    assert!(matches!(buf_mgr.pin(blk.clone()), Err(DbError::CorruptPage { .. })));
+    assert_eq!(buf_mgr.available(), 3);
+
+    //threads finding the block while another one reads it never get the unread page
+    std::thread::scope(|s| {
+        for _ in 0..8 {
+            let buf_mgr = &buf_mgr;
+            let blk = blk.clone();
+            s.spawn(move || {
+                for _ in 0..50 {
+                    assert!(matches!(buf_mgr.pin(blk.clone()), Err(DbError::CorruptPage { .. })));
+                }
+            });
+        }
+    });
+    assert_eq!(buf_mgr.available(), 3);
+    let buffers: Vec<_> = (0..3)
+        .map(|i| buf_mgr.pin(BlockId::new("testfile", i)).unwrap())
+        .collect();
+    assert_eq!(buf_mgr.available(), 0);
+    drop(buffers);
+    assert_eq!(buf_mgr.available(), 3);
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_flush_stamps_page_lsn() {
+    let directory = "pagelsntest";
+    if std::path::Path::new(directory).exists() {
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+    let file_mgr_lock = Arc::new(Mutex::new(FileMgr::with_checksums(directory.to_string(), 400).unwrap()));
+    let log_mgr_lock = Arc::new(Mutex::new(LogMgr::new(file_mgr_lock.clone(), "pagelsnlog".to_string()).unwrap()));
+    let buf_mgr = BufferMgr::new(file_mgr_lock.clone(), log_mgr_lock.clone(), 1, ReplacementStrategy::Lru);
+    let blk = BlockId::new("testfile", 0);
+
+    let lsn = log_mgr_lock.lock().unwrap().append(&[1, 2, 3]).unwrap();
+    let buf = buf_mgr.pin(blk.clone()).unwrap();
+    {
+        let mut buf_guard = buf.write().unwrap();
+        buf_guard.contents().set_int(0, 99).unwrap();
+        buf_guard.set_modified(1, Some(lsn));
+    }
+    drop(buf);
+    buf_mgr.flush_all(1).unwrap();
+
+    let mut page_buf = vec![0u8; 400];
+    let mut page = Page::from_buffer(&mut page_buf);
+    let (_, page_lsn) = file_mgr_lock.lock().unwrap().read_with_lsn(&blk, &mut page).unwrap();
+    assert_eq!(page_lsn, lsn);
+    assert_eq!(page.get_int(0).unwrap(), 99);
+
+    //block 1 takes the only buffer, block 0 is read back from disk
+    drop(buf_mgr.pin(BlockId::new("testfile", 1)).unwrap());
+    //a later change without log record keeps the lsn of the page
+    let buf = buf_mgr.pin(blk.clone()).unwrap();
+    {
+        let mut buf_guard = buf.write().unwrap();
+        buf_guard.contents().set_int(0, 100).unwrap();
+        buf_guard.set_modified(1, None);
+    }
+    drop(buf);
+    buf_mgr.flush_all(1).unwrap();
+    let (_, page_lsn) = file_mgr_lock.lock().unwrap().read_with_lsn(&blk, &mut page).unwrap();
+    assert_eq!(page_lsn, lsn);
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_victim_written_back_outside_pool_lock() {
+    let directory = "evictionflushtest";
+    if std::path::Path::new(directory).exists() {
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+    let file_mgr_lock = Arc::new(Mutex::new(FileMgr::new(directory.to_string(), 400).unwrap()));
+    //every log flush waits for the group commit window
+    file_mgr_lock
+        .lock()
+        .unwrap()
+        .set_sync_policy(SyncPolicy::GroupCommit(Duration::from_millis(300)));
+    let log_mgr_lock = Arc::new(Mutex::new(LogMgr::new(file_mgr_lock.clone(), "evictionlog".to_string()).unwrap()));
+    let buf_mgr = BufferMgr::new(file_mgr_lock, log_mgr_lock.clone(), 2, ReplacementStrategy::Lru);
+
+    let buf0 = buf_mgr.pin(BlockId::new("testfile", 0)).unwrap();
+    let lsn = log_mgr_lock.lock().unwrap().append(&[1, 2, 3]).unwrap();
+    let buf1 = buf_mgr.pin(BlockId::new("testfile", 1)).unwrap();
+    {
+        let mut buf_guard = buf1.write().unwrap();
+        buf_guard.contents().set_int(0, 77).unwrap();
+        buf_guard.set_modified(1, Some(lsn));
+    }
+    drop(buf1);
+
+    std::thread::scope(|s| {
+        //block 2 takes the buffer of block 1, which is written back first
+        s.spawn(|| drop(buf_mgr.pin(BlockId::new("testfile", 2)).unwrap()));
+        std::thread::sleep(Duration::from_millis(50));
+
+        //a block in the pool is pinned while the write is going on
+        let start = Instant::now();
+        let buf0_again = buf_mgr.pin(BlockId::new("testfile", 0)).unwrap();
+        assert!(start.elapsed() < Duration::from_millis(150));
+        drop(buf0_again);
+
+        //the block being written waits for the write and reads the new version
+        let buf1 = buf_mgr.pin(BlockId::new("testfile", 1)).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert_eq!(buf1.write().unwrap().contents().get_int(0).unwrap(), 77);
+    });
+    drop(buf0);
+    assert_eq!(buf_mgr.available(), 2);
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_remove_file_drops_its_blocks() {
+    let directory = "removefiletest";
+    if std::path::Path::new(directory).exists() {
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+    let file_mgr_lock = Arc::new(Mutex::new(FileMgr::new(directory.to_string(), 400).unwrap()));
+    let log_mgr_lock = Arc::new(Mutex::new(LogMgr::new(file_mgr_lock.clone(), "removefilelog".to_string()).unwrap()));
+    let buf_mgr = BufferMgr::new(file_mgr_lock.clone(), log_mgr_lock, 2, ReplacementStrategy::Lru);
+    let blk = BlockId::new("1temp", 0);
+    let file_path = format!("{}/1temp", directory);
+
+    file_mgr_lock.lock().unwrap().append("1temp".to_string()).unwrap();
+    let buf = buf_mgr.pin(blk.clone()).unwrap();
+    {
+        let mut buf_guard = buf.write().unwrap();
+        buf_guard.contents().set_int(0, 7).unwrap();
+        buf_guard.set_modified(1, None);
+    }
+    //a pinned block is still in use
+    assert!(matches!(buf_mgr.remove_file("1temp"), Err(DbError::FileInUse(name)) if name == "1temp"));
+    assert!(std::path::Path::new(&file_path).exists());
+
+    drop(buf);
+    buf_mgr.remove_file("1temp").unwrap();
+    assert!(!std::path::Path::new(&file_path).exists());
+    assert_eq!(buf_mgr.pin_count(&blk), 0);
+    assert_eq!(buf_mgr.available(), 2);
+    //the modified block is not written back, the file stays removed
+    buf_mgr.flush_all(1).unwrap();
+    assert!(!std::path::Path::new(&file_path).exists());
+    //removing it again does nothing
+    buf_mgr.remove_file("1temp").unwrap();
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
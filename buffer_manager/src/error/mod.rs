@@ -0,0 +1,131 @@
+use std::fmt;
+use std::io;
+use std::string::FromUtf8Error;
+use crate::file_mgr::BlockId;
+
+/*
+crate wide error type, every manager returns DbError so that the caller
+can tell a disk failure from a corrupted block or an exhausted buffer pool
+and react to it instead of crashing on an unwrap
+*/
+#[derive(Debug)]
+pub enum DbError {
+    //underlying file system error, for example disk full or permission denied
+    Io(io::Error),
+    //read or write beyond the end of a page buffer
+    PageOverflow { offset: usize, size: usize, capacity: usize },
+    //the given file is not opened or does not exist
+    FileNotFound(String),
+    //the file can't be removed while a block of it is pinned
+    FileInUse(String),
+    //log record can't be decoded from the log block
+    CorruptLogRecord(String),
+    //no buffer became available before the waiting time ran out
+    BufferAbort,
+    //bytes saved in page are not valid utf8 string
+    Utf8(FromUtf8Error),
+    //transaction accesses a block without pinning it first
+    BlockNotPinned(BlockId),
+    //waiting for lock on the block too long, the transaction should roll back
+    LockAbort(BlockId),
+    /*
+    lock request on the block closes a cycle in the waits-for graph,
+    the cycle lists the transactions beginning with the aborted one
+    */
+    Deadlock { blk: BlockId, cycle: Vec<i32> },
+    //checksum in the page header does not match the block read from disk
+    CorruptPage { blk: BlockId, expected: u32, actual: u32 },
+    //the field is not in the schema of the record
+    FieldNotFound(String),
+    //string value is longer than the varchar field can hold
+    FieldTooLong { field_name: String, len: usize, max_len: usize },
+    //no room left in the block for the record
+    PageFull(BlockId),
+    //the slot is beyond the slot directory or the record in it is deleted
+    SlotNotFound { blk: BlockId, slot: usize },
+    //the table is not in the catalog
+    TableNotFound(String),
+    //a table or a view with the name is in the catalog
+    TableExists(String),
+    //catalog record can't be turned into metadata
+    CorruptCatalog(String),
+    //an index with the name, or on the same field, is in the catalog
+    IndexExists(String),
+    //value of the wrong type for the field
+    TypeMismatch(String),
+    //sql text does not follow the grammar, position is the offset of the offending token
+    BadSyntax { position: usize, message: String },
+    //index can't be built from the records, it is not empty or the records are not sorted
+    BulkLoad(String),
+    //min, max or avg of a group without records, there are no nulls to give
+    EmptyAggregation(String),
+}
+
+pub type DbResult<T> = Result<T, DbError>;
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::Io(e) => write!(f, "io error: {}", e),
+            DbError::PageOverflow { offset, size, capacity } => write!(
+                f,
+                "page overflow, offset:{}, size:{}, buffer cap:{}",
+                offset, size, capacity
+            ),
+            DbError::FileNotFound(file_name) => write!(f, "file with name:{} not found", file_name),
+            DbError::FileInUse(file_name) => write!(f, "file with name:{} has pinned blocks", file_name),
+            DbError::CorruptLogRecord(msg) => write!(f, "corrupt log record: {}", msg),
+            DbError::BufferAbort => write!(f, "waiting for buffer too long, buffer abort"),
+            DbError::Utf8(e) => write!(f, "invalid utf8 string: {}", e),
+            DbError::BlockNotPinned(blk) => write!(f, "block is not pinned, {}", blk),
+            DbError::LockAbort(blk) => write!(f, "waiting for lock too long, lock abort, {}", blk),
+            DbError::Deadlock { blk, cycle } => {
+                let txs: Vec<String> = cycle.iter().map(|tx| tx.to_string()).collect();
+                write!(f, "deadlock on {}, waits-for cycle: {} -> {}", blk, txs.join(" -> "), txs[0])
+            }
+            DbError::CorruptPage { blk, expected, actual } => write!(
+                f,
+                "corrupt page, {}, checksum expected:{:#010x}, actual:{:#010x}",
+                blk, expected, actual
+            ),
+            DbError::FieldNotFound(field_name) => write!(f, "field with name:{} not found", field_name),
+            DbError::FieldTooLong { field_name, len, max_len } => write!(
+                f,
+                "value of length:{} is too long for field:{}, max length:{}",
+                len, field_name, max_len
+            ),
+            DbError::PageFull(blk) => write!(f, "no room for the record, {}", blk),
+            DbError::SlotNotFound { blk, slot } => write!(f, "no record in slot:{}, {}", slot, blk),
+            DbError::TableNotFound(tbl_name) => write!(f, "table with name:{} not found", tbl_name),
+            DbError::TableExists(tbl_name) => write!(f, "table or view with name:{} already exists", tbl_name),
+            DbError::CorruptCatalog(msg) => write!(f, "corrupt catalog: {}", msg),
+            DbError::IndexExists(idx_name) => write!(f, "index with name:{} already exists", idx_name),
+            DbError::TypeMismatch(field_name) => write!(f, "value of wrong type for field:{}", field_name),
+            DbError::BadSyntax { position, message } => write!(f, "syntax error at position {}: {}", position, message),
+            DbError::BulkLoad(msg) => write!(f, "bulk load failed: {}", msg),
+            DbError::EmptyAggregation(agg) => write!(f, "aggregation:{} over no records has no value", agg),
+        }
+    }
+}
+
+impl std::error::Error for DbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DbError::Io(e) => Some(e),
+            DbError::Utf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DbError {
+    fn from(e: io::Error) -> Self {
+        DbError::Io(e)
+    }
+}
+
+impl From<FromUtf8Error> for DbError {
+    fn from(e: FromUtf8Error) -> Self {
+        DbError::Utf8(e)
+    }
+}
@@ -1,21 +1,27 @@
+#[cfg(test)]
 pub mod test;
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::io::{Cursor, Seek, SeekFrom, Write, Read};
 use std::io;
 use std::fs;
+use std::fmt;
 use std::collections::HashMap;
 use std::fs::File;
 use walkdir::WalkDir;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use std::path::Path;
 use std::fs::OpenOptions;
+use crate::error::{DbError, DbResult};
+use log::warn;
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct BlockId {
     //block taken from given binary file
-    file_name: String, 
+    file_name: String,
     //block number into the binary file
     blk_num: u64,
 }
@@ -35,15 +41,11 @@ impl BlockId {
     pub fn number(&self) -> u64 {
         self.blk_num
     }
-
-    pub fn to_string(&self) -> String {
-        format!("file: {}, block: {}", self.file_name, self.blk_num)
-    }
 }
 
-impl PartialEq for BlockId {
-    fn eq(&self, other: &Self) -> bool {
-        self.file_name == other.file_name && self.blk_num == other.blk_num
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "file: {}, block: {}", self.file_name, self.blk_num)
     }
 }
 
@@ -57,109 +59,112 @@ impl<'t> Page <'t>{
         Page { bb: buf }
     }
 
-    pub fn get_int(&mut self, offset: u64) -> Result<i32, String> {
+    fn overflow(&self, offset: usize, size: usize) -> DbError {
+        DbError::PageOverflow {
+            offset,
+            size,
+            capacity: self.bb.capacity(),
+        }
+    }
+
+    //end of size bytes from offset, an error when it passes limit or overflows
+    fn end_of(&self, offset: usize, size: usize, limit: usize) -> DbResult<usize> {
+        match offset.checked_add(size) {
+            Some(end) if end <= limit => Ok(end),
+            _ => Err(self.overflow(offset, size)),
+        }
+    }
+
+    pub fn get_int(&mut self, offset: u64) -> DbResult<i32> {
         /*
         offset + 4 is type of u64, self.bb.capacity() is type of uside,
-        using try_into() convert usize to u64
-        try_into is a trait, it will find the given object implement
-        the trait for converting to the given type
+        convert the offset to usize before comparing, an offset too
+        large for usize is out of the page anyway
         */
-        if offset + 4 > self.bb.capacity().try_into().unwrap() {
-            let err_msg = format!("get_int overflow, offset+4:{}, buffer cap:{}", offset+4, self.bb.capacity());
-            return Err(err_msg);
-        }
+        let offset = usize::try_from(offset).unwrap_or(usize::MAX);
+        self.end_of(offset, 4, self.bb.capacity())?;
         /*
         read 4 bytes as int value from given offset
-        there is only one mutable reference allowed, since we have a 
+        there is only one mutable reference allowed, since we have a
         mutable reference by self.bb, then if we allow Cursor to have
         a mutable reference to the same buffer, we need to release
         the mutable reference by bb that is why using *self.bb
         */
         let mut cursor = Cursor::new(&mut *self.bb);
-        /*
-        if the offset is outside the limit of buffer, throw an Error
-        */
-        cursor.seek(SeekFrom::Start(offset)).unwrap();
-        let val = cursor.read_i32::<BigEndian>().unwrap();
+        cursor.seek(SeekFrom::Start(offset as u64))?;
+        let val = cursor.read_i32::<BigEndian>()?;
         Ok(val)
     }
 
-    pub fn set_int(&mut self, offset: usize, n: i32) ->Result<(), String>{
+    pub fn set_int(&mut self, offset: usize, n: i32) -> DbResult<()> {
         //need to check buffer overflow
-        //change here
-        if offset + 4 > self.bb.capacity() {
-            let err_msg =format!("set_int buffer overflow offset+4:{}, buffer cap:{}", offset+4, self.bb.capacity()); 
-            return Err(err_msg);
-        }
+        self.end_of(offset, 4, self.bb.capacity())?;
 
-        let mut cursor = Cursor::new(&mut *self.bb); 
-        /*
-        if the offset is outside the limit of buffer, throw an Error
-        */
-        cursor.seek(SeekFrom::Start(offset as u64)).unwrap();
-        cursor.write_i32::<BigEndian>(n).unwrap();
+        let mut cursor = Cursor::new(&mut *self.bb);
+        cursor.seek(SeekFrom::Start(offset as u64))?;
+        cursor.write_i32::<BigEndian>(n)?;
 
         Ok(())
     }
 
-    pub fn get_bytes(&mut self, offset: usize) -> Result<Vec<u8>, String>{
+    pub fn get_bytes(&mut self, offset: usize) -> DbResult<Vec<u8>> {
         /*
         if offset is wrong, the result is unpredictable
          */
-        if offset >= self.bb.capacity() {
-            let err_msg = format!("get bytes overflow: offset:{}, buffer cap:{}", offset, self.bb.capacity());
-            return Err(err_msg);
-        }
+        let begin = self.end_of(offset, 4, self.bb.capacity())?;
 
         //the first 4 bytes from offset is the length for following bytes
-        let mut cursor = Cursor::new(&mut *self.bb); 
-        cursor.seek(SeekFrom::Start(offset as u64)).unwrap();
-        let bytes_len = cursor.read_u32::<BigEndian>().unwrap();
-        let begin = offset+4;
-
-        if begin >= self.bb.len() {
-            let err_msg = format!("get bytes with bytes buffer overflow, start pos:{}, buffer len:{}", begin, self.bb.len());
-            return Err(err_msg);
-        }
+        let mut cursor = Cursor::new(&mut *self.bb);
+        cursor.seek(SeekFrom::Start(offset as u64))?;
+        let bytes_len = cursor.read_u32::<BigEndian>()? as usize;
+        /*
+        the length prefix comes from the page itself, a corrupted page
+        may give a length that runs out of the buffer
+        */
+        let end = self.end_of(begin, bytes_len, self.bb.len())?;
 
-        let end = begin + (bytes_len as usize);
         Ok(self.bb[begin..end].to_vec())
     }
 
-    pub fn set_bytes(&mut self, offset: usize, bytes: &[u8]) ->Result<(), String> {
-        //change here
-        if offset + 4 + bytes.len() > self.bb.capacity() {
-            let err_msg = format!("set bytes overflow: offset+4+bytes.leng():{}, buffer cap:{}", offset + 4 + bytes.len(), self.bb.capacity());
-            return Err(err_msg);
-        }
-        let mut cursor = Cursor::new(&mut *self.bb); 
-        cursor.seek(SeekFrom::Start(offset as u64)).unwrap();
+    pub fn set_bytes(&mut self, offset: usize, bytes: &[u8]) -> DbResult<()> {
+        self.end_of(offset, 4 + bytes.len(), self.bb.capacity())?;
+        let mut cursor = Cursor::new(&mut *self.bb);
+        cursor.seek(SeekFrom::Start(offset as u64))?;
         //use 4 bytes to indicate the following bytes length
-        cursor.write_u32::<BigEndian>(bytes.len().try_into().unwrap()).unwrap();
-        cursor.write_all(bytes).unwrap();
+        cursor.write_u32::<BigEndian>(bytes.len() as u32)?;
+        cursor.write_all(bytes)?;
 
         Ok(())
     }
 
-    pub fn get_string(&mut self, offset: usize) -> Result<String,String> {
-        let str = String::from_utf8(self.get_bytes(offset).unwrap());
-        match str {
-            Ok(s) => return Ok(s),
-            Err(e) => return Err(e.to_string())
-        }
+    //bytes without the length prefix, the caller knows how many to read
+    pub fn get_raw_bytes(&mut self, offset: usize, len: usize) -> DbResult<Vec<u8>> {
+        let end = self.end_of(offset, len, self.bb.len())?;
+        Ok(self.bb[offset..end].to_vec())
+    }
+
+    pub fn set_raw_bytes(&mut self, offset: usize, bytes: &[u8]) -> DbResult<()> {
+        let end = self.end_of(offset, bytes.len(), self.bb.len())?;
+        self.bb[offset..end].copy_from_slice(bytes);
+        Ok(())
+    }
+
+    pub fn get_string(&mut self, offset: usize) -> DbResult<String> {
+        let s = String::from_utf8(self.get_bytes(offset)?)?;
+        Ok(s)
     }
 
-    pub fn set_string(&mut self, offset: usize, s: &String) -> Result<(),String> {
+    pub fn set_string(&mut self, offset: usize, s: &str) -> DbResult<()> {
         let bytes = s.as_bytes();
         self.set_bytes(offset, bytes)
     }
 
     pub fn max_length(str_len: u64) -> u64 {
         /*
-        buffer length for given string with str_len is 
+        buffer length for given string with str_len is
         4 bytes plus the bytes for the string
         */
-        return 4 + str_len;
+        4 + str_len
     }
 
     pub fn contents(&mut self) -> &mut Vec<u8> {
@@ -167,6 +172,34 @@ impl<'t> Page <'t>{
     }
 }
 
+/*
+optional header saved in front of every block on disk, the header is not
+part of the page, a page still has block_size bytes for its owner.
+    checksum: 4 bytes, crc32c over the page lsn and the page bytes
+    page lsn: 8 bytes, lsn of the latest log record changing the page
+a block that is appended but never written is all zeros, it is treated
+as an empty page with lsn 0
+*/
+pub const PAGE_HEADER_SIZE: u64 = 12;
+
+/*
+when writes are forced from the os cache onto the disk, data pages of a
+transaction and the log up to its COMMIT record are synced before commit
+returns, unless the policy is Never
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncPolicy {
+    //every log flush syncs the log file at once
+    Always,
+    /*
+    log flushes arriving within the window share one sync, a commit waits
+    at most the window longer but many commits pay for a single sync
+    */
+    GroupCommit(Duration),
+    //leave it to the os, a power loss may lose committed transactions
+    Never,
+}
+
 pub struct FileMgr {
     //prepare for concurrent accessing low level binary files
     open_files: Arc<RwLock<HashMap<String, RwLock<File> >>>,
@@ -178,9 +211,15 @@ pub struct FileMgr {
     is_new:  bool,
 
     block_size: u64,
+    //every block on disk begins with a page header
+    checksums: bool,
+    sync_policy: SyncPolicy,
+    //number of syncs done, tells how many commits share one sync
+    syncs: AtomicU64,
 }
 
-fn delete_temp_files(directory: &str) -> io::Result<()> {
+//a temp file left behind is only wasted space, failing to delete it does not stop opening the db
+fn delete_temp_files(directory: &str) {
     for entry in WalkDir::new(directory).into_iter().filter_map(|e| e.ok()) {
         let path = entry.path();
 
@@ -190,117 +229,255 @@ fn delete_temp_files(directory: &str) -> io::Result<()> {
                 if let Some(file_name_str) = file_name.to_str() {
                     if file_name_str.ends_with("temp") {
                         // Delete the file
-                        fs::remove_file(&path)?;
-                        println!("Deleted: {:?}", path);
+                        if let Err(err) = fs::remove_file(path) {
+                            warn!("delete temp file: {:?}, with err: {}", path, err);
+                        }
                     }
                 }
             }
         }
     }
-
-    Ok(())
 }
 
 impl FileMgr {
-    pub fn new(db_directory: String, block_size: u64) -> Self{
-        //check given directory exist or not 
+    pub fn new(db_directory: String, block_size: u64) -> DbResult<Self> {
+        //check given directory exist or not
         let is_new = !(Path::new(db_directory.as_str()).exists() && Path::new(db_directory.as_str()).is_dir());
-        
+
         //dierctory not exist then create it
         if is_new {
-            fs::create_dir_all(db_directory.clone()).unwrap();
+            fs::create_dir_all(db_directory.clone())?;
         } else {
             //delete temp files
-            let _ = delete_temp_files(&db_directory);
+            delete_temp_files(&db_directory);
         }
 
-        FileMgr{
+        Ok(FileMgr{
             open_files: Arc::new(RwLock::new(HashMap::new())),
             directory: db_directory,
             is_new,
             block_size,
+            checksums: false,
+            sync_policy: SyncPolicy::Always,
+            syncs: AtomicU64::new(0),
+        })
+    }
+
+    /*
+    every block is saved with a page header, a torn write or a flipped bit
+    is reported as CorruptPage on reading. The same directory must always
+    be opened with the same setting since the header changes the position
+    of blocks in the file
+    */
+    pub fn with_checksums(db_directory: String, block_size: u64) -> DbResult<Self> {
+        let mut fm = FileMgr::new(db_directory, block_size)?;
+        fm.checksums = true;
+        Ok(fm)
+    }
+
+    pub fn checksums(&self) -> bool {
+        self.checksums
+    }
+
+    pub fn sync_policy(&self) -> SyncPolicy {
+        self.sync_policy
+    }
+
+    pub fn set_sync_policy(&mut self, sync_policy: SyncPolicy) {
+        self.sync_policy = sync_policy;
+    }
+
+    //force the written blocks of the file from the os cache onto the disk
+    pub fn sync(&self, file_name: &str) -> DbResult<()> {
+        self.add_file(file_name.to_string())?;
+        let map_guard = self.open_files.read().unwrap();
+        if let Some(file_lock) = map_guard.get(file_name) {
+            file_lock.read().unwrap().sync_data()?;
+            self.syncs.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        } else {
+            Err(DbError::FileNotFound(file_name.to_string()))
+        }
+    }
+
+    pub fn syncs(&self) -> u64 {
+        self.syncs.load(Ordering::SeqCst)
+    }
+
+    //bytes taken by one block in the file
+    fn disk_block_size(&self) -> u64 {
+        if self.checksums {
+            self.block_size + PAGE_HEADER_SIZE
+        } else {
+            self.block_size
         }
     }
 
-    pub fn read_write(&mut self ,blk: &BlockId, p: &mut Page, is_write: bool) -> Result<usize, String> {
+    //checksum covering the lsn, then a changed lsn is detected as well
+    fn page_checksum(lsn: u64, contents: &[u8]) -> u32 {
+        crc32c::crc32c_append(crc32c::crc32c(&lsn.to_be_bytes()), contents)
+    }
+
+    //write the page and stamp the given lsn in its header
+    pub fn write_with_lsn(&mut self, blk: &BlockId, p: &mut Page, lsn: u64) -> DbResult<usize> {
+        if !self.checksums {
+            return self.read_write(blk, p, true);
+        }
+        let contents = p.contents();
+        let mut bytes = Vec::with_capacity(self.disk_block_size() as usize);
+        bytes.write_u32::<BigEndian>(Self::page_checksum(lsn, contents))?;
+        bytes.write_u64::<BigEndian>(lsn)?;
+        bytes.extend_from_slice(contents);
+        self.access_block(blk, true, |file| {
+            file.write_all(&bytes)?;
+            Ok(contents.len())
+        })
+    }
+
+    /*
+    read the page and return the number of bytes read together with the
+    lsn in the page header, the lsn is 0 without checksums
+    */
+    pub fn read_with_lsn(&mut self, blk: &BlockId, p: &mut Page) -> DbResult<(usize, u64)> {
+        if !self.checksums {
+            return Ok((self.read_write(blk, p, false)?, 0));
+        }
+        let mut bytes = vec![0u8; self.disk_block_size() as usize];
+        self.access_block(blk, false, |file| Ok(file.read_exact(&mut bytes)?))?;
+
+        let mut cursor = Cursor::new(&bytes);
+        let expected = cursor.read_u32::<BigEndian>()?;
+        let lsn = cursor.read_u64::<BigEndian>()?;
+        let data = &bytes[PAGE_HEADER_SIZE as usize..];
+        let actual = Self::page_checksum(lsn, data);
+        let never_written = expected == 0 && lsn == 0 && data.iter().all(|b| *b == 0);
+        if actual != expected && !never_written {
+            return Err(DbError::CorruptPage {
+                blk: blk.clone(),
+                expected,
+                actual,
+            });
+        }
+
+        let contents = p.contents();
+        let len = contents.len().min(data.len());
+        contents[..len].copy_from_slice(&data[..len]);
+        Ok((len, lsn))
+    }
+
+    pub fn read_write(&mut self ,blk: &BlockId, p: &mut Page, is_write: bool) -> DbResult<usize> {
+        if self.checksums {
+            //pages written without a lsn are stamped with lsn 0
+            return if is_write {
+                self.write_with_lsn(blk, p, 0)
+            } else {
+                Ok(self.read_with_lsn(blk, p)?.0)
+            };
+        }
+        self.access_block(blk, is_write, |file| {
+            if !is_write {
+                let bytes_read = file.read( p.contents())?;
+                Ok(bytes_read)
+            } else {
+                let bytes_write = file.write(p.contents())?;
+                Ok(bytes_write)
+            }
+        })
+    }
+
+    //seek to the beginning of the block and hand the file to the given io
+    fn access_block<T>(&mut self, blk: &BlockId, is_write: bool,
+        io: impl FnOnce(&mut File) -> DbResult<T>) -> DbResult<T> {
         /*
         read given binary file from given offset, file name and offset
         can get from BlockId, take cocurrent read into concerns
         */
         //check file exist or not, if not should call add_file to create file
-        self.add_file(blk.file_name()).unwrap();
+        self.add_file(blk.file_name())?;
         if is_write {
               /*
-                if the write position is beyond the length of the file, then we 
+                if the write position is beyond the length of the file, then we
                 extend the file to the given block
                 */
-            self.extend(blk);
+            self.extend(blk)?;
         }
 
         let map_guard = self.open_files.read().unwrap();
         let file_name = blk.file_name();
         if let Some(file_lock) = map_guard.get(&file_name) {
             let mut file_guard = file_lock.write().unwrap();
-            let meta_data = file_guard.metadata().unwrap();
-            let offset = blk.number() * self.block_size;
-           
+            let meta_data = file_guard.metadata()?;
+            let offset = blk.number() * self.disk_block_size();
+
             if offset >= meta_data.len() {
-                return Err(format!("offset out bound of given file:{}", file_name));
+                return Err(DbError::PageOverflow {
+                    offset: offset as usize,
+                    size: self.disk_block_size() as usize,
+                    capacity: meta_data.len() as usize,
+                });
             }
-            
-            file_guard.seek(SeekFrom::Start(offset)).unwrap();
 
-            if !is_write {
-                let bytes_read = file_guard.read( p.contents()).unwrap();
-                Ok(bytes_read)
-            } else {
-                let bytes_write = file_guard.write(p.contents()).unwrap();
-                Ok(bytes_write)
-            }
-            
+            file_guard.seek(SeekFrom::Start(offset))?;
+            io(&mut file_guard)
         } else {
-            Err(format!("file with name:{} not found", file_name))
+            Err(DbError::FileNotFound(file_name))
         }
     }
 
-    fn extend(&mut self, blk :&BlockId) {
-           while self.length(blk.file_name()).unwrap() <= blk.number() {
-                let _ = self.append(blk.file_name());
+    fn extend(&mut self, blk :&BlockId) -> DbResult<()> {
+           while self.length(blk.file_name())? <= blk.number() {
+                self.append(blk.file_name())?;
            }
-       } 
+           Ok(())
+       }
 
-   pub fn append(&mut self, file_name: String) ->Result<BlockId, String> {
+   pub fn append(&mut self, file_name: String) -> DbResult<BlockId> {
+      self.add_file(file_name.clone())?;
       let map_guard = self.open_files.read().unwrap();
       if let Some(file_lock) = map_guard.get(&file_name) {
-          let new_blk_num = self.length(file_name.clone()).unwrap();
+          let new_blk_num = self.length(file_name.clone())?;
           //enlarge the file with block size at the end
-          let file_guard = file_lock.write().unwrap();
-          let meta_data = file_guard.metadata().unwrap();
-          let new_size = meta_data.len() + self.block_size;
-          file_guard.set_len(new_size).unwrap();
+          let file_guard = file_lock.read().unwrap();
+          let meta_data = file_guard.metadata()?;
+          let new_size = meta_data.len() + self.disk_block_size();
+          file_guard.set_len(new_size)?;
           Ok(BlockId::new(file_name.as_str(), new_blk_num))
       } else {
-          return Err(format!("file with name: {} not found", file_name));
+          Err(DbError::FileNotFound(file_name))
       }
    }
 
-   pub fn length(&self, file_name: String) -> Result<u64, String> {
+   pub fn length(&self, file_name: String) -> DbResult<u64> {
+        //a file that is not created yet has no blocks
+        self.add_file(file_name.clone())?;
         let map_guard = self.open_files.read().unwrap();
         if let Some(file_lock) = map_guard.get(&file_name) {
             //compute how many blocks in the file
             let file_guard = file_lock.read().unwrap();
-            let meta_data = file_guard.metadata().unwrap();
-            Ok(meta_data.len() / self.block_size)
+            let meta_data = file_guard.metadata()?;
+            Ok(meta_data.len() / self.disk_block_size())
         } else {
-            Err(format!("file : {} not found", file_name))
+            Err(DbError::FileNotFound(file_name))
         }
    }
 
+    /*
+    close the file and delete it from the directory, the caller makes
+    sure no buffer holds a block of it, a missing file is already removed
+    */
+    pub fn remove_file(&mut self, file_name: &str) -> DbResult<()> {
+        self.open_files.write().unwrap().remove(file_name);
+        match fs::remove_file(format!("{}/{}", self.directory, file_name)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
     pub fn is_new(&self) -> bool {
         self.is_new
     }
 
-    fn add_file(&mut self,file_name: String) -> io::Result<()> {
+    fn add_file(&self,file_name: String) -> io::Result<()> {
         /*
         check file opened or not, using scope here to make sure the guard
         */
@@ -312,11 +489,12 @@ impl FileMgr {
         drop(map_guard);
 
         let file_path = format!("{}/{}", self.directory, file_name);
-        //open file for read and write
+        //open file for read and write, existing content must be kept
         let  file = OpenOptions::new()
         .read(true)  // Allow reading
         .write(true) // Allow writing
         .create(true) // Create the file if it doesn't exist
+        .truncate(false)
         .open(file_path)?;
 
         let file_lock = RwLock::new(file);
@@ -329,4 +507,4 @@ impl FileMgr {
     pub fn block_size(&self) ->u64 {
         self.block_size
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,5 @@
-use super::{BlockId, FileMgr, Page};
+use super::{BlockId, FileMgr, Page, PAGE_HEADER_SIZE};
+use crate::error::DbError;
 
 #[test]
 fn test_block_id_funcs() {
@@ -25,7 +26,7 @@ fn test_page_int() {
    let mut buf = Vec::<u8>::with_capacity(8);
    let mut page = Page::from_buffer(&mut buf);
    let result = page.set_int(2, 1234);
-   assert_eq!(result, Ok(()));
+   assert!(result.is_ok());
    let res = page.get_int(2).unwrap();
    assert_eq!(res, 1234);
 }
@@ -35,7 +36,7 @@ fn test_page_int_buf_overflow() {
     let mut buf = Vec::<u8>::with_capacity(3);
     let mut page = Page::from_buffer(&mut buf);
     let result = page.set_int(0, 0x0fffff);
-    assert_eq!(result.is_err(), true);
+    assert!(result.is_err());
 }
 
 #[test]
@@ -44,7 +45,7 @@ fn test_page_bytes() {
     let mut page = Page::from_buffer(&mut buf);
     let bytes = vec![0x01, 0x02, 0x03, 0x04];
     let res = page.set_bytes(3, bytes.as_slice());
-    assert_eq!(res, Ok(()));
+    assert!(res.is_ok());
     assert_eq!(page.get_bytes(3).unwrap(), bytes);
 }
 
@@ -54,7 +55,7 @@ fn test_page_bytes_overflow() {
     let mut page = Page::from_buffer(&mut buf);
     let bytes = vec![0x01, 0x02, 0x03, 0x04];
     let res = page.set_bytes(3, bytes.as_slice());
-    assert_eq!(res.is_err(), true);
+    assert!(res.is_err());
 }
 
 #[test]
@@ -63,7 +64,7 @@ fn test_page_string() {
     let mut page = Page::from_buffer(&mut buf);
     let str = String::from("hello, world!");
     let res = page.set_string(3, &str);
-    assert_eq!(res, Ok(()));
+    assert!(res.is_ok());
     assert_eq!(page.get_string(3).unwrap(), str);
 }
 
@@ -73,13 +74,13 @@ fn test_page_string_overflow() {
     let mut page = Page::from_buffer(&mut buf);
     let str = String::from("hello, world!");
     let res = page.set_string(3, &str);
-    assert_eq!(res.is_err(), true);
+    assert!(res.is_err());
 }
 
 #[test]
 fn test_file_manage() {
-    let mut file_mgr = FileMgr::new("filetest".to_string(), 
-    512);
+    let mut file_mgr = FileMgr::new("filetest".to_string(),
+    512).unwrap();
     //read from offset of 512 * 2
     let blk = BlockId::new("testfile", 2);
     let mut buf = vec![0u8; file_mgr.block_size() as usize];
@@ -90,19 +91,19 @@ fn test_file_manage() {
     the block, which means we write to offset from the file 512 * 2 + 88
      */
     let hello = String::from("hello, world!");
-    p1.set_string(pos1, &hello);
+    p1.set_string(pos1, &hello).unwrap();
 
     // //len of string plus 4 bytes, 4 bytes to indicate the length of the string
     let pos2 = Page::max_length(hello.len() as u64) + pos1 as u64;
-    p1.set_int(pos2 as usize, 345);
+    p1.set_int(pos2 as usize, 345).unwrap();
     /*
     blk indicates wrtie to which file and from what offset
     */
-    file_mgr.read_write(&blk, &mut p1, true);
+    file_mgr.read_write(&blk, &mut p1, true).unwrap();
 
     let mut buf1 = vec![0u8; file_mgr.block_size() as usize];
     let mut p2 = Page::from_buffer(&mut buf1);
-    file_mgr.read_write(&blk, &mut p2, false);
+    file_mgr.read_write(&blk, &mut p2, false).unwrap();
     p2.get_int(pos2).unwrap();
     let int_val = p2.get_int(pos2).unwrap();
     assert_eq!(int_val, 345);
@@ -110,4 +111,143 @@ fn test_file_manage() {
     let str_val = p2.get_string(pos1).unwrap();
     assert_eq!("hello, world!", str_val);
 
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_page_error_kinds() {
+    let mut buf = vec![0u8; 8];
+    let mut page = Page::from_buffer(&mut buf);
+    match page.get_int(6) {
+        Err(DbError::PageOverflow { offset, size, capacity }) => {
+            assert_eq!(offset, 6);
+            assert_eq!(size, 4);
+            assert_eq!(capacity, 8);
+        },
+        other => panic!("expect page overflow, got {:?}", other),
+    }
+
+    //length prefix says 100 bytes but the page only has 8
+    page.set_int(0, 100).unwrap();
+    assert!(matches!(page.get_bytes(0), Err(DbError::PageOverflow { .. })));
+
+    page.set_bytes(0, &[0xff, 0xfe]).unwrap();
+    assert!(matches!(page.get_string(0), Err(DbError::Utf8(_))));
+
+    //offsets near the top of the range must not wrap around the bounds check
+    assert!(matches!(page.get_int(u64::MAX), Err(DbError::PageOverflow { .. })));
+    assert!(matches!(page.set_int(usize::MAX - 1, 1), Err(DbError::PageOverflow { .. })));
+    assert!(matches!(page.get_bytes(usize::MAX), Err(DbError::PageOverflow { .. })));
+    assert!(matches!(page.set_bytes(usize::MAX - 2, &[1]), Err(DbError::PageOverflow { .. })));
+    assert!(matches!(page.get_raw_bytes(usize::MAX, 2), Err(DbError::PageOverflow { .. })));
+    assert!(matches!(page.set_raw_bytes(usize::MAX, &[1]), Err(DbError::PageOverflow { .. })));
+}
+
+fn new_checksum_file_mgr(directory: &str) -> FileMgr {
+    if std::path::Path::new(directory).exists() {
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+    FileMgr::with_checksums(directory.to_string(), 400).unwrap()
+}
+
+#[test]
+fn test_checksum_page_round_trip() {
+    let directory = "checksumtest";
+    let mut file_mgr = new_checksum_file_mgr(directory);
+    let blk = BlockId::new("testfile", 1);
+
+    let mut buf = vec![0u8; 400];
+    let mut page = Page::from_buffer(&mut buf);
+    page.set_string(20, "checked").unwrap();
+    file_mgr.write_with_lsn(&blk, &mut page, 42).unwrap();
+    //header is kept outside of the page
+    assert_eq!(file_mgr.length("testfile".to_string()).unwrap(), 2);
+    let file_len = std::fs::metadata(format!("{}/testfile", directory)).unwrap().len();
+    assert_eq!(file_len, 2 * (400 + PAGE_HEADER_SIZE));
+
+    let mut buf = vec![0u8; 400];
+    let mut page = Page::from_buffer(&mut buf);
+    let (bytes_read, lsn) = file_mgr.read_with_lsn(&blk, &mut page).unwrap();
+    assert_eq!(bytes_read, 400);
+    assert_eq!(lsn, 42);
+    assert_eq!(page.get_string(20).unwrap(), "checked");
+
+    //block 0 is created by extending the file but never written
+    let mut buf = vec![1u8; 400];
+    let mut page = Page::from_buffer(&mut buf);
+    let (_, lsn) = file_mgr.read_with_lsn(&BlockId::new("testfile", 0), &mut page).unwrap();
+    assert_eq!(lsn, 0);
+    assert!(page.contents().iter().all(|b| *b == 0));
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_checksum_detects_corruption() {
+    let directory = "corruptpagetest";
+    let mut file_mgr = new_checksum_file_mgr(directory);
+    let blk = BlockId::new("testfile", 0);
+    let file_path = format!("{}/testfile", directory);
+
+    let mut buf = vec![0u8; 400];
+    let mut page = Page::from_buffer(&mut buf);
+    page.set_int(100, 7).unwrap();
+    file_mgr.write_with_lsn(&blk, &mut page, 3).unwrap();
+
+    //flip one bit of the page on disk
+    let mut bytes = std::fs::read(&file_path).unwrap();
+    bytes[PAGE_HEADER_SIZE as usize + 103] ^= 0x01;
+    std::fs::write(&file_path, &bytes).unwrap();
+    //reopen the directory, the old file handle may not see the change
+    let mut file_mgr = FileMgr::with_checksums(directory.to_string(), 400).unwrap();
+    let mut buf = vec![0u8; 400];
+    let mut page = Page::from_buffer(&mut buf);
+    match file_mgr.read_with_lsn(&blk, &mut page) {
+        Err(DbError::CorruptPage { blk: corrupt_blk, expected, actual }) => {
+            assert_eq!(corrupt_blk, blk);
+            assert_ne!(expected, actual);
+        }
+        other => panic!("expect corrupt page, got {:?}", other),
+    }
+
+    /*
+    torn write, the new header and the first half of the new page reach
+    the disk but the second half still holds the old bytes
+    */
+    let mut buf = vec![0u8; 400];
+    let mut page = Page::from_buffer(&mut buf);
+    page.set_int(100, 7).unwrap();
+    page.set_int(300, 7).unwrap();
+    file_mgr.write_with_lsn(&blk, &mut page, 3).unwrap();
+    let old_bytes = std::fs::read(&file_path).unwrap();
+    page.set_int(100, 8).unwrap();
+    page.set_int(300, 8).unwrap();
+    file_mgr.write_with_lsn(&blk, &mut page, 4).unwrap();
+    let mut bytes = std::fs::read(&file_path).unwrap();
+    let half = PAGE_HEADER_SIZE as usize + 200;
+    bytes[half..].copy_from_slice(&old_bytes[half..]);
+    std::fs::write(&file_path, &bytes).unwrap();
+
+    let mut file_mgr = FileMgr::with_checksums(directory.to_string(), 400).unwrap();
+    let result = file_mgr.read_write(&blk, &mut page, false);
+    assert!(matches!(result, Err(DbError::CorruptPage { .. })));
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_remove_file() {
+    let directory = "removetest";
+    if std::path::Path::new(directory).exists() {
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+    let mut file_mgr = FileMgr::new(directory.to_string(), 400).unwrap();
+    file_mgr.append("1temp".to_string()).unwrap();
+    assert!(file_mgr.open_files.read().unwrap().contains_key("1temp"));
+
+    file_mgr.remove_file("1temp").unwrap();
+    assert!(!file_mgr.open_files.read().unwrap().contains_key("1temp"));
+    assert!(!std::path::Path::new(&format!("{}/1temp", directory)).exists());
+    file_mgr.remove_file("1temp").unwrap();
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,110 @@
+use super::btree_page::BTPage;
+use crate::error::DbResult;
+use crate::file_mgr::BlockId;
+use crate::query::Constant;
+use crate::record_mgr::Layout;
+use crate::tx_mgr::Transaction;
+
+use std::sync::{Arc, Mutex};
+
+//directory record, the first value in the child block and its block number
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirEntry {
+    pub data_val: Constant,
+    pub blk_num: u64,
+}
+
+/*
+directory block of a b-tree, the record with value v points to the child
+holding the values from v up to the value of the next record. The root
+is block 0 of the directory file and never moves
+*/
+pub struct BTreeDir {
+    tx: Arc<Mutex<Transaction>>,
+    layout: Arc<Layout>,
+    contents: BTPage,
+    file_name: String,
+}
+
+impl BTreeDir {
+    pub fn new(tx: Arc<Mutex<Transaction>>, blk: BlockId, layout: Arc<Layout>) -> DbResult<Self> {
+        let file_name = blk.file_name();
+        let contents = BTPage::new(tx.clone(), blk, layout.clone())?;
+        Ok(BTreeDir {
+            tx,
+            layout,
+            contents,
+            file_name,
+        })
+    }
+
+    //block number of the leaf where the key belongs
+    pub fn search(&mut self, search_key: &Constant) -> DbResult<u64> {
+        let mut child_blk = self.find_child_block(search_key)?;
+        while self.contents.flag()? > 0 {
+            self.contents = BTPage::new(self.tx.clone(), child_blk, self.layout.clone())?;
+            child_blk = self.find_child_block(search_key)?;
+        }
+        Ok(child_blk.number())
+    }
+
+    /*
+    the root is split, its records move into a new block, then the root
+    points to that block and the one given by the entry, one level higher
+    */
+    pub fn make_new_root(&self, e: DirEntry) -> DbResult<()> {
+        let first_val = self.contents.get_data_val(0)?;
+        let level = self.contents.flag()?;
+        let new_blk = self.contents.split(0, level)?;
+        let old_root = DirEntry {
+            data_val: first_val,
+            blk_num: new_blk.number(),
+        };
+        self.insert_entry(&old_root)?;
+        self.insert_entry(&e)?;
+        self.contents.set_flag(level + 1)
+    }
+
+    /*
+    insert the entry of a new leaf into the directory below this block,
+    return the entry of a new block of this level if this one is split
+    */
+    pub fn insert(&self, e: &DirEntry) -> DbResult<Option<DirEntry>> {
+        if self.contents.flag()? == 0 {
+            return self.insert_entry(e);
+        }
+        let child_blk = self.find_child_block(&e.data_val)?;
+        let child = BTreeDir::new(self.tx.clone(), child_blk, self.layout.clone())?;
+        match child.insert(e)? {
+            Some(my_entry) => self.insert_entry(&my_entry),
+            None => Ok(None),
+        }
+    }
+
+    fn insert_entry(&self, e: &DirEntry) -> DbResult<Option<DirEntry>> {
+        let new_slot = self.contents.find_slot_before(&e.data_val)?.map_or(0, |slot| slot + 1);
+        self.contents.insert_dir(new_slot, &e.data_val, e.blk_num)?;
+        if !self.contents.is_full()? {
+            return Ok(None);
+        }
+        let level = self.contents.flag()?;
+        let split_pos = self.contents.num_recs()? / 2;
+        let split_val = self.contents.get_data_val(split_pos)?;
+        let new_blk = self.contents.split(split_pos, level)?;
+        Ok(Some(DirEntry {
+            data_val: split_val,
+            blk_num: new_blk.number(),
+        }))
+    }
+
+    fn find_child_block(&self, search_key: &Constant) -> DbResult<BlockId> {
+        let mut slot = self.contents.find_slot_before(search_key)?;
+        let next = slot.map_or(0, |slot| slot + 1);
+        if next < self.contents.num_recs()? && self.contents.get_data_val(next)? == *search_key {
+            slot = Some(next);
+        }
+        //the first record of the root holds the smallest value, so only a smaller key gets None
+        let blk_num = self.contents.get_child_num(slot.unwrap_or(0))?;
+        Ok(BlockId::new(&self.file_name, blk_num))
+    }
+}
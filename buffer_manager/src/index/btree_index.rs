@@ -0,0 +1,262 @@
+use super::btree_dir::{BTreeDir, DirEntry};
+use super::btree_leaf::BTreeLeaf;
+use super::btree_page::{BTPage, NO_BLOCK};
+use super::Index;
+use crate::error::{DbError, DbResult};
+use crate::file_mgr::BlockId;
+use crate::query::Constant;
+use crate::record_mgr::{FieldType, Layout, RecordId, Schema};
+use crate::tx_mgr::Transaction;
+
+use std::ops::Bound;
+use std::sync::{Arc, Mutex};
+
+/*
+b-tree index, the leaf records are kept in <index name>leaf and the
+directory in <index name>dir. Leaf block 0 is the leftmost leaf and
+directory block 0 is the root, it begins with the smallest value of the
+field type pointing to leaf 0, so every key finds a leaf. Besides the
+search for one value, the leaves can be walked for a range of values
+*/
+pub struct BTreeIndex {
+    tx: Arc<Mutex<Transaction>>,
+    dir_layout: Arc<Layout>,
+    leaf_layout: Arc<Layout>,
+    leaf_tbl: String,
+    root_blk: BlockId,
+    leaf: Option<BTreeLeaf>,
+}
+
+impl BTreeIndex {
+    pub fn new(tx: Arc<Mutex<Transaction>>, idx_name: &str, leaf_layout: Arc<Layout>) -> DbResult<Self> {
+        let leaf_tbl = format!("{}leaf", idx_name);
+        if tx.lock().unwrap().size(&leaf_tbl)? == 0 {
+            BTPage::append_new(tx.clone(), &leaf_tbl, leaf_layout.clone(), NO_BLOCK)?;
+        }
+
+        let leaf_schema = leaf_layout.schema();
+        let mut dir_schema = Schema::new();
+        dir_schema.add_int_field("block");
+        let min_val = match leaf_schema.field_type("dataval") {
+            Some(FieldType::Varchar) => {
+                dir_schema.add_string_field("dataval", leaf_schema.length("dataval").unwrap_or(0));
+                Constant::Str(String::new())
+            }
+            _ => {
+                dir_schema.add_int_field("dataval");
+                Constant::Int(i32::MIN)
+            }
+        };
+        let dir_layout = Arc::new(Layout::new(dir_schema));
+
+        let dir_tbl = format!("{}dir", idx_name);
+        if tx.lock().unwrap().size(&dir_tbl)? == 0 {
+            let root = BTPage::append_new(tx.clone(), &dir_tbl, dir_layout.clone(), 0)?;
+            root.insert_dir(0, &min_val, 0)?;
+        }
+        Ok(BTreeIndex {
+            tx,
+            dir_layout,
+            leaf_layout,
+            leaf_tbl,
+            root_blk: BlockId::new(&dir_tbl, 0),
+            leaf: None,
+        })
+    }
+
+    //one block for each directory level and the leaf
+    pub fn search_cost(num_blocks: u64, rpb: usize) -> u64 {
+        if num_blocks <= 1 || rpb <= 1 {
+            return 1;
+        }
+        1 + (num_blocks as f64).log(rpb as f64).ceil() as u64
+    }
+
+    /*
+    position the index before the first record with value in the range,
+    next then returns the records in the order of their values, for
+    example (Included(a), Excluded(b)) for a <= value < b
+    */
+    pub fn before_range(&mut self, lo: Bound<Constant>, hi: Bound<Constant>) -> DbResult<()> {
+        self.close();
+        let blk_num = match &lo {
+            Bound::Included(key) | Bound::Excluded(key) => {
+                let mut root = BTreeDir::new(self.tx.clone(), self.root_blk.clone(), self.dir_layout.clone())?;
+                root.search(key)?
+            }
+            Bound::Unbounded => 0,
+        };
+        let leaf_blk = BlockId::new(&self.leaf_tbl, blk_num);
+        self.leaf = Some(BTreeLeaf::new(
+            self.tx.clone(),
+            leaf_blk,
+            self.leaf_layout.clone(),
+            lo,
+            hi,
+        )?);
+        Ok(())
+    }
+
+    /*
+    build the index from records sorted by value, the leaves are filled
+    one after another and the directory is built level by level above
+    them, which is much faster than inserting the records one by one.
+    The index must be empty
+    */
+    pub fn bulk_load<I>(&mut self, records: I) -> DbResult<()>
+    where
+        I: IntoIterator<Item = (Constant, RecordId)>,
+    {
+        self.close();
+        let dir_tbl = self.root_blk.file_name();
+        let root = BTPage::new(self.tx.clone(), self.root_blk.clone(), self.dir_layout.clone())?;
+        let mut leaf = BTPage::new(
+            self.tx.clone(),
+            BlockId::new(&self.leaf_tbl, 0),
+            self.leaf_layout.clone(),
+        )?;
+        let is_empty = {
+            let mut tx = self.tx.lock().unwrap();
+            tx.size(&self.leaf_tbl)? == 1 && tx.size(&dir_tbl)? == 1
+        };
+        if !is_empty || leaf.num_recs()? > 0 || root.num_recs()? > 1 {
+            return Err(DbError::BulkLoad(format!("index {} is not empty", self.leaf_tbl)));
+        }
+
+        let block_size = self.tx.lock().unwrap().block_size();
+        let leaf_cap = BTPage::capacity(&self.leaf_layout, block_size).max(1);
+        let mut leaf_entries = vec![DirEntry {
+            data_val: root.get_data_val(0)?,
+            blk_num: 0,
+        }];
+        //last block in the overflow chain of the current leaf
+        let mut overflow: Option<BTPage> = None;
+        //slot in the current leaf of the first record with the last value
+        let mut group_start = 0;
+        let mut last_val: Option<Constant> = None;
+        for (val, rid) in records {
+            if last_val.as_ref().is_some_and(|last| val < *last) {
+                return Err(DbError::BulkLoad(format!("value {} is out of order", val)));
+            }
+            let same_val = last_val.as_ref() == Some(&val);
+            if !same_val {
+                group_start = leaf.num_recs()?;
+            }
+
+            let target = if let (true, Some(page)) = (same_val, overflow.as_ref()) {
+                if page.num_recs()? >= leaf_cap {
+                    let new_page =
+                        BTPage::append_new(self.tx.clone(), &self.leaf_tbl, self.leaf_layout.clone(), NO_BLOCK)?;
+                    page.set_flag(new_page.block().number() as i32)?;
+                    overflow = Some(new_page);
+                }
+                overflow.as_ref().unwrap()
+            } else if leaf.num_recs()? < leaf_cap {
+                &leaf
+            } else if same_val && group_start == 0 {
+                //the leaf holds only this value, the rest goes into overflow
+                let new_page = BTPage::append_new(self.tx.clone(), &self.leaf_tbl, self.leaf_layout.clone(), NO_BLOCK)?;
+                leaf.set_flag(new_page.block().number() as i32)?;
+                overflow.insert(new_page)
+            } else {
+                //records with the same value move into the new leaf together
+                let new_blk = if same_val {
+                    leaf.split(group_start, NO_BLOCK)?
+                } else {
+                    BTPage::append_new(self.tx.clone(), &self.leaf_tbl, self.leaf_layout.clone(), NO_BLOCK)?
+                        .block()
+                        .clone()
+                };
+                leaf.set_next(new_blk.number() as i32)?;
+                leaf_entries.push(DirEntry {
+                    data_val: val.clone(),
+                    blk_num: new_blk.number(),
+                });
+                leaf = BTPage::new(self.tx.clone(), new_blk, self.leaf_layout.clone())?;
+                overflow = None;
+                group_start = 0;
+                &leaf
+            };
+            target.push(&[
+                ("dataval", val.clone()),
+                ("block", Constant::Int(rid.block_number() as i32)),
+                ("slot", Constant::Int(rid.slot() as i32)),
+            ])?;
+            last_val = Some(val);
+        }
+
+        //the entries of one level fill the blocks of the level above, until they fit into the root
+        let dir_cap = BTPage::capacity(&self.dir_layout, block_size).max(2);
+        let mut entries = leaf_entries;
+        let mut level = 0;
+        while entries.len() > dir_cap {
+            let mut upper_entries = Vec::new();
+            for chunk in entries.chunks(dir_cap) {
+                let page = BTPage::append_new(self.tx.clone(), &dir_tbl, self.dir_layout.clone(), level)?;
+                for e in chunk {
+                    page.insert_dir(page.num_recs()?, &e.data_val, e.blk_num)?;
+                }
+                upper_entries.push(DirEntry {
+                    data_val: chunk[0].data_val.clone(),
+                    blk_num: page.block().number(),
+                });
+            }
+            entries = upper_entries;
+            level += 1;
+        }
+        root.delete(0)?;
+        root.set_flag(level)?;
+        for e in &entries {
+            root.insert_dir(root.num_recs()?, &e.data_val, e.blk_num)?;
+        }
+        Ok(())
+    }
+
+    fn leaf(&mut self) -> &mut BTreeLeaf {
+        self.leaf
+            .as_mut()
+            .expect("b-tree index is not positioned by before_first")
+    }
+}
+
+impl Index for BTreeIndex {
+    fn before_first(&mut self, search_key: &Constant) -> DbResult<()> {
+        self.before_range(Bound::Included(search_key.clone()), Bound::Included(search_key.clone()))
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        self.leaf().next()
+    }
+
+    fn get_data_rid(&self) -> DbResult<RecordId> {
+        self.leaf
+            .as_ref()
+            .expect("b-tree index is not positioned by before_first")
+            .get_data_rid()
+    }
+
+    fn insert(&mut self, data_val: &Constant, data_rid: RecordId) -> DbResult<()> {
+        self.before_first(data_val)?;
+        let e = self.leaf().insert(data_val, data_rid)?;
+        self.close();
+        let Some(e) = e else {
+            return Ok(());
+        };
+        let root = BTreeDir::new(self.tx.clone(), self.root_blk.clone(), self.dir_layout.clone())?;
+        if let Some(e) = root.insert(&e)? {
+            root.make_new_root(e)?;
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, data_val: &Constant, data_rid: RecordId) -> DbResult<()> {
+        self.before_first(data_val)?;
+        self.leaf().delete(data_rid)?;
+        self.close();
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        self.leaf = None;
+    }
+}
@@ -0,0 +1,249 @@
+use super::btree_dir::DirEntry;
+use super::btree_page::{BTPage, NO_BLOCK};
+use crate::error::DbResult;
+use crate::file_mgr::BlockId;
+use crate::query::Constant;
+use crate::record_mgr::{Layout, RecordId};
+use crate::tx_mgr::Transaction;
+
+use std::ops::Bound;
+use std::sync::{Arc, Mutex};
+
+/*
+cursor over the leaf records with values in the range [lo, hi], it
+starts in the leaf where lo belongs and follows the next pointers of
+the leaves. All records with the same value are kept in one leaf, when
+they don't fit, the leaf holds the first of them and the others go into
+a chain of overflow blocks, which is visited right after that first
+record, so the records come out in sorted order
+*/
+pub struct BTreeLeaf {
+    tx: Arc<Mutex<Transaction>>,
+    layout: Arc<Layout>,
+    file_name: String,
+    //the leaf, or one of the overflow blocks of the leaf
+    contents: BTPage,
+    current_slot: Option<usize>,
+    //set while going through the overflow chain of the leaf
+    leaf_blk: Option<BlockId>,
+    overflow_done: bool,
+    lo: Bound<Constant>,
+    hi: Bound<Constant>,
+}
+
+impl BTreeLeaf {
+    pub fn new(
+        tx: Arc<Mutex<Transaction>>,
+        blk: BlockId,
+        layout: Arc<Layout>,
+        lo: Bound<Constant>,
+        hi: Bound<Constant>,
+    ) -> DbResult<Self> {
+        let file_name = blk.file_name();
+        let contents = BTPage::new(tx.clone(), blk, layout.clone())?;
+        let current_slot = match &lo {
+            Bound::Included(key) | Bound::Excluded(key) => contents.find_slot_before(key)?,
+            Bound::Unbounded => None,
+        };
+        Ok(BTreeLeaf {
+            tx,
+            layout,
+            file_name,
+            contents,
+            current_slot,
+            leaf_blk: None,
+            overflow_done: false,
+            lo,
+            hi,
+        })
+    }
+
+    //named like Scan::next, it can fail so it is not an iterator
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> DbResult<bool> {
+        loop {
+            if self.leaf_blk.is_some() {
+                if self.move_in_overflow()? {
+                    return Ok(true);
+                }
+                continue;
+            }
+            //the first record is done, go through its overflow chain before the second one
+            if self.current_slot == Some(0) && !self.overflow_done && self.contents.flag()? != NO_BLOCK {
+                self.overflow_done = true;
+                if self.in_lo(&self.contents.get_data_val(0)?) {
+                    let overflow_blk = self.block(self.contents.flag()?);
+                    self.leaf_blk = Some(self.contents.block().clone());
+                    self.contents = BTPage::new(self.tx.clone(), overflow_blk, self.layout.clone())?;
+                    self.current_slot = None;
+                    continue;
+                }
+            }
+
+            let slot = self.current_slot.map_or(0, |slot| slot + 1);
+            if slot >= self.contents.num_recs()? {
+                let next = self.contents.next()?;
+                if next == NO_BLOCK {
+                    return Ok(false);
+                }
+                self.contents = BTPage::new(self.tx.clone(), self.block(next), self.layout.clone())?;
+                self.current_slot = None;
+                self.overflow_done = false;
+                continue;
+            }
+            self.current_slot = Some(slot);
+            let val = self.contents.get_data_val(slot)?;
+            if !self.in_hi(&val) {
+                return Ok(false);
+            }
+            if self.in_lo(&val) {
+                return Ok(true);
+            }
+        }
+    }
+
+    pub fn get_data_rid(&self) -> DbResult<RecordId> {
+        self.contents.get_data_rid(self.slot())
+    }
+
+    //the leaf is positioned by a range beginning with the value of the record
+    pub fn delete(&mut self, data_rid: RecordId) -> DbResult<()> {
+        while self.next()? {
+            if self.get_data_rid()? != data_rid {
+                continue;
+            }
+            //the first record of a leaf with overflow chain is replaced by one from the chain
+            if self.leaf_blk.is_none() && self.slot() == 0 {
+                while self.contents.flag()? != NO_BLOCK {
+                    let overflow_blk = self.block(self.contents.flag()?);
+                    let overflow = BTPage::new(self.tx.clone(), overflow_blk, self.layout.clone())?;
+                    let num_recs = overflow.num_recs()?;
+                    if num_recs == 0 {
+                        self.contents.set_flag(overflow.flag()?)?;
+                        continue;
+                    }
+                    let val = overflow.get_data_val(num_recs - 1)?;
+                    let rid = overflow.get_data_rid(num_recs - 1)?;
+                    overflow.delete(num_recs - 1)?;
+                    self.contents.delete(0)?;
+                    self.contents.insert_leaf(0, &val, rid)?;
+                    if num_recs == 1 {
+                        self.contents.set_flag(overflow.flag()?)?;
+                    }
+                    return Ok(());
+                }
+            }
+            return self.contents.delete(self.slot());
+        }
+        Ok(())
+    }
+
+    /*
+    the leaf is positioned by a range beginning with the value, return the
+    entry of the new leaf if the leaf is split
+    */
+    pub fn insert(&mut self, data_val: &Constant, data_rid: RecordId) -> DbResult<Option<DirEntry>> {
+        let contents = &self.contents;
+        //a smaller value can't be the first record of a leaf with overflow chain
+        if contents.flag()? != NO_BLOCK && contents.num_recs()? > 0 && contents.get_data_val(0)? > *data_val {
+            let first_val = contents.get_data_val(0)?;
+            let new_blk = contents.split(0, contents.flag()?)?;
+            self.link_next_leaf(&new_blk)?;
+            self.contents.set_flag(NO_BLOCK)?;
+            self.contents.insert_leaf(0, data_val, data_rid)?;
+            self.current_slot = Some(0);
+            return Ok(Some(DirEntry {
+                data_val: first_val,
+                blk_num: new_blk.number(),
+            }));
+        }
+
+        let slot = self.current_slot.map_or(0, |slot| slot + 1);
+        self.contents.insert_leaf(slot, data_val, data_rid)?;
+        self.current_slot = Some(slot);
+        if !self.contents.is_full()? {
+            return Ok(None);
+        }
+
+        let contents = &self.contents;
+        let num_recs = contents.num_recs()?;
+        let first_key = contents.get_data_val(0)?;
+        let last_key = contents.get_data_val(num_recs - 1)?;
+        if last_key == first_key {
+            //all records have the same value, all but the first go into overflow
+            let new_blk = contents.split(1, contents.flag()?)?;
+            contents.set_flag(new_blk.number() as i32)?;
+            return Ok(None);
+        }
+
+        //split in the middle, but keep records with the same value in one leaf
+        let mut split_pos = num_recs / 2;
+        let mut split_key = contents.get_data_val(split_pos)?;
+        if split_key == first_key {
+            while contents.get_data_val(split_pos)? == split_key {
+                split_pos += 1;
+            }
+            split_key = contents.get_data_val(split_pos)?;
+        } else {
+            while contents.get_data_val(split_pos - 1)? == split_key {
+                split_pos -= 1;
+            }
+        }
+        let new_blk = contents.split(split_pos, NO_BLOCK)?;
+        self.link_next_leaf(&new_blk)?;
+        Ok(Some(DirEntry {
+            data_val: split_key,
+            blk_num: new_blk.number(),
+        }))
+    }
+
+    //returns true when on a record of the overflow chain, false when back in the leaf
+    fn move_in_overflow(&mut self) -> DbResult<bool> {
+        let slot = self.current_slot.map_or(0, |slot| slot + 1);
+        if slot < self.contents.num_recs()? {
+            self.current_slot = Some(slot);
+            return Ok(true);
+        }
+        let next = self.contents.flag()?;
+        let blk = if next == NO_BLOCK {
+            self.current_slot = Some(0);
+            self.leaf_blk.take().unwrap()
+        } else {
+            self.current_slot = None;
+            self.block(next)
+        };
+        self.contents = BTPage::new(self.tx.clone(), blk, self.layout.clone())?;
+        Ok(false)
+    }
+
+    //the new leaf holds larger values, so it comes right after this one
+    fn link_next_leaf(&self, new_blk: &BlockId) -> DbResult<()> {
+        let new_leaf = BTPage::new(self.tx.clone(), new_blk.clone(), self.layout.clone())?;
+        new_leaf.set_next(self.contents.next()?)?;
+        self.contents.set_next(new_blk.number() as i32)
+    }
+
+    fn in_lo(&self, val: &Constant) -> bool {
+        match &self.lo {
+            Bound::Included(lo) => val >= lo,
+            Bound::Excluded(lo) => val > lo,
+            Bound::Unbounded => true,
+        }
+    }
+
+    fn in_hi(&self, val: &Constant) -> bool {
+        match &self.hi {
+            Bound::Included(hi) => val <= hi,
+            Bound::Excluded(hi) => val < hi,
+            Bound::Unbounded => true,
+        }
+    }
+
+    fn slot(&self) -> usize {
+        self.current_slot.expect("b-tree leaf is not on a record")
+    }
+
+    fn block(&self, blk_num: i32) -> BlockId {
+        BlockId::new(&self.file_name, blk_num as u64)
+    }
+}
@@ -0,0 +1,249 @@
+use crate::error::{DbError, DbResult};
+use crate::file_mgr::BlockId;
+use crate::query::Constant;
+use crate::record_mgr::{FieldType, Layout, RecordId};
+use crate::tx_mgr::Transaction;
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+const FLAG_OFFSET: usize = 0;
+const NUM_RECS_OFFSET: usize = 4;
+const NEXT_OFFSET: usize = 8;
+pub const BTREE_HEADER_SIZE: usize = 12;
+//value of the flag and the next pointer when they point nowhere
+pub const NO_BLOCK: i32 = -1;
+
+/*
+one block of a b-tree, directory and leaf blocks share the format:
+    flag: level of a directory block, 0 when its children are leaves.
+          For a leaf the block number of its overflow block, or -1
+    num recs: number of records in the block
+    next: for a leaf the block number of the next leaf, or -1
+then the records sorted by dataval in slots of the layout size. Unlike a
+record page there are no empty slots, inserting and deleting shift the
+records after the slot
+*/
+pub struct BTPage {
+    tx: Arc<Mutex<Transaction>>,
+    blk: BlockId,
+    layout: Arc<Layout>,
+}
+
+impl BTPage {
+    pub fn new(tx: Arc<Mutex<Transaction>>, blk: BlockId, layout: Arc<Layout>) -> DbResult<Self> {
+        tx.lock().unwrap().pin(&blk)?;
+        Ok(BTPage { tx, blk, layout })
+    }
+
+    //append a formatted block to the file of the page
+    pub fn append_new(tx: Arc<Mutex<Transaction>>, file_name: &str, layout: Arc<Layout>, flag: i32) -> DbResult<Self> {
+        let blk = tx.lock().unwrap().append(file_name)?;
+        let page = BTPage::new(tx, blk, layout)?;
+        page.format(flag)?;
+        Ok(page)
+    }
+
+    //max number of records in a block that is not full
+    pub fn capacity(layout: &Layout, block_size: u64) -> usize {
+        ((block_size as usize - BTREE_HEADER_SIZE - 1) / layout.slot_size()).saturating_sub(1)
+    }
+
+    pub fn block(&self) -> &BlockId {
+        &self.blk
+    }
+
+    //the new block is not seen by anybody before the change is logged
+    pub fn format(&self, flag: i32) -> DbResult<()> {
+        let mut tx = self.tx.lock().unwrap();
+        tx.set_int(&self.blk, FLAG_OFFSET, flag, false)?;
+        tx.set_int(&self.blk, NUM_RECS_OFFSET, 0, false)?;
+        tx.set_int(&self.blk, NEXT_OFFSET, NO_BLOCK, false)
+    }
+
+    //the last slot whose value is smaller than the key, None if there is no such slot
+    pub fn find_slot_before(&self, search_key: &Constant) -> DbResult<Option<usize>> {
+        let num_recs = self.num_recs()?;
+        let mut slot = 0;
+        while slot < num_recs && self.get_data_val(slot)? < *search_key {
+            slot += 1;
+        }
+        Ok(slot.checked_sub(1))
+    }
+
+    //no room for one more record, the block has to be split
+    pub fn is_full(&self) -> DbResult<bool> {
+        let block_size = self.tx.lock().unwrap().block_size() as usize;
+        Ok(self.slot_pos(self.num_recs()? + 1) >= block_size)
+    }
+
+    /*
+    move the records from split_pos to the end into a new block, which
+    gets the given flag
+    */
+    pub fn split(&self, split_pos: usize, flag: i32) -> DbResult<BlockId> {
+        let new_page = BTPage::append_new(self.tx.clone(), &self.blk.file_name(), self.layout.clone(), flag)?;
+        self.transfer_recs(split_pos, &new_page)?;
+        Ok(new_page.block().clone())
+    }
+
+    pub fn get_data_val(&self, slot: usize) -> DbResult<Constant> {
+        self.get_val(slot, "dataval")
+    }
+
+    pub fn flag(&self) -> DbResult<i32> {
+        self.tx.lock().unwrap().get_int(&self.blk, FLAG_OFFSET)
+    }
+
+    pub fn set_flag(&self, flag: i32) -> DbResult<()> {
+        self.tx.lock().unwrap().set_int(&self.blk, FLAG_OFFSET, flag, true)
+    }
+
+    pub fn next(&self) -> DbResult<i32> {
+        self.tx.lock().unwrap().get_int(&self.blk, NEXT_OFFSET)
+    }
+
+    pub fn set_next(&self, next: i32) -> DbResult<()> {
+        self.tx.lock().unwrap().set_int(&self.blk, NEXT_OFFSET, next, true)
+    }
+
+    pub fn num_recs(&self) -> DbResult<usize> {
+        Ok(self.tx.lock().unwrap().get_int(&self.blk, NUM_RECS_OFFSET)? as usize)
+    }
+
+    //directory records point to a child block
+    pub fn get_child_num(&self, slot: usize) -> DbResult<u64> {
+        Ok(self.get_int(slot, "block")? as u64)
+    }
+
+    pub fn insert_dir(&self, slot: usize, val: &Constant, blk_num: u64) -> DbResult<()> {
+        self.insert(slot)?;
+        self.set_val(slot, "dataval", val)?;
+        self.set_int(slot, "block", blk_num as i32)
+    }
+
+    //leaf records point to a data record
+    pub fn get_data_rid(&self, slot: usize) -> DbResult<RecordId> {
+        let blk_num = self.get_int(slot, "block")?;
+        let id = self.get_int(slot, "slot")?;
+        Ok(RecordId::new(blk_num as u64, id as usize))
+    }
+
+    pub fn insert_leaf(&self, slot: usize, val: &Constant, rid: RecordId) -> DbResult<()> {
+        self.insert(slot)?;
+        self.set_val(slot, "dataval", val)?;
+        self.set_int(slot, "block", rid.block_number() as i32)?;
+        self.set_int(slot, "slot", rid.slot() as i32)
+    }
+
+    pub fn delete(&self, slot: usize) -> DbResult<()> {
+        let num_recs = self.num_recs()?;
+        for i in slot + 1..num_recs {
+            self.copy_record(i, i - 1)?;
+        }
+        self.set_num_recs(num_recs - 1)
+    }
+
+    //append a record after the last one, for building blocks from sorted input
+    pub fn push(&self, vals: &[(&str, Constant)]) -> DbResult<()> {
+        let slot = self.num_recs()?;
+        self.set_num_recs(slot + 1)?;
+        for (field_name, val) in vals {
+            self.set_val(slot, field_name, val)?;
+        }
+        Ok(())
+    }
+
+    fn insert(&self, slot: usize) -> DbResult<()> {
+        let num_recs = self.num_recs()?;
+        for i in (slot..num_recs).rev() {
+            self.copy_record(i, i + 1)?;
+        }
+        self.set_num_recs(num_recs + 1)
+    }
+
+    fn copy_record(&self, from: usize, to: usize) -> DbResult<()> {
+        for field_name in self.layout.schema().fields() {
+            let val = self.get_val(from, field_name)?;
+            self.set_val(to, field_name, &val)?;
+        }
+        Ok(())
+    }
+
+    fn transfer_recs(&self, slot: usize, dest: &BTPage) -> DbResult<()> {
+        let mut dest_slot = 0;
+        while slot < self.num_recs()? {
+            dest.insert(dest_slot)?;
+            for field_name in self.layout.schema().fields() {
+                dest.set_val(dest_slot, field_name, &self.get_val(slot, field_name)?)?;
+            }
+            self.delete(slot)?;
+            dest_slot += 1;
+        }
+        Ok(())
+    }
+
+    fn set_num_recs(&self, num_recs: usize) -> DbResult<()> {
+        self.tx
+            .lock()
+            .unwrap()
+            .set_int(&self.blk, NUM_RECS_OFFSET, num_recs as i32, true)
+    }
+
+    fn get_int(&self, slot: usize, field_name: &str) -> DbResult<i32> {
+        let offset = self.field_pos(slot, field_name)?;
+        self.tx.lock().unwrap().get_int(&self.blk, offset)
+    }
+
+    fn set_int(&self, slot: usize, field_name: &str, val: i32) -> DbResult<()> {
+        let offset = self.field_pos(slot, field_name)?;
+        self.tx.lock().unwrap().set_int(&self.blk, offset, val, true)
+    }
+
+    fn get_val(&self, slot: usize, field_name: &str) -> DbResult<Constant> {
+        let offset = self.field_pos(slot, field_name)?;
+        let mut tx = self.tx.lock().unwrap();
+        match self.layout.schema().field_type(field_name) {
+            Some(FieldType::Integer) => Ok(Constant::Int(tx.get_int(&self.blk, offset)?)),
+            _ => Ok(Constant::Str(tx.get_string(&self.blk, offset)?)),
+        }
+    }
+
+    fn set_val(&self, slot: usize, field_name: &str, val: &Constant) -> DbResult<()> {
+        let offset = self.field_pos(slot, field_name)?;
+        match (self.layout.schema().field_type(field_name), val) {
+            (Some(FieldType::Integer), Constant::Int(val)) => {
+                self.tx.lock().unwrap().set_int(&self.blk, offset, *val, true)
+            }
+            (Some(FieldType::Varchar), Constant::Str(val)) => {
+                let max_len = self.layout.schema().length(field_name).unwrap_or(0);
+                if val.len() > max_len {
+                    return Err(DbError::FieldTooLong {
+                        field_name: field_name.to_string(),
+                        len: val.len(),
+                        max_len,
+                    });
+                }
+                self.tx.lock().unwrap().set_string(&self.blk, offset, val, true)
+            }
+            _ => Err(DbError::TypeMismatch(field_name.to_string())),
+        }
+    }
+
+    fn slot_pos(&self, slot: usize) -> usize {
+        BTREE_HEADER_SIZE + slot * self.layout.slot_size()
+    }
+
+    fn field_pos(&self, slot: usize, field_name: &str) -> DbResult<usize> {
+        let offset = self
+            .layout
+            .offset(field_name)
+            .ok_or_else(|| DbError::FieldNotFound(field_name.to_string()))?;
+        Ok(self.slot_pos(slot) + offset)
+    }
+}
+
+impl Drop for BTPage {
+    fn drop(&mut self) {
+        self.tx.lock().unwrap_or_else(PoisonError::into_inner).unpin(&self.blk);
+    }
+}
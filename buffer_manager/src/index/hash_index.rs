@@ -0,0 +1,100 @@
+use super::Index;
+use crate::error::DbResult;
+use crate::query::{Constant, Scan, UpdateScan};
+use crate::record_mgr::{Layout, RecordId, TableScan};
+use crate::tx_mgr::Transaction;
+
+use std::sync::{Arc, Mutex};
+
+pub const NUM_BUCKETS: usize = 100;
+
+/*
+static hash index, the index records are spread over NUM_BUCKETS
+buckets by the hash of their value, each bucket is a table named
+<index name><bucket> searched from the beginning to the end. The
+number of buckets never changes, so the buckets grow longer with the
+table
+*/
+pub struct HashIndex {
+    tx: Arc<Mutex<Transaction>>,
+    idx_name: String,
+    layout: Arc<Layout>,
+    search_key: Option<Constant>,
+    ts: Option<TableScan>,
+}
+
+impl HashIndex {
+    pub fn new(tx: Arc<Mutex<Transaction>>, idx_name: &str, layout: Arc<Layout>) -> Self {
+        HashIndex {
+            tx,
+            idx_name: idx_name.to_string(),
+            layout,
+            search_key: None,
+            ts: None,
+        }
+    }
+
+    //the whole bucket is read, num_blocks is the size of all buckets together
+    pub fn search_cost(num_blocks: u64) -> u64 {
+        num_blocks / NUM_BUCKETS as u64
+    }
+
+    fn bucket(key: &Constant) -> usize {
+        (key.stable_hash() % NUM_BUCKETS as u64) as usize
+    }
+
+    fn table_scan(&mut self) -> &mut TableScan {
+        self.ts.as_mut().expect("hash index is not positioned by before_first")
+    }
+}
+
+impl Index for HashIndex {
+    fn before_first(&mut self, search_key: &Constant) -> DbResult<()> {
+        self.close();
+        let tbl_name = format!("{}{}", self.idx_name, Self::bucket(search_key));
+        self.ts = Some(TableScan::new(self.tx.clone(), &tbl_name, self.layout.clone())?);
+        self.search_key = Some(search_key.clone());
+        Ok(())
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        let search_key = self.search_key.clone();
+        let ts = self.table_scan();
+        while ts.next()? {
+            if Some(ts.get_val("dataval")?) == search_key {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn get_data_rid(&self) -> DbResult<RecordId> {
+        let ts = self.ts.as_ref().expect("hash index is not positioned by before_first");
+        let blk_num = ts.get_int("block")?;
+        let slot = ts.get_int("slot")?;
+        Ok(RecordId::new(blk_num as u64, slot as usize))
+    }
+
+    fn insert(&mut self, data_val: &Constant, data_rid: RecordId) -> DbResult<()> {
+        self.before_first(data_val)?;
+        let ts = self.table_scan();
+        ts.insert()?;
+        ts.set_int("block", data_rid.block_number() as i32)?;
+        ts.set_int("slot", data_rid.slot() as i32)?;
+        ts.set_val("dataval", data_val)
+    }
+
+    fn delete(&mut self, data_val: &Constant, data_rid: RecordId) -> DbResult<()> {
+        self.before_first(data_val)?;
+        while self.next()? {
+            if self.get_data_rid()? == data_rid {
+                return self.table_scan().delete();
+            }
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        self.ts = None;
+    }
+}
@@ -0,0 +1,57 @@
+pub mod btree_dir;
+pub mod btree_index;
+pub mod btree_leaf;
+pub mod btree_page;
+pub mod hash_index;
+#[cfg(test)]
+pub mod test;
+
+pub use btree_index::BTreeIndex;
+pub use hash_index::{HashIndex, NUM_BUCKETS};
+
+use crate::error::DbResult;
+use crate::query::Constant;
+use crate::record_mgr::RecordId;
+
+/*
+secondary index on one field of a table, every index record maps a
+value of the field to the record id of a data record having it.
+before_first positions the index before the first index record with
+the search key, next moves through them like a scan
+*/
+pub trait Index {
+    fn before_first(&mut self, search_key: &Constant) -> DbResult<()>;
+    fn next(&mut self) -> DbResult<bool>;
+    fn get_data_rid(&self) -> DbResult<RecordId>;
+    fn insert(&mut self, data_val: &Constant, data_rid: RecordId) -> DbResult<()>;
+    fn delete(&mut self, data_val: &Constant, data_rid: RecordId) -> DbResult<()>;
+    fn close(&mut self);
+}
+
+//kind of index structure, chosen when the index is created
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexType {
+    //equality search only
+    #[default]
+    Hash,
+    //equality and range search
+    BTree,
+}
+
+impl IndexType {
+    //code saved in the catalog for the type
+    pub fn code(self) -> i32 {
+        match self {
+            IndexType::Hash => 0,
+            IndexType::BTree => 1,
+        }
+    }
+
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(IndexType::Hash),
+            1 => Some(IndexType::BTree),
+            _ => None,
+        }
+    }
+}
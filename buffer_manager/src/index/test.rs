@@ -0,0 +1,318 @@
+use super::*;
+use crate::error::DbError;
+use crate::metadata_mgr::{IndexInfo, StatInfo};
+use crate::query::{Constant, Scan, UpdateScan};
+use crate::record_mgr::{Layout, RecordId, Schema, TableScan};
+use crate::server::RustDB;
+use crate::tx_mgr::Transaction;
+use std::collections::HashMap;
+use std::ops::Bound;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+fn remove_dir(directory: &str) {
+    if Path::new(directory).exists() {
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
+
+//data record ids found by the index for the key
+fn search(idx: &mut dyn Index, key: &Constant) -> Vec<RecordId> {
+    idx.before_first(key).unwrap();
+    let mut rids = Vec::new();
+    while idx.next().unwrap() {
+        rids.push(idx.get_data_rid().unwrap());
+    }
+    rids.sort_by_key(|rid| (rid.block_number(), rid.slot()));
+    rids
+}
+
+fn index_info(tx: &Arc<Mutex<Transaction>>, idx_name: &str, schema: &Schema, fld_name: &str) -> IndexInfo {
+    IndexInfo::new(tx.clone(), idx_name, fld_name, schema.clone(), StatInfo::default())
+}
+
+#[test]
+fn test_hash_index_insert_and_delete() {
+    let directory = "hashindextest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let mut schema = Schema::new();
+    schema.add_int_field("a");
+    schema.add_string_field("b", 8);
+
+    let mut int_idx = index_info(&tx, "aidx", &schema, "a").open().unwrap();
+    let mut str_idx = index_info(&tx, "bidx", &schema, "b").open().unwrap();
+    for i in 0..300 {
+        let rid = RecordId::new(i / 10, (i % 10) as usize);
+        int_idx.insert(&Constant::Int((i % 30) as i32), rid).unwrap();
+        str_idx.insert(&Constant::Str(format!("v{}", i % 7)), rid).unwrap();
+    }
+
+    let rids = search(int_idx.as_mut(), &Constant::Int(7));
+    let expected: Vec<RecordId> = (0..10).map(|k| RecordId::new((k * 30 + 7) / 10, 7)).collect();
+    assert_eq!(rids, expected);
+    assert_eq!(search(str_idx.as_mut(), &Constant::from("v3")).len(), 43);
+    assert!(search(int_idx.as_mut(), &Constant::Int(30)).is_empty());
+
+    int_idx.delete(&Constant::Int(7), RecordId::new(3, 7)).unwrap();
+    //deleting a missing entry changes nothing
+    int_idx.delete(&Constant::Int(8), RecordId::new(3, 7)).unwrap();
+    assert_eq!(search(int_idx.as_mut(), &Constant::Int(7)).len(), 9);
+    assert_eq!(search(int_idx.as_mut(), &Constant::Int(8)).len(), 10);
+    int_idx.close();
+    str_idx.close();
+    tx.lock().unwrap().commit().unwrap();
+
+    //the buckets are tables named by the index, the 31 values looked up are spread over many of them
+    let buckets = fs::read_dir(directory)
+        .unwrap()
+        .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().starts_with("aidx"))
+        .count();
+    assert!(buckets > 20 && buckets <= 31);
+    remove_dir(directory);
+}
+
+//value of the field in every data record found by the index
+fn lookup(db: &RustDB, tx: &Arc<Mutex<Transaction>>, field_name: &str, key: Constant) -> Vec<Constant> {
+    let mdm = db.metadata_mgr();
+    let infos: HashMap<String, IndexInfo> = mdm.get_index_info("student", tx).unwrap();
+    let mut idx = infos[field_name].open().unwrap();
+    let layout = Arc::new(mdm.get_layout("student", tx).unwrap());
+    let mut ts = TableScan::new(tx.clone(), "student", layout).unwrap();
+    let mut vals = Vec::new();
+    for rid in search(idx.as_mut(), &key) {
+        ts.move_to_rid(rid).unwrap();
+        assert_eq!(ts.get_val(field_name).unwrap(), key);
+        vals.push(ts.get_val("sid").unwrap());
+    }
+    idx.close();
+    vals
+}
+
+#[test]
+fn test_index_follows_table() {
+    let directory = "indexupdatetest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let planner = db.planner();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    planner
+        .execute_update("create table student (sid int, sname varchar(10), majorid int)", &tx)
+        .unwrap();
+    for i in 0..40 {
+        let sql = format!(
+            "insert into student (sid, sname, majorid) values ({}, 's{}', {})",
+            i,
+            i % 4,
+            (i % 2 + 1) * 10
+        );
+        planner.execute_update(&sql, &tx).unwrap();
+    }
+
+    //indexes are built over the existing records
+    planner
+        .execute_update("create index majoridx on student (majorid)", &tx)
+        .unwrap();
+    planner
+        .execute_update("create index snameidx on student (sname) using btree", &tx)
+        .unwrap();
+    assert_eq!(lookup(&db, &tx, "majorid", Constant::Int(10)).len(), 20);
+    assert_eq!(lookup(&db, &tx, "sname", Constant::from("s1")).len(), 10);
+    let err = planner
+        .execute_update("create index majoridx2 on student (majorid)", &tx)
+        .unwrap_err();
+    assert!(matches!(err, DbError::IndexExists(name) if name == "majoridx"));
+
+    //records inserted, modified and deleted afterwards
+    planner
+        .execute_update("insert into student (sid, sname) values (100, 's1')", &tx)
+        .unwrap();
+    assert_eq!(lookup(&db, &tx, "majorid", Constant::Int(0)), [Constant::Int(100)]);
+    assert_eq!(lookup(&db, &tx, "sname", Constant::from("s1")).len(), 11);
+    planner
+        .execute_update("update student set majorid = 30 where sname = 's1'", &tx)
+        .unwrap();
+    assert_eq!(lookup(&db, &tx, "majorid", Constant::Int(30)).len(), 11);
+    assert_eq!(lookup(&db, &tx, "majorid", Constant::Int(20)).len(), 10);
+    assert!(lookup(&db, &tx, "majorid", Constant::Int(0)).is_empty());
+    planner
+        .execute_update("delete from student where majorid = 10", &tx)
+        .unwrap();
+    assert!(lookup(&db, &tx, "majorid", Constant::Int(10)).is_empty());
+    assert_eq!(lookup(&db, &tx, "sname", Constant::from("s0")).len(), 0);
+    assert_eq!(lookup(&db, &tx, "sname", Constant::from("s3")).len(), 10);
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_create_btree_index_bulk_loads() {
+    let directory = "createbtreetest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let planner = db.planner();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    planner.execute_update("create table enroll (eid int)", &tx).unwrap();
+    for i in 0..200 {
+        let sql = format!("insert into enroll (eid) values ({})", i * 7919 % 200);
+        planner.execute_update(&sql, &tx).unwrap();
+    }
+
+    //the records are loaded in order, every leaf is full
+    planner
+        .execute_update("create index eididx on enroll (eid) using btree", &tx)
+        .unwrap();
+    let capacity = btree_page::BTPage::capacity(&int_layout(), 400);
+    assert_eq!(tx.lock().unwrap().size("eididxleaf").unwrap() as usize, 200usize.div_ceil(capacity));
+    let ii = db.metadata_mgr().get_index_info("enroll", &tx).unwrap().remove("eid").unwrap();
+    let mut idx = ii.open().unwrap();
+    for i in [0, 57, 199] {
+        assert_eq!(search(idx.as_mut(), &Constant::Int(i)).len(), 1);
+    }
+    idx.close();
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+//the test data encodes the value in the block number of the record id
+fn range(idx: &mut BTreeIndex, lo: Bound<Constant>, hi: Bound<Constant>) -> Vec<i32> {
+    idx.before_range(lo, hi).unwrap();
+    let mut vals = Vec::new();
+    while idx.next().unwrap() {
+        vals.push(idx.get_data_rid().unwrap().block_number() as i32);
+    }
+    vals
+}
+
+fn int_layout() -> Arc<Layout> {
+    let mut schema = Schema::new();
+    schema.add_int_field("block");
+    schema.add_int_field("slot");
+    schema.add_int_field("dataval");
+    Arc::new(Layout::new(schema))
+}
+
+#[test]
+fn test_btree_index_split_and_overflow() {
+    let directory = "btreeindextest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let mut idx = BTreeIndex::new(tx.clone(), "bidx", int_layout()).unwrap();
+
+    //values 0..500 in scrambled order, 3 records each, and many records with value 250
+    for i in 0..1500 {
+        let val = (i * 37 % 500) as i32;
+        idx.insert(&Constant::Int(val), RecordId::new(val as u64, i / 500)).unwrap();
+    }
+    for i in 0..200 {
+        idx.insert(&Constant::Int(250), RecordId::new(250, i + 3)).unwrap();
+    }
+    //leaves and directory blocks are split
+    assert!(tx.lock().unwrap().size("bidxleaf").unwrap() > 50);
+    assert!(tx.lock().unwrap().size("bidxdir").unwrap() > 1);
+
+    assert_eq!(search(&mut idx, &Constant::Int(7)).len(), 3);
+    assert_eq!(search(&mut idx, &Constant::Int(250)).len(), 203);
+    assert!(search(&mut idx, &Constant::Int(500)).is_empty());
+    assert!(search(&mut idx, &Constant::Int(-1)).is_empty());
+    let all = range(&mut idx, Bound::Unbounded, Bound::Unbounded);
+    assert_eq!(all.len(), 1700);
+    assert!(all.windows(2).all(|w| w[0] <= w[1]));
+
+    //the records in overflow blocks take the place of the deleted ones
+    for i in 0..150 {
+        idx.delete(&Constant::Int(250), RecordId::new(250, i)).unwrap();
+    }
+    assert_eq!(search(&mut idx, &Constant::Int(250)).len(), 53);
+    idx.delete(&Constant::Int(7), RecordId::new(7, 1)).unwrap();
+    assert_eq!(search(&mut idx, &Constant::Int(7)), [RecordId::new(7, 0), RecordId::new(7, 2)]);
+    //a smaller value goes before the records of the leaf with overflow
+    idx.insert(&Constant::Int(249), RecordId::new(249, 9)).unwrap();
+    assert_eq!(search(&mut idx, &Constant::Int(249)).len(), 4);
+    assert_eq!(range(&mut idx, Bound::Included(Constant::Int(249)), Bound::Excluded(Constant::Int(251))).len(), 57);
+    idx.close();
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_btree_range_scan() {
+    let directory = "btreerangetest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let mut idx = BTreeIndex::new(tx.clone(), "ridx", int_layout()).unwrap();
+    for i in (0..300).rev() {
+        idx.insert(&Constant::Int(i * 2), RecordId::new((i * 2) as u64, 0)).unwrap();
+    }
+
+    let ge = range(&mut idx, Bound::Included(Constant::Int(500)), Bound::Unbounded);
+    assert_eq!(ge, (250..300).map(|i| i * 2).collect::<Vec<i32>>());
+    let lt = range(&mut idx, Bound::Unbounded, Bound::Excluded(Constant::Int(20)));
+    assert_eq!(lt, (0..10).map(|i| i * 2).collect::<Vec<i32>>());
+    //bounds between two values, and bounds on values in the index
+    let between = range(&mut idx, Bound::Included(Constant::Int(101)), Bound::Included(Constant::Int(111)));
+    assert_eq!(between, [102, 104, 106, 108, 110]);
+    let open = range(&mut idx, Bound::Excluded(Constant::Int(100)), Bound::Excluded(Constant::Int(110)));
+    assert_eq!(open, [102, 104, 106, 108]);
+    assert!(range(&mut idx, Bound::Included(Constant::Int(700)), Bound::Unbounded).is_empty());
+    assert!(range(&mut idx, Bound::Included(Constant::Int(51)), Bound::Excluded(Constant::Int(52))).is_empty());
+    idx.close();
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_btree_bulk_load() {
+    let directory = "btreebulktest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+
+    //sorted records, value 100 has too many records for one leaf
+    let mut records = Vec::new();
+    for val in 0..400 {
+        let count = if val == 100 { 120 } else { 1 + val % 3 };
+        for k in 0..count {
+            records.push((Constant::Int(val), RecordId::new(val as u64, k as usize)));
+        }
+    }
+    let mut idx = BTreeIndex::new(tx.clone(), "lidx", int_layout()).unwrap();
+    idx.bulk_load(records.clone()).unwrap();
+    assert!(tx.lock().unwrap().size("lidxdir").unwrap() > 1);
+    let all = range(&mut idx, Bound::Unbounded, Bound::Unbounded);
+    let expected: Vec<i32> = records.iter().map(|(val, _)| val.as_int().unwrap()).collect();
+    assert_eq!(all, expected);
+    assert_eq!(search(&mut idx, &Constant::Int(100)).len(), 120);
+    assert_eq!(search(&mut idx, &Constant::Int(302)).len(), 3);
+    let between = range(&mut idx, Bound::Included(Constant::Int(10)), Bound::Excluded(Constant::Int(13)));
+    assert_eq!(between, [10, 10, 11, 11, 11, 12]);
+
+    //the loaded tree takes more records like any other
+    for val in 0..400 {
+        idx.insert(&Constant::Int(val), RecordId::new(val as u64, 200)).unwrap();
+    }
+    assert_eq!(search(&mut idx, &Constant::Int(100)).len(), 121);
+    assert_eq!(search(&mut idx, &Constant::Int(302)).len(), 4);
+    assert_eq!(range(&mut idx, Bound::Unbounded, Bound::Unbounded).len(), records.len() + 400);
+
+    //only an empty index is loaded, from sorted records
+    let err = idx.bulk_load(records.clone()).unwrap_err();
+    assert!(matches!(err, DbError::BulkLoad(_)));
+    let mut unsorted = BTreeIndex::new(tx.clone(), "uidx", int_layout()).unwrap();
+    let err = unsorted
+        .bulk_load([(Constant::Int(2), RecordId::new(2, 0)), (Constant::Int(1), RecordId::new(1, 0))])
+        .unwrap_err();
+    assert!(matches!(err, DbError::BulkLoad(_)));
+    idx.close();
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
@@ -0,0 +1,110 @@
+use crate::error::DbResult;
+use crate::file_mgr::*;
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+struct SyncState {
+    //latest lsn handed to the os by the log manager
+    written_lsn: u64,
+    //latest lsn known to be on disk
+    durable_lsn: u64,
+    //a leader is collecting flushes or syncing the log file
+    syncing: bool,
+}
+
+/*
+threads waiting for their log records to reach the disk. The first one
+becomes the leader, it waits for the window so that more flushes pile
+up, then syncs the log file once for all of them. Others arriving in the
+meantime only wait for the leader, if their records are written after
+the leader began syncing, one of them leads the next round
+*/
+pub struct GroupCommit {
+    fm: Arc<Mutex<FileMgr>>,
+    log_file: String,
+    state: Mutex<SyncState>,
+    synced: Condvar,
+}
+
+impl GroupCommit {
+    pub fn new(fm: Arc<Mutex<FileMgr>>, log_file: String) -> Self {
+        GroupCommit {
+            fm,
+            log_file,
+            state: Mutex::new(SyncState {
+                written_lsn: 0,
+                durable_lsn: 0,
+                syncing: false,
+            }),
+            synced: Condvar::new(),
+        }
+    }
+
+    //records up to the lsn are in the os cache, called by the log manager
+    pub fn written(&self, lsn: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.written_lsn = state.written_lsn.max(lsn);
+    }
+
+    //records up to the lsn are on disk, for syncs done by the log manager itself
+    pub fn synced(&self, lsn: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.durable_lsn = state.durable_lsn.max(lsn);
+        self.synced.notify_all();
+    }
+
+    pub fn is_durable(&self, lsn: u64) -> bool {
+        self.state.lock().unwrap().durable_lsn >= lsn
+    }
+
+    pub fn wait_durable(&self, lsn: u64, window: Duration) -> DbResult<()> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.durable_lsn >= lsn {
+                return Ok(());
+            }
+            if state.syncing {
+                state = self.synced.wait(state).unwrap();
+                continue;
+            }
+
+            state.syncing = true;
+            drop(state);
+            thread::sleep(window);
+            let target = self.state.lock().unwrap().written_lsn;
+            let result = self.fm.lock().unwrap().sync(&self.log_file);
+
+            state = self.state.lock().unwrap();
+            state.syncing = false;
+            if result.is_ok() {
+                state.durable_lsn = state.durable_lsn.max(target);
+            }
+            //waiters retry the sync by themselves if it failed
+            self.synced.notify_all();
+            result?;
+        }
+    }
+}
+
+/*
+returned by LogMgr::flush, the records are handed to the os but may not
+be on disk yet. wait must be called after the log manager is unlocked,
+otherwise no other thread can join the group commit
+*/
+#[must_use = "the log records are not durable until the ticket is waited for"]
+pub struct SyncTicket {
+    pub(super) group: Option<Arc<GroupCommit>>,
+    pub(super) lsn: u64,
+    pub(super) window: Duration,
+}
+
+impl SyncTicket {
+    pub fn wait(self) -> DbResult<()> {
+        match self.group {
+            Some(group) => group.wait_durable(self.lsn, self.window),
+            None => Ok(()),
+        }
+    }
+}
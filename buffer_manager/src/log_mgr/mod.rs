@@ -1,8 +1,23 @@
 
+#[cfg(test)]
 pub mod test;
+pub mod group_commit;
 use crate::file_mgr::*;
+use crate::error::{DbError, DbResult};
+pub use group_commit::{GroupCommit, SyncTicket};
 
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/*
+every log block begins with a header, records fill the rest of the block
+from its end toward the header
+    boundary: 4 bytes, offset of the latest record in the block
+    base lsn: 8 bytes, lsn of the last record in the blocks before
+then the lsn of the latest record is found from the last block alone
+when the log is opened again
+*/
+const LOG_HEADER_SIZE: i32 = 12;
 
 //notice the changes in file_mgr for set_int and set_bytes
 pub struct LogMgr{
@@ -31,91 +46,119 @@ pub struct LogMgr{
     */
     last_saved_lsn: u64,
 
-    //from here for iterator
-    current_pos: i32,
-    iter_init: bool,
-    iter_blk: BlockId,
-    buf_for_inter: Vec<u8>,
+    //iterator used when the log manager itself is iterated
+    iter: Option<LogIterator>,
+
+    //syncs of the log file shared by concurrent flushes
+    group_commit: Arc<GroupCommit>,
 }
 
 impl LogMgr{
-    pub fn new(fm: Arc<Mutex<FileMgr>>, log_file_name: String) -> Self {
-        let log_file_len = fm.lock().unwrap().length(log_file_name.clone());
+    pub fn new(fm: Arc<Mutex<FileMgr>>, log_file_name: String) -> DbResult<Self> {
+        let log_size = fm.lock().unwrap().length(log_file_name.clone())?;
         let mut log_buf = vec![0u8; fm.lock().unwrap().block_size() as usize];
         let mut p = Page::from_buffer(&mut log_buf);
 
-        let blk: BlockId;
-        match log_file_len {
-            Ok(log_size) => {
-                /*
-                read the last page of the log file
-                */
-                blk = BlockId::new(log_file_name.as_str(), log_size - 1);
-                fm.lock().unwrap().read_write(&blk, &mut p, false).unwrap();
-            },
-
-            Err(_) => {
-                blk = BlockId::new(&log_file_name, 0);
-                p.set_int(0, fm.lock().unwrap().block_size() as i32).unwrap();
-                //the first write will create the file
-                fm.lock().unwrap().read_write(&blk, &mut p, true).unwrap();
-            }
+        let block_size = fm.lock().unwrap().block_size() as i32;
+
+        let (blk, latest_lsn) = if log_size == 0 {
+            let blk = BlockId::new(&log_file_name, 0);
+            init_block(&mut p, block_size, 0)?;
+            //the first write will create the file
+            fm.lock().unwrap().read_write(&blk, &mut p, true)?;
+            (blk, 0)
+        } else {
+            /*
+            read the last page of the log file, new records are appended
+            to it until it is full, their lsns go on from the records
+            already in the log
+            */
+            let blk = BlockId::new(log_file_name.as_str(), log_size - 1);
+            fm.lock().unwrap().read_write(&blk, &mut p, false)?;
+            let latest_lsn = latest_lsn_in_block(&mut p, block_size, &blk)?;
+            (blk, latest_lsn)
         };
 
-        let blk = BlockId::new(&log_file_name, 0);
-        let block_size = fm.lock().unwrap().block_size();
         let  log_mgr = LogMgr {
-            fm,
+            fm: fm.clone(),
             log_file: log_file_name.clone(),
-            log_buf: Arc::new(Mutex::new(log_buf.clone())),
-            latest_lsn: 0,
-            last_saved_lsn: 0,
-            current_blk: blk.clone(),
-
-            //for iterator
-            current_pos: 0,
-            iter_init: true,
-            iter_blk: blk,
-            buf_for_inter: vec![0u8; block_size as usize],
+            log_buf: Arc::new(Mutex::new(log_buf)),
+            latest_lsn,
+            last_saved_lsn: latest_lsn,
+            current_blk: blk,
+            iter: None,
+            group_commit: Arc::new(GroupCommit::new(fm.clone(), log_file_name.clone())),
         };
 
-        return log_mgr;
+        Ok(log_mgr)
     }
 
-   fn do_flush(&mut self) {
+   fn do_flush(&mut self) -> DbResult<()> {
         /*
         Write record info in buf onto disk
         */
         let mut log_buf = self.log_buf.lock().unwrap();
         let mut p = Page::from_buffer(&mut log_buf);
-        self.fm.lock().unwrap().read_write(&self.current_blk, &mut p, true).unwrap();
+        self.fm.lock().unwrap().read_write(&self.current_blk, &mut p, true)?;
         self.last_saved_lsn = self.latest_lsn;
+        self.group_commit.written(self.last_saved_lsn);
+        Ok(())
    }
 
-   pub fn flush(&mut self,lsn :u64) {
+   /*
+   write the log records up to the given lsn and sync them according to
+   the sync policy of the file manager. With group commit the sync is left
+   to the returned ticket, the caller unlocks the log manager and waits on
+   the ticket:
+        let ticket = lm.lock().unwrap().flush(lsn)?;
+        ticket.wait()?;
+   */
+   pub fn flush(&mut self,lsn :u64) -> DbResult<SyncTicket> {
+       //a buffer modified without log record asks for lsn u64::MAX
+       let lsn = lsn.min(self.latest_lsn);
        if lsn >= self.last_saved_lsn {
-           self.do_flush();
+           self.do_flush()?;
        }
+
+       let sync_policy = self.fm.lock().unwrap().sync_policy();
+       let mut ticket = SyncTicket {
+           group: None,
+           lsn,
+           window: Duration::ZERO,
+       };
+       match sync_policy {
+           SyncPolicy::Never => {}
+           SyncPolicy::Always => {
+               if !self.group_commit.is_durable(lsn) {
+                   self.fm.lock().unwrap().sync(&self.log_file)?;
+                   self.group_commit.synced(self.last_saved_lsn);
+               }
+           }
+           SyncPolicy::GroupCommit(window) => {
+               ticket.group = Some(self.group_commit.clone());
+               ticket.window = window;
+           }
+       }
+       Ok(ticket)
    }
 
-   fn append_new_block(&mut self) -> BlockId {
+   fn append_new_block(&mut self) -> DbResult<BlockId> {
       //append a block at the end of log file
       let mut log_buf = self.log_buf.lock().unwrap();
-      let blk = self.fm.lock().unwrap().append(self.log_file.clone()).unwrap();
+      let blk = self.fm.lock().unwrap().append(self.log_file.clone())?;
       let mut p = Page::from_buffer(&mut log_buf);
-      p.set_int(0, self.fm.lock().unwrap().block_size() as i32).unwrap();
-      self.fm.lock().unwrap().read_write(&blk, &mut p, true).unwrap();
-      return  blk;
+      init_block(&mut p, self.fm.lock().unwrap().block_size() as i32, self.latest_lsn)?;
+      self.fm.lock().unwrap().read_write(&blk, &mut p, true)?;
+      Ok(blk)
    }
 
-   fn get_boundary(&self) -> i32 {
+   fn get_boundary(&self) -> DbResult<i32> {
        let mut log_buf = self.log_buf.lock().unwrap();
        let mut p = Page::from_buffer(&mut log_buf);
-       let boundary = p.get_int(0).unwrap();
-       boundary
+       p.get_int(0)
    }
 
-   pub fn append(&mut self , log_rec: &Vec<u8>) -> u64 {
+   pub fn append(&mut self , log_rec: &[u8]) -> DbResult<u64> {
         /*
       \ when append log record to current page, we append it from the end to the beginning,
         for example for a clear page with length of 512 bytes, and the length of current record
@@ -126,21 +169,20 @@ impl LogMgr{
         By doing so, when we read the buffer from beginning to end, we get the latest 
         log record to oldest
         */ 
-        let mut boundary = self.get_boundary();
+        let mut boundary = self.get_boundary()?;
 
         let rec_size = log_rec.len() as i32;
         //4 bytes needs to record the length of log info length
         let bytes_needed = rec_size + 4;
-        if boundary - bytes_needed < 4 {
+        if boundary - bytes_needed < LOG_HEADER_SIZE {
             /*
-            we need the first 4 bytes to record the boundary value,
-            if the remaining room at the top not enough for 4 bytes,
-            then we need to write the page to clear room for the
-            current record
+            we need the first bytes of the page for the header, if the
+            remaining room at the top is not enough for the header, then
+            we need to write the page to clear room for the current record
             */
             
-            self.do_flush();
-            self.current_blk = self.append_new_block();
+            self.do_flush()?;
+            self.current_blk = self.append_new_block()?;
             //when append a new block, the boundary turns into the end of the page
             boundary = self.fm.lock().unwrap().block_size() as i32;
         }
@@ -148,23 +190,50 @@ impl LogMgr{
         let rec_pos = boundary - bytes_needed;
         let mut log_buf = self.log_buf.lock().unwrap();
         let mut p = Page::from_buffer(&mut log_buf);
-        p.set_bytes(rec_pos as usize, &log_rec).unwrap();
+        p.set_bytes(rec_pos as usize, log_rec)?;
         //set new boundary
-        p.set_int(0, rec_pos).unwrap();
+        p.set_int(0, rec_pos)?;
         self.latest_lsn += 1;
-        return self.latest_lsn;
+        Ok(self.latest_lsn)
    }
 
-   //for iterator
-   fn move_to_block(&mut self) {
-        let mut p = Page::from_buffer(&mut self.buf_for_inter);
-        self.fm.lock().unwrap().read_write(&self.iter_blk, &mut p, false).unwrap();
-        self.current_pos = p.get_int(0).unwrap();
+   /*
+   return an iterator visiting log records from the latest to the oldest,
+   the iterator reads blocks by itself, therefore the log manager can
+   still be used for appending while the iterator is alive
+   */
+   pub fn iterator(&mut self) -> DbResult<LogIterator> {
+        self.do_flush()?;
+        LogIterator::new(self.fm.clone(), self.current_blk.clone())
    }
 }
 
+//an empty log block, the records before it end at base_lsn
+fn init_block(p: &mut Page, block_size: i32, base_lsn: u64) -> DbResult<()> {
+    p.set_int(0, block_size)?;
+    p.set_raw_bytes(4, &base_lsn.to_be_bytes())
+}
+
+//lsn of the latest record in the block, the base lsn plus the records in the block
+fn latest_lsn_in_block(p: &mut Page, block_size: i32, blk: &BlockId) -> DbResult<u64> {
+    let corrupt = |e: DbError| DbError::CorruptLogRecord(format!("{}: {}", blk, e));
+    let boundary = p.get_int(0)?;
+    if boundary < LOG_HEADER_SIZE || boundary > block_size {
+        return Err(DbError::CorruptLogRecord(format!("invalid boundary:{} in log block {}", boundary, blk)));
+    }
+    let mut base_lsn = [0u8; 8];
+    base_lsn.copy_from_slice(&p.get_raw_bytes(4, 8)?);
+    let mut lsn = u64::from_be_bytes(base_lsn);
+    let mut pos = boundary as usize;
+    while pos < block_size as usize {
+        pos += 4 + p.get_bytes(pos).map_err(corrupt)?.len();
+        lsn += 1;
+    }
+    Ok(lsn)
+}
+
 impl Iterator for LogMgr {
-    type Item = Vec<u8>;
+    type Item = DbResult<Vec<u8>>;
     fn next(&mut self) -> Option<Self::Item> {
         /*
         iterator will visit record in reverse order, for example
@@ -172,25 +241,79 @@ impl Iterator for LogMgr {
         then the iterator will return:
         rec2, rec1, rec0
         */
-        if self.iter_init {
-            self.iter_init = false;
-            self.do_flush();
-            self.iter_blk = BlockId::new(&self.log_file, self.current_blk.number());
-            self.move_to_block();
+        if self.iter.is_none() {
+            match self.iterator() {
+                Ok(iter) => self.iter = Some(iter),
+                Err(e) => return Some(Err(e)),
+            }
         }
 
-        if self.current_pos == self.fm.lock().unwrap().block_size() as i32 && self.iter_blk.number() == 0 {
-            return None;
+        self.iter.as_mut().and_then(|iter| iter.next())
+    }
+}
+
+pub struct LogIterator {
+    fm: Arc<Mutex<FileMgr>>,
+    blk: BlockId,
+    buf: Vec<u8>,
+    current_pos: i32,
+    block_size: i32,
+}
+
+impl LogIterator {
+    fn new(fm: Arc<Mutex<FileMgr>>, blk: BlockId) -> DbResult<Self> {
+        let block_size = fm.lock().unwrap().block_size();
+        let mut iter = LogIterator {
+            fm,
+            blk,
+            buf: vec![0u8; block_size as usize],
+            current_pos: 0,
+            block_size: block_size as i32,
+        };
+        iter.move_to_block()?;
+        Ok(iter)
+    }
+
+    fn move_to_block(&mut self) -> DbResult<()> {
+        let mut p = Page::from_buffer(&mut self.buf);
+        self.fm.lock().unwrap().read_write(&self.blk, &mut p, false)?;
+        self.current_pos = p.get_int(0)?;
+        if self.current_pos < LOG_HEADER_SIZE || self.current_pos > self.block_size {
+            return Err(DbError::CorruptLogRecord(format!(
+                "invalid boundary:{} in log block {}", self.current_pos, self.blk
+            )));
         }
+        Ok(())
+    }
 
-        if self.current_pos == self.fm.lock().unwrap().block_size() as i32 {
-            self.iter_blk = BlockId::new(&self.log_file, self.iter_blk.number() - 1);
-            self.move_to_block();
+    fn next_record(&mut self) -> DbResult<Option<Vec<u8>>> {
+        if self.current_pos == self.block_size && self.blk.number() == 0 {
+            return Ok(None);
         }
 
-        let mut p = Page::from_buffer(&mut self.buf_for_inter);
-        let log_rec = p.get_bytes(self.current_pos as usize).unwrap();
+        if self.current_pos == self.block_size {
+            self.blk = BlockId::new(&self.blk.file_name(), self.blk.number() - 1);
+            self.move_to_block()?;
+        }
+
+        let mut p = Page::from_buffer(&mut self.buf);
+        let log_rec = p.get_bytes(self.current_pos as usize).map_err(|e| {
+            DbError::CorruptLogRecord(format!("record at pos {} of {}: {}", self.current_pos, self.blk, e))
+        })?;
         self.current_pos = self.current_pos + 4 + log_rec.len() as i32;
-        Some(log_rec)
+        Ok(Some(log_rec))
+    }
+}
+
+impl Iterator for LogIterator {
+    type Item = DbResult<Vec<u8>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.next_record().transpose();
+        if let Some(Err(_)) = result {
+            //can't go on after a corrupted block
+            self.current_pos = self.block_size;
+            self.blk = BlockId::new(&self.blk.file_name(), 0);
+        }
+        result
     }
-}
\ No newline at end of file
+}
@@ -3,6 +3,8 @@ use crate::file_mgr::*;
 use std::fs;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 static DIRECTORY : &str=  "./logtest";
 static LOGFILE: &str = "log_file.txt";
@@ -37,23 +39,23 @@ fn create_records(log_mgr :&mut LogMgr,start: u64, end: u64) {
         */
         let s = format!("record:{}", val);
         let rec = create_log_record(s, (val + 100) as i32);
-        let _ = log_mgr.append(&rec);
+        log_mgr.append(&rec).unwrap();
     }
 }
 
 #[test]
 fn test_log_mgr_add_records() {
     remove_dir();
-    let  file_mgr = FileMgr::new(DIRECTORY.to_string(), 400);
+    let  file_mgr = FileMgr::new(DIRECTORY.to_string(), 400).unwrap();
     let file_mgr_lock = Arc::new(Mutex::new(file_mgr));
-    let mut log_mgr = LogMgr::new(file_mgr_lock.clone(), LOGFILE.to_string());
+    let mut log_mgr = LogMgr::new(file_mgr_lock.clone(), LOGFILE.to_string()).unwrap();
     let start = 1;
     let mut end = 36;
     create_records(&mut log_mgr, start, end);
     
     for rec in log_mgr {
         end -= 1;
-        let mut record_buffer = rec.clone();
+        let mut record_buffer = rec.unwrap();
         let mut p = Page::from_buffer(&mut record_buffer);
         let s = p.get_string(0).unwrap();
         let s_should = format!("record:{}", end);
@@ -63,3 +65,136 @@ fn test_log_mgr_add_records() {
         assert_eq!(val, (end+100) as i32);
     }
 }
+
+#[test]
+fn test_lsn_continues_after_reopen() {
+    let directory = "./logreopentest";
+    if Path::new(directory).exists() {
+        fs::remove_dir_all(directory).unwrap();
+    }
+    let file_mgr_lock = Arc::new(Mutex::new(FileMgr::new(directory.to_string(), 400).unwrap()));
+    let mut log_mgr = LogMgr::new(file_mgr_lock.clone(), LOGFILE.to_string()).unwrap();
+    //the records take several blocks
+    create_records(&mut log_mgr, 1, 36);
+    let latest_lsn = log_mgr.append(&create_log_record("record:36".to_string(), 136)).unwrap();
+    assert_eq!(latest_lsn, 36);
+    log_mgr.flush(latest_lsn).unwrap().wait().unwrap();
+    drop(log_mgr);
+
+    let mut log_mgr = LogMgr::new(file_mgr_lock.clone(), LOGFILE.to_string()).unwrap();
+    let lsn = log_mgr.append(&create_log_record("record:37".to_string(), 137)).unwrap();
+    assert_eq!(lsn, latest_lsn + 1);
+    //reopened again after more blocks are written, every record is still there
+    create_records(&mut log_mgr, 38, 60);
+    let lsn = log_mgr.append(&create_log_record("record:60".to_string(), 160)).unwrap();
+    log_mgr.flush(lsn).unwrap().wait().unwrap();
+    drop(log_mgr);
+    let mut log_mgr = LogMgr::new(file_mgr_lock, LOGFILE.to_string()).unwrap();
+    assert_eq!(log_mgr.append(&create_log_record("record:61".to_string(), 161)).unwrap(), 61);
+    assert_eq!(log_mgr.count(), 61);
+    fs::remove_dir_all(directory).unwrap();
+}
+
+fn new_log_mgr(directory: &str, sync_policy: SyncPolicy) -> Arc<Mutex<LogMgr>> {
+    if Path::new(directory).exists() {
+        fs::remove_dir_all(directory).unwrap();
+    }
+    let mut file_mgr = FileMgr::new(directory.to_string(), 400).unwrap();
+    file_mgr.set_sync_policy(sync_policy);
+    let file_mgr_lock = Arc::new(Mutex::new(file_mgr));
+    Arc::new(Mutex::new(LogMgr::new(file_mgr_lock, LOGFILE.to_string()).unwrap()))
+}
+
+fn syncs(log_mgr: &Arc<Mutex<LogMgr>>) -> u64 {
+    log_mgr.lock().unwrap().fm.lock().unwrap().syncs()
+}
+
+//append one record and wait until it is on disk, as a commit does
+fn commit_record(log_mgr: &Mutex<LogMgr>, val: u64) {
+    let rec = create_log_record(format!("commit:{}", val), val as i32);
+    let lsn = log_mgr.lock().unwrap().append(&rec).unwrap();
+    let ticket = log_mgr.lock().unwrap().flush(lsn).unwrap();
+    ticket.wait().unwrap();
+}
+
+fn concurrent_commits(log_mgr: &Arc<Mutex<LogMgr>>, num_threads: u64, commits: u64) {
+    thread::scope(|s| {
+        for t in 0..num_threads {
+            s.spawn(move || {
+                for i in 0..commits {
+                    commit_record(log_mgr, t * commits + i);
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn test_sync_policy() {
+    let directory = "syncpolicytest_always";
+    let log_mgr = new_log_mgr(directory, SyncPolicy::Always);
+    for val in 0..5 {
+        commit_record(&log_mgr, val);
+    }
+    assert_eq!(syncs(&log_mgr), 5);
+    //records already on disk need no sync
+    let ticket = log_mgr.lock().unwrap().flush(3).unwrap();
+    ticket.wait().unwrap();
+    assert_eq!(syncs(&log_mgr), 5);
+    fs::remove_dir_all(directory).unwrap();
+
+    let directory = "syncpolicytest_never";
+    let log_mgr = new_log_mgr(directory, SyncPolicy::Never);
+    concurrent_commits(&log_mgr, 4, 5);
+    assert_eq!(syncs(&log_mgr), 0);
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_group_commit_shares_sync() {
+    let directory = "groupcommittest";
+    let log_mgr = new_log_mgr(directory, SyncPolicy::GroupCommit(Duration::from_millis(50)));
+    concurrent_commits(&log_mgr, 8, 1);
+    //the threads come within one window, a few rounds at most
+    let group_syncs = syncs(&log_mgr);
+    assert!(group_syncs >= 1);
+    assert!(group_syncs < 8, "8 commits take {} syncs", group_syncs);
+
+    //every committed record is readable
+    let records = log_mgr.lock().unwrap().iterator().unwrap().count();
+    assert_eq!(records, 8);
+    fs::remove_dir_all(directory).unwrap();
+}
+
+/*
+commits per second under each sync policy, run it with
+cargo test bench_sync_policy -- --ignored --nocapture
+*/
+#[test]
+#[ignore]
+fn bench_sync_policy() {
+    let num_threads = 8;
+    let commits = 50;
+    for (name, sync_policy) in [
+        ("always", SyncPolicy::Always),
+        ("group commit 1ms", SyncPolicy::GroupCommit(Duration::from_millis(1))),
+        ("group commit 5ms", SyncPolicy::GroupCommit(Duration::from_millis(5))),
+        ("never", SyncPolicy::Never),
+    ] {
+        let directory = "syncpolicybench";
+        let log_mgr = new_log_mgr(directory, sync_policy);
+        let start = Instant::now();
+        concurrent_commits(&log_mgr, num_threads, commits);
+        let elapsed = start.elapsed();
+        let total = num_threads * commits;
+        println!(
+            "{:>18}: {:>6} commits in {:>8.1?}, {:>10.0} commits/s, {:>4} syncs",
+            name,
+            total,
+            elapsed,
+            total as f64 / elapsed.as_secs_f64(),
+            syncs(&log_mgr)
+        );
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -1,16 +1,27 @@
+pub mod error;
 pub mod file_mgr;
 pub mod log_mgr;
 pub mod buf_mgr;
+pub mod tx_mgr;
+pub mod record_mgr;
+pub mod metadata_mgr;
+pub mod query;
+pub mod parse;
+pub mod plan;
+pub mod index;
+pub mod server;
 use std::sync::{Arc, Mutex};
 
+use error::DbResult;
 use file_mgr::*;
 use log_mgr::*;
 use buf_mgr::*;
-fn main() {
-   let mut file_mgr = FileMgr::new("filetest".to_string(), 
-    512);
+fn main() -> DbResult<()> {
+   let file_mgr = FileMgr::new("filetest".to_string(),
+    512)?;
    let file_mgr_lock = Arc::new(Mutex::new(file_mgr));
-   let log_mgr = LogMgr::new(file_mgr_lock.clone(), "logfile".to_string());
+   let log_mgr = LogMgr::new(file_mgr_lock.clone(), "logfile".to_string())?;
    let log_mgr_lock = Arc::new(Mutex::new(log_mgr));
-   let _ = BufferMgr::new(file_mgr_lock.clone(), log_mgr_lock.clone(), 3);
+   let _ = BufferMgr::new(file_mgr_lock.clone(), log_mgr_lock.clone(), 3, ReplacementStrategy::Lru);
+   Ok(())
 }
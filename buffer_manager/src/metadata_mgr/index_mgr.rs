@@ -0,0 +1,220 @@
+use super::stat_mgr::{StatInfo, StatMgr};
+use super::table_mgr::{TableMgr, MAX_NAME};
+use crate::error::{DbError, DbResult};
+use crate::index::{BTreeIndex, HashIndex, Index, IndexType};
+use crate::query::{Constant, Scan, UpdateScan};
+use crate::record_mgr::{FieldType, Layout, RecordId, Schema, TableScan};
+use crate::tx_mgr::Transaction;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+//indexes are saved in idxcat, one record for each index
+pub struct IndexMgr {
+    tbl_mgr: Arc<TableMgr>,
+    stat_mgr: Arc<StatMgr>,
+}
+
+impl IndexMgr {
+    pub fn new(
+        is_new: bool,
+        tbl_mgr: Arc<TableMgr>,
+        stat_mgr: Arc<StatMgr>,
+        tx: &Arc<Mutex<Transaction>>,
+    ) -> DbResult<Self> {
+        if is_new {
+            let mut schema = Schema::new();
+            schema.add_string_field("indexname", MAX_NAME);
+            schema.add_string_field("tablename", MAX_NAME);
+            schema.add_string_field("fieldname", MAX_NAME);
+            schema.add_int_field("indextype");
+            tbl_mgr.create_table("idxcat", &schema, tx)?;
+        }
+        Ok(IndexMgr { tbl_mgr, stat_mgr })
+    }
+
+    pub fn create_index(
+        &self,
+        idx_name: &str,
+        tbl_name: &str,
+        fld_name: &str,
+        idx_type: IndexType,
+        tx: &Arc<Mutex<Transaction>>,
+    ) -> DbResult<()> {
+        //the indexed field must be in the table
+        let tbl_layout = self.tbl_mgr.get_layout(tbl_name, tx)?;
+        if !tbl_layout.schema().has_field(fld_name) {
+            return Err(DbError::FieldNotFound(fld_name.to_string()));
+        }
+        let layout = Arc::new(self.tbl_mgr.get_layout("idxcat", tx)?);
+        let mut ts = TableScan::new(tx.clone(), "idxcat", layout)?;
+        /*
+        index names are used for the index files, and indexes are looked up
+        by the field, so both can only have one index
+        */
+        while ts.next()? {
+            let same_field = ts.get_string("tablename")? == tbl_name && ts.get_string("fieldname")? == fld_name;
+            if same_field || ts.get_string("indexname")? == idx_name {
+                return Err(DbError::IndexExists(ts.get_string("indexname")?));
+            }
+        }
+        ts.insert()?;
+        ts.set_string("indexname", idx_name)?;
+        ts.set_string("tablename", tbl_name)?;
+        ts.set_string("fieldname", fld_name)?;
+        ts.set_int("indextype", idx_type.code())
+    }
+
+    pub fn get_index_info(&self, tbl_name: &str, tx: &Arc<Mutex<Transaction>>) -> DbResult<HashMap<String, IndexInfo>> {
+        let mut result = HashMap::new();
+        let layout = Arc::new(self.tbl_mgr.get_layout("idxcat", tx)?);
+        let mut ts = TableScan::new(tx.clone(), "idxcat", layout)?;
+        let mut tbl_info = None;
+        while ts.next()? {
+            if ts.get_string("tablename")? != tbl_name {
+                continue;
+            }
+            if tbl_info.is_none() {
+                let tbl_layout = self.tbl_mgr.get_layout(tbl_name, tx)?;
+                let si = self.stat_mgr.get_stat_info(tbl_name, &tbl_layout, tx)?;
+                tbl_info = Some((tbl_layout, si));
+            }
+            let (tbl_layout, si) = tbl_info.as_ref().unwrap();
+            let idx_name = ts.get_string("indexname")?;
+            let fld_name = ts.get_string("fieldname")?;
+            let code = ts.get_int("indextype")?;
+            let idx_type = IndexType::from_code(code)
+                .ok_or_else(|| DbError::CorruptCatalog(format!("index {} has unknown type {}", idx_name, code)))?;
+            let ii = IndexInfo::new(tx.clone(), &idx_name, &fld_name, tbl_layout.schema().clone(), si.clone())
+                .with_index_type(idx_type);
+            result.insert(fld_name, ii);
+        }
+        Ok(result)
+    }
+}
+
+/*
+what a planner needs to know about an index, the index records are saved
+with the layout given by idx_layout:
+    block: block number of the indexed record
+    slot: slot of the indexed record
+    dataval: value of the indexed field
+*/
+#[derive(Clone)]
+pub struct IndexInfo {
+    tx: Arc<Mutex<Transaction>>,
+    idx_name: String,
+    fld_name: String,
+    tbl_schema: Schema,
+    idx_layout: Arc<Layout>,
+    idx_type: IndexType,
+    si: StatInfo,
+}
+
+impl IndexInfo {
+    pub fn new(tx: Arc<Mutex<Transaction>>, idx_name: &str, fld_name: &str, tbl_schema: Schema, si: StatInfo) -> Self {
+        let idx_layout = Arc::new(Self::create_idx_layout(fld_name, &tbl_schema));
+        IndexInfo {
+            tx,
+            idx_name: idx_name.to_string(),
+            fld_name: fld_name.to_string(),
+            tbl_schema,
+            idx_layout,
+            idx_type: IndexType::default(),
+            si,
+        }
+    }
+
+    pub fn with_index_type(mut self, idx_type: IndexType) -> Self {
+        self.idx_type = idx_type;
+        self
+    }
+
+    fn create_idx_layout(fld_name: &str, tbl_schema: &Schema) -> Layout {
+        let mut schema = Schema::new();
+        schema.add_int_field("block");
+        schema.add_int_field("slot");
+        match tbl_schema.field_type(fld_name) {
+            Some(FieldType::Varchar) => {
+                schema.add_string_field("dataval", tbl_schema.length(fld_name).unwrap_or(0))
+            }
+            _ => schema.add_int_field("dataval"),
+        }
+        Layout::new(schema)
+    }
+
+    pub fn index_name(&self) -> &str {
+        &self.idx_name
+    }
+
+    pub fn field_name(&self) -> &str {
+        &self.fld_name
+    }
+
+    pub fn table_schema(&self) -> &Schema {
+        &self.tbl_schema
+    }
+
+    pub fn index_layout(&self) -> &Layout {
+        &self.idx_layout
+    }
+
+    pub fn index_type(&self) -> IndexType {
+        self.idx_type
+    }
+
+    pub fn open(&self) -> DbResult<Box<dyn Index>> {
+        match self.idx_type {
+            IndexType::Hash => Ok(Box::new(HashIndex::new(self.tx.clone(), &self.idx_name, self.idx_layout.clone()))),
+            IndexType::BTree => Ok(Box::new(BTreeIndex::new(self.tx.clone(), &self.idx_name, self.idx_layout.clone())?)),
+        }
+    }
+
+    /*
+    fill the new index with the records of its table, a b-tree is bulk
+    loaded from the records sorted by value, a hash index takes them one
+    by one as the buckets are not ordered anyway
+    */
+    pub fn load(&self, mut records: Vec<(Constant, RecordId)>) -> DbResult<()> {
+        match self.idx_type {
+            IndexType::Hash => {
+                let mut idx = self.open()?;
+                for (val, rid) in records {
+                    idx.insert(&val, rid)?;
+                }
+                idx.close();
+            }
+            IndexType::BTree => {
+                records.sort_by(|(val1, _), (val2, _)| val1.cmp(val2));
+                let mut idx = BTreeIndex::new(self.tx.clone(), &self.idx_name, self.idx_layout.clone())?;
+                idx.bulk_load(records)?;
+                idx.close();
+            }
+        }
+        Ok(())
+    }
+
+    //block accesses to find the index records of one value, the data records are not counted
+    pub fn blocks_accessed(&self) -> u64 {
+        let block_size = self.tx.lock().unwrap().block_size();
+        let rpb = (block_size as usize / self.idx_layout.slot_size()).max(1);
+        let num_blocks = self.si.records_output() / rpb;
+        match self.idx_type {
+            IndexType::Hash => HashIndex::search_cost(num_blocks as u64),
+            IndexType::BTree => BTreeIndex::search_cost(num_blocks as u64, rpb),
+        }
+    }
+
+    //records having one value of the indexed field, values are taken as evenly spread
+    pub fn records_output(&self) -> usize {
+        self.si.records_output() / self.si.distinct_values(&self.fld_name)
+    }
+
+    pub fn distinct_values(&self, field_name: &str) -> usize {
+        if field_name == self.fld_name {
+            1
+        } else {
+            self.si.distinct_values(field_name)
+        }
+    }
+}
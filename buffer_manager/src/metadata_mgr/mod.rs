@@ -0,0 +1,96 @@
+pub mod index_mgr;
+pub mod stat_mgr;
+pub mod table_mgr;
+#[cfg(test)]
+pub mod test;
+pub mod view_mgr;
+
+pub use index_mgr::{IndexInfo, IndexMgr};
+pub use stat_mgr::{StatInfo, StatMgr, REFRESH_LIMIT};
+pub use table_mgr::{TableMgr, MAX_NAME};
+pub use view_mgr::{ViewMgr, MAX_VIEWDEF};
+
+use crate::error::{DbError, DbResult};
+use crate::index::IndexType;
+use crate::record_mgr::{Layout, Schema};
+use crate::tx_mgr::Transaction;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/*
+one place for clients to reach the catalog, the catalog is kept in
+tables like any other data:
+    tblcat: tblname, slotsize
+    fldcat: tblname, fldname, type, length, offset
+    viewcat: viewname, viewdef
+    idxcat: indexname, tablename, fieldname
+the catalog tables are created when the database is new, statistics
+are not saved, they are computed from the tables when the system starts
+*/
+pub struct MetadataMgr {
+    tbl_mgr: Arc<TableMgr>,
+    view_mgr: ViewMgr,
+    stat_mgr: Arc<StatMgr>,
+    idx_mgr: IndexMgr,
+}
+
+impl MetadataMgr {
+    pub fn new(is_new: bool, tx: &Arc<Mutex<Transaction>>) -> DbResult<Self> {
+        let tbl_mgr = Arc::new(TableMgr::new(is_new, tx)?);
+        let view_mgr = ViewMgr::new(is_new, tbl_mgr.clone(), tx)?;
+        let stat_mgr = Arc::new(StatMgr::new(tbl_mgr.clone(), tx)?);
+        let idx_mgr = IndexMgr::new(is_new, tbl_mgr.clone(), stat_mgr.clone(), tx)?;
+        Ok(MetadataMgr {
+            tbl_mgr,
+            view_mgr,
+            stat_mgr,
+            idx_mgr,
+        })
+    }
+
+    //a table can't take the name of a view either, the view would hide it
+    pub fn create_table(&self, tbl_name: &str, schema: &Schema, tx: &Arc<Mutex<Transaction>>) -> DbResult<()> {
+        if self.view_mgr.get_view_def(tbl_name, tx)?.is_some() {
+            return Err(DbError::TableExists(tbl_name.to_string()));
+        }
+        self.tbl_mgr.create_table(tbl_name, schema, tx)
+    }
+
+    pub fn get_layout(&self, tbl_name: &str, tx: &Arc<Mutex<Transaction>>) -> DbResult<Layout> {
+        self.tbl_mgr.get_layout(tbl_name, tx)
+    }
+
+    pub fn create_view(&self, view_name: &str, view_def: &str, tx: &Arc<Mutex<Transaction>>) -> DbResult<()> {
+        self.view_mgr.create_view(view_name, view_def, tx)
+    }
+
+    pub fn get_view_def(&self, view_name: &str, tx: &Arc<Mutex<Transaction>>) -> DbResult<Option<String>> {
+        self.view_mgr.get_view_def(view_name, tx)
+    }
+
+    pub fn get_stat_info(&self, tbl_name: &str, layout: &Layout, tx: &Arc<Mutex<Transaction>>) -> DbResult<StatInfo> {
+        self.stat_mgr.get_stat_info(tbl_name, layout, tx)
+    }
+
+    //records of some table are modified, statistics are refreshed after enough of them
+    pub fn updated(&self, count: usize) {
+        self.stat_mgr.updated(count)
+    }
+
+    pub fn create_index(
+        &self,
+        idx_name: &str,
+        tbl_name: &str,
+        fld_name: &str,
+        idx_type: IndexType,
+        tx: &Arc<Mutex<Transaction>>,
+    ) -> DbResult<()> {
+        self.idx_mgr.create_index(idx_name, tbl_name, fld_name, idx_type, tx)
+    }
+
+    //indexes of the table keyed by the indexed field
+    pub fn get_index_info(&self, tbl_name: &str, tx: &Arc<Mutex<Transaction>>) -> DbResult<HashMap<String, IndexInfo>> {
+        self.idx_mgr.get_index_info(tbl_name, tx)
+    }
+}
@@ -0,0 +1,195 @@
+use super::table_mgr::TableMgr;
+use crate::error::DbResult;
+use crate::query::Scan;
+use crate::record_mgr::{FieldType, Layout, TableScan};
+use crate::tx_mgr::Transaction;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+//statistics of all tables are computed again after this many updates
+pub const REFRESH_LIMIT: usize = 100;
+
+/*
+statistics of one table, the planner uses them to estimate the cost of
+a plan, they are only refreshed from time to time, so they may not be
+exact
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatInfo {
+    num_blocks: u64,
+    num_recs: usize,
+    distinct: HashMap<String, usize>,
+}
+
+impl StatInfo {
+    pub fn new(num_blocks: u64, num_recs: usize, distinct: HashMap<String, usize>) -> Self {
+        StatInfo {
+            num_blocks,
+            num_recs,
+            distinct,
+        }
+    }
+
+    pub fn blocks_accessed(&self) -> u64 {
+        self.num_blocks
+    }
+
+    pub fn records_output(&self) -> usize {
+        self.num_recs
+    }
+
+    /*
+    never less than 1 so that callers can divide by it, for a field not
+    in the table we know nothing and take every record as different
+    */
+    pub fn distinct_values(&self, field_name: &str) -> usize {
+        self.distinct
+            .get(field_name)
+            .copied()
+            .unwrap_or(self.num_recs)
+            .max(1)
+    }
+}
+
+struct StatState {
+    table_stats: HashMap<String, StatInfo>,
+    num_updates: usize,
+}
+
+/*
+keep statistics of every table in memory, they are computed by scanning
+the whole table. Clients report their modifications with updated, after
+REFRESH_LIMIT of them the statistics of all tables are computed again.
+
+the scans run in an unlocked reader committed right after, they take no
+block locks, so a query on one table never holds up writers of the others
+or waits for them, the statistics are only estimates anyway. The mutex is
+not held during a scan either, other threads keep the old statistics
+meanwhile
+*/
+pub struct StatMgr {
+    tbl_mgr: Arc<TableMgr>,
+    refresh_limit: usize,
+    state: Mutex<StatState>,
+}
+
+impl StatMgr {
+    pub fn new(tbl_mgr: Arc<TableMgr>, tx: &Arc<Mutex<Transaction>>) -> DbResult<Self> {
+        Self::with_refresh_limit(tbl_mgr, REFRESH_LIMIT, tx)
+    }
+
+    pub fn with_refresh_limit(
+        tbl_mgr: Arc<TableMgr>,
+        refresh_limit: usize,
+        tx: &Arc<Mutex<Transaction>>,
+    ) -> DbResult<Self> {
+        let stat_mgr = StatMgr {
+            tbl_mgr,
+            refresh_limit,
+            state: Mutex::new(StatState {
+                table_stats: HashMap::new(),
+                num_updates: 0,
+            }),
+        };
+        stat_mgr.refresh_statistics(tx)?;
+        Ok(stat_mgr)
+    }
+
+    pub fn get_stat_info(&self, tbl_name: &str, layout: &Layout, tx: &Arc<Mutex<Transaction>>) -> DbResult<StatInfo> {
+        let mut state = self.state.lock().unwrap();
+        if state.num_updates >= self.refresh_limit {
+            //this thread refreshes, the others keep the old statistics meanwhile
+            let num_updates = std::mem::take(&mut state.num_updates);
+            drop(state);
+            if let Err(err) = self.refresh_statistics(tx) {
+                self.state.lock().unwrap().num_updates += num_updates;
+                return Err(err);
+            }
+            state = self.state.lock().unwrap();
+        }
+        if let Some(si) = state.table_stats.get(tbl_name) {
+            return Ok(si.clone());
+        }
+        drop(state);
+        //table created after the latest refresh
+        let si = with_reader(tx, |reader| calc_table_stats(tbl_name, layout, reader))?;
+        self.state
+            .lock()
+            .unwrap()
+            .table_stats
+            .insert(tbl_name.to_string(), si.clone());
+        Ok(si)
+    }
+
+    //called after records are inserted, deleted or modified
+    pub fn updated(&self, count: usize) {
+        self.state.lock().unwrap().num_updates += count;
+    }
+
+    fn refresh_statistics(&self, tx: &Arc<Mutex<Transaction>>) -> DbResult<()> {
+        let table_stats = with_reader(tx, |reader| self.calc_all_stats(reader))?;
+        self.state.lock().unwrap().table_stats = table_stats;
+        Ok(())
+    }
+
+    fn calc_all_stats(&self, tx: &Arc<Mutex<Transaction>>) -> DbResult<HashMap<String, StatInfo>> {
+        let mut tbl_names = Vec::new();
+        let tcat_layout = Arc::new(self.tbl_mgr.get_layout("tblcat", tx)?);
+        let mut tcat = TableScan::new(tx.clone(), "tblcat", tcat_layout)?;
+        while tcat.next()? {
+            tbl_names.push(tcat.get_string("tblname")?);
+        }
+        tcat.close();
+
+        let mut table_stats = HashMap::new();
+        for tbl_name in tbl_names {
+            let layout = self.tbl_mgr.get_layout(&tbl_name, tx)?;
+            let si = calc_table_stats(&tbl_name, &layout, tx)?;
+            table_stats.insert(tbl_name, si);
+        }
+        Ok(table_stats)
+    }
+}
+
+//f reads in an unlocked reader of the transaction, the reader only reads and is committed at once
+fn with_reader<T>(
+    tx: &Arc<Mutex<Transaction>>,
+    f: impl FnOnce(&Arc<Mutex<Transaction>>) -> DbResult<T>,
+) -> DbResult<T> {
+    let reader = Arc::new(Mutex::new(tx.lock().unwrap().unlocked_reader()?));
+    let result = f(&reader);
+    reader.lock().unwrap().commit()?;
+    result
+}
+
+fn calc_table_stats(tbl_name: &str, layout: &Layout, tx: &Arc<Mutex<Transaction>>) -> DbResult<StatInfo> {
+    let fields = layout.schema().fields().to_vec();
+    let mut values: Vec<HashSet<String>> = vec![HashSet::new(); fields.len()];
+    let mut num_recs = 0;
+
+    //opening a scan on an empty table would append a block to it
+    let num_blocks = tx.lock().unwrap().size(&format!("{}.tbl", tbl_name))?;
+    if num_blocks == 0 {
+        return Ok(StatInfo::new(0, 0, HashMap::new()));
+    }
+    let mut ts = TableScan::new(tx.clone(), tbl_name, Arc::new(layout.clone()))?;
+    while ts.next()? {
+        num_recs += 1;
+        for (fld_name, seen) in fields.iter().zip(values.iter_mut()) {
+            let val = match layout.schema().field_type(fld_name) {
+                Some(FieldType::Integer) => ts.get_int(fld_name)?.to_string(),
+                _ => ts.get_string(fld_name)?,
+            };
+            seen.insert(val);
+        }
+    }
+    ts.close();
+
+    let distinct = fields
+        .into_iter()
+        .zip(values)
+        .map(|(fld_name, seen)| (fld_name, seen.len()))
+        .collect();
+    Ok(StatInfo::new(num_blocks, num_recs, distinct))
+}
@@ -0,0 +1,113 @@
+use crate::error::{DbError, DbResult};
+use crate::query::{Scan, UpdateScan};
+use crate::record_mgr::{FieldType, Layout, Schema, TableScan};
+use crate::tx_mgr::Transaction;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+//max length of table and field names
+pub const MAX_NAME: usize = 16;
+
+/*
+keep the layout of every table in the catalog, tblcat has one record for
+each table with its slot size, fldcat has one record for each field with
+its type, length and offset in the slot
+*/
+pub struct TableMgr {
+    tcat_layout: Arc<Layout>,
+    fcat_layout: Arc<Layout>,
+}
+
+impl TableMgr {
+    pub fn new(is_new: bool, tx: &Arc<Mutex<Transaction>>) -> DbResult<Self> {
+        let mut tcat_schema = Schema::new();
+        tcat_schema.add_string_field("tblname", MAX_NAME);
+        tcat_schema.add_int_field("slotsize");
+
+        let mut fcat_schema = Schema::new();
+        fcat_schema.add_string_field("tblname", MAX_NAME);
+        fcat_schema.add_string_field("fldname", MAX_NAME);
+        fcat_schema.add_int_field("type");
+        fcat_schema.add_int_field("length");
+        fcat_schema.add_int_field("offset");
+
+        let tbl_mgr = TableMgr {
+            tcat_layout: Arc::new(Layout::new(tcat_schema.clone())),
+            fcat_layout: Arc::new(Layout::new(fcat_schema.clone())),
+        };
+        //the catalog tables describe themselves as well
+        if is_new {
+            tbl_mgr.create_table("tblcat", &tcat_schema, tx)?;
+            tbl_mgr.create_table("fldcat", &fcat_schema, tx)?;
+        }
+        Ok(tbl_mgr)
+    }
+
+    pub fn create_table(&self, tbl_name: &str, schema: &Schema, tx: &Arc<Mutex<Transaction>>) -> DbResult<()> {
+        //a second set of catalog records would merge both schemas under the name
+        if self.slot_size(tbl_name, tx)?.is_some() {
+            return Err(DbError::TableExists(tbl_name.to_string()));
+        }
+        let layout = Layout::new(schema.clone());
+
+        let mut tcat = TableScan::new(tx.clone(), "tblcat", self.tcat_layout.clone())?;
+        tcat.insert()?;
+        tcat.set_string("tblname", tbl_name)?;
+        tcat.set_int("slotsize", layout.slot_size() as i32)?;
+        tcat.close();
+
+        let mut fcat = TableScan::new(tx.clone(), "fldcat", self.fcat_layout.clone())?;
+        for fld_name in schema.fields() {
+            fcat.insert()?;
+            fcat.set_string("tblname", tbl_name)?;
+            fcat.set_string("fldname", fld_name)?;
+            //fields of the schema always have type and offset
+            fcat.set_int("type", schema.field_type(fld_name).unwrap().code())?;
+            fcat.set_int("length", schema.length(fld_name).unwrap() as i32)?;
+            fcat.set_int("offset", layout.offset(fld_name).unwrap() as i32)?;
+        }
+        Ok(())
+    }
+
+    pub fn table_exists(&self, tbl_name: &str, tx: &Arc<Mutex<Transaction>>) -> DbResult<bool> {
+        Ok(self.slot_size(tbl_name, tx)?.is_some())
+    }
+
+    //slot size of the table in tblcat, None if the table is not there
+    fn slot_size(&self, tbl_name: &str, tx: &Arc<Mutex<Transaction>>) -> DbResult<Option<usize>> {
+        let mut slot_size = None;
+        let mut tcat = TableScan::new(tx.clone(), "tblcat", self.tcat_layout.clone())?;
+        while tcat.next()? {
+            if tcat.get_string("tblname")? == tbl_name {
+                slot_size = Some(tcat.get_int("slotsize")? as usize);
+                break;
+            }
+        }
+        tcat.close();
+        Ok(slot_size)
+    }
+
+    pub fn get_layout(&self, tbl_name: &str, tx: &Arc<Mutex<Transaction>>) -> DbResult<Layout> {
+        let slot_size = self
+            .slot_size(tbl_name, tx)?
+            .ok_or_else(|| DbError::TableNotFound(tbl_name.to_string()))?;
+
+        let mut schema = Schema::new();
+        let mut offsets = HashMap::new();
+        let mut fcat = TableScan::new(tx.clone(), "fldcat", self.fcat_layout.clone())?;
+        while fcat.next()? {
+            if fcat.get_string("tblname")? != tbl_name {
+                continue;
+            }
+            let fld_name = fcat.get_string("fldname")?;
+            let code = fcat.get_int("type")?;
+            let field_type = FieldType::from_code(code).ok_or_else(|| {
+                DbError::CorruptCatalog(format!("unknown type:{} of field:{} in table:{}", code, fld_name, tbl_name))
+            })?;
+            schema.add_field(&fld_name, field_type, fcat.get_int("length")? as usize);
+            offsets.insert(fld_name, fcat.get_int("offset")? as usize);
+        }
+        Ok(Layout::from_metadata(schema, offsets, slot_size))
+    }
+}
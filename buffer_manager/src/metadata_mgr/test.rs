@@ -0,0 +1,252 @@
+use super::*;
+use crate::error::{DbError, DbResult};
+use crate::index::IndexType;
+use crate::query::UpdateScan;
+use crate::record_mgr::{FieldType, Layout, Schema, TableScan};
+use crate::server::RustDB;
+use crate::tx_mgr::Transaction;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+fn remove_dir(directory: &str) {
+    if Path::new(directory).exists() {
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
+
+fn student_schema() -> Schema {
+    let mut schema = Schema::new();
+    schema.add_int_field("sid");
+    schema.add_string_field("sname", 10);
+    schema.add_int_field("majorid");
+    schema
+}
+
+#[test]
+fn test_catalog_describes_itself() {
+    let directory = "catalogtest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let mdm = db.metadata_mgr();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+
+    let layout = mdm.get_layout("tblcat", &tx).unwrap();
+    assert_eq!(layout.schema().fields(), ["tblname".to_string(), "slotsize".to_string()]);
+    assert_eq!(layout.schema().length("tblname"), Some(MAX_NAME));
+    let layout = mdm.get_layout("viewcat", &tx).unwrap();
+    assert_eq!(layout.schema().length("viewdef"), Some(MAX_VIEWDEF));
+    assert!(mdm.get_layout("fldcat", &tx).is_ok());
+    assert!(mdm.get_layout("idxcat", &tx).is_ok());
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_table_metadata_survives_restart() {
+    let directory = "tablemetatest";
+    remove_dir(directory);
+    let schema = student_schema();
+    {
+        let db = RustDB::new(directory, 400, 8).unwrap();
+        let mdm = db.metadata_mgr();
+        let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+        mdm.create_table("student", &schema, &tx).unwrap();
+        assert_eq!(mdm.get_layout("student", &tx).unwrap(), Layout::new(schema.clone()));
+        tx.lock().unwrap().commit().unwrap();
+    }
+
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let mdm = db.metadata_mgr();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let layout = mdm.get_layout("student", &tx).unwrap();
+    assert_eq!(layout, Layout::new(schema.clone()));
+    assert_eq!(layout.schema().field_type("sname"), Some(FieldType::Varchar));
+    assert!(matches!(mdm.get_layout("course", &tx), Err(DbError::TableNotFound(name)) if name == "course"));
+
+    //a second create table adds no catalog records
+    let mut other = Schema::new();
+    other.add_int_field("cid");
+    let err = mdm.create_table("student", &other, &tx);
+    assert!(matches!(err, Err(DbError::TableExists(name)) if name == "student"));
+    assert_eq!(mdm.get_layout("student", &tx).unwrap(), Layout::new(schema));
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_rolled_back_table_is_gone() {
+    let directory = "tablerollbacktest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let mdm = db.metadata_mgr();
+
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    mdm.create_table("student", &student_schema(), &tx).unwrap();
+    tx.lock().unwrap().rollback().unwrap();
+
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    assert!(matches!(mdm.get_layout("student", &tx), Err(DbError::TableNotFound(_))));
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_view_metadata() {
+    let directory = "viewmetatest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let mdm = db.metadata_mgr();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+
+    let view_def = "select sname from student where majorid = 10";
+    mdm.create_view("mathstudents", view_def, &tx).unwrap();
+    assert_eq!(mdm.get_view_def("mathstudents", &tx).unwrap().as_deref(), Some(view_def));
+    assert_eq!(mdm.get_view_def("nosuchview", &tx).unwrap(), None);
+
+    //views and tables share one name space
+    let err = mdm.create_view("mathstudents", "select sid from student", &tx);
+    assert!(matches!(err, Err(DbError::TableExists(_))));
+    mdm.create_table("student", &student_schema(), &tx).unwrap();
+    let err = mdm.create_view("student", view_def, &tx);
+    assert!(matches!(err, Err(DbError::TableExists(_))));
+    let err = mdm.create_table("mathstudents", &student_schema(), &tx);
+    assert!(matches!(err, Err(DbError::TableExists(_))));
+    assert_eq!(mdm.get_view_def("student", &tx).unwrap(), None);
+
+    let too_long = "x".repeat(MAX_VIEWDEF + 1);
+    assert!(matches!(
+        mdm.create_view("longview", &too_long, &tx),
+        Err(DbError::FieldTooLong { .. })
+    ));
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+fn insert_students(tx: &Arc<Mutex<Transaction>>, layout: Layout, from: i32, to: i32) {
+    let mut ts = TableScan::new(tx.clone(), "student", Arc::new(layout)).unwrap();
+    for i in from..to {
+        ts.insert().unwrap();
+        ts.set_int("sid", i).unwrap();
+        ts.set_string("sname", &format!("s{}", i % 10)).unwrap();
+        ts.set_int("majorid", i % 4).unwrap();
+    }
+}
+
+#[test]
+fn test_stat_info() {
+    let directory = "statinfotest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let mdm = db.metadata_mgr();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    mdm.create_table("student", &student_schema(), &tx).unwrap();
+    let layout = mdm.get_layout("student", &tx).unwrap();
+    insert_students(&tx, layout.clone(), 0, 60);
+
+    let si = mdm.get_stat_info("student", &layout, &tx).unwrap();
+    //26 bytes per slot, 15 records in one block
+    assert_eq!(si.records_output(), 60);
+    assert_eq!(si.blocks_accessed(), 4);
+    assert_eq!(si.distinct_values("sid"), 60);
+    assert_eq!(si.distinct_values("sname"), 10);
+    assert_eq!(si.distinct_values("majorid"), 4);
+
+    //cached until enough updates are reported
+    insert_students(&tx, layout.clone(), 60, 80);
+    assert_eq!(mdm.get_stat_info("student", &layout, &tx).unwrap(), si);
+    mdm.updated(REFRESH_LIMIT);
+    let si = mdm.get_stat_info("student", &layout, &tx).unwrap();
+    assert_eq!(si.records_output(), 80);
+    assert_eq!(si.distinct_values("sid"), 80);
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_stat_refresh_does_not_block_other_tx() {
+    let directory = "statrefreshtest";
+    remove_dir(directory);
+    let db = RustDB::with_lock_wait(directory, 400, 8, Duration::from_secs(2)).unwrap();
+    let mdm = db.metadata_mgr();
+    let tx1 = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    mdm.create_table("student", &student_schema(), &tx1).unwrap();
+    let layout = mdm.get_layout("student", &tx1).unwrap();
+    insert_students(&tx1, layout.clone(), 0, 60);
+    let mut dept_schema = Schema::new();
+    dept_schema.add_int_field("did");
+    mdm.create_table("dept", &dept_schema, &tx1).unwrap();
+    let dept_layout = Arc::new(mdm.get_layout("dept", &tx1).unwrap());
+    let insert_dept = |tx: &Arc<Mutex<Transaction>>, did: i32| -> DbResult<()> {
+        let mut ts = TableScan::new(tx.clone(), "dept", dept_layout.clone())?;
+        ts.insert()?;
+        ts.set_int("did", did)
+    };
+    insert_dept(&tx1, 10).unwrap();
+    tx1.lock().unwrap().commit().unwrap();
+
+    //tx2 holds an exclusive lock on the last block of student
+    let tx2 = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    insert_students(&tx2, layout.clone(), 60, 61);
+    mdm.updated(REFRESH_LIMIT);
+
+    //the refresh for tx3 reads without locks, it neither waits for tx2 nor keeps locks on dept
+    let tx3 = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let start = Instant::now();
+    let si = mdm.get_stat_info("student", &layout, &tx3).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_eq!(si.records_output(), 61);
+    let tx4 = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let start = Instant::now();
+    insert_dept(&tx4, 20).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    for tx in [tx2, tx3, tx4] {
+        tx.lock().unwrap().commit().unwrap();
+    }
+    remove_dir(directory);
+}
+
+#[test]
+fn test_index_metadata() {
+    let directory = "indexmetatest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let mdm = db.metadata_mgr();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+
+    mdm.create_table("student", &student_schema(), &tx).unwrap();
+    insert_students(&tx, mdm.get_layout("student", &tx).unwrap(), 0, 40);
+    mdm.create_index("sididx", "student", "sid", IndexType::Hash, &tx).unwrap();
+    mdm.create_index("snameidx", "student", "sname", IndexType::BTree, &tx).unwrap();
+    assert!(matches!(
+        mdm.create_index("gradeidx", "student", "grade", IndexType::Hash, &tx),
+        Err(DbError::FieldNotFound(_))
+    ));
+
+    let infos = mdm.get_index_info("student", &tx).unwrap();
+    assert_eq!(infos.len(), 2);
+    let sid_info = &infos["sid"];
+    assert_eq!(sid_info.index_name(), "sididx");
+    assert_eq!(sid_info.index_type(), IndexType::Hash);
+    assert_eq!(sid_info.records_output(), 1);
+    assert_eq!(sid_info.index_layout().schema().field_type("dataval"), Some(FieldType::Integer));
+    let sname_info = &infos["sname"];
+    assert_eq!(sname_info.index_name(), "snameidx");
+    assert_eq!(sname_info.index_type(), IndexType::BTree);
+    assert_eq!(sname_info.index_layout().schema().field_type("dataval"), Some(FieldType::Varchar));
+    assert_eq!(sname_info.index_layout().schema().length("dataval"), Some(10));
+    assert_eq!(sname_info.records_output(), 4);
+    assert_eq!(sname_info.distinct_values("sname"), 1);
+    assert_eq!(sname_info.distinct_values("majorid"), 4);
+    assert!(mdm.get_index_info("course", &tx).unwrap().is_empty());
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
@@ -0,0 +1,53 @@
+use super::table_mgr::{TableMgr, MAX_NAME};
+use crate::error::{DbError, DbResult};
+use crate::query::{Scan, UpdateScan};
+use crate::record_mgr::{Schema, TableScan};
+use crate::tx_mgr::Transaction;
+
+use std::sync::{Arc, Mutex};
+
+//max length of the sql text defining a view
+pub const MAX_VIEWDEF: usize = 100;
+
+//view definitions are saved as sql text in viewcat
+pub struct ViewMgr {
+    tbl_mgr: Arc<TableMgr>,
+}
+
+impl ViewMgr {
+    pub fn new(is_new: bool, tbl_mgr: Arc<TableMgr>, tx: &Arc<Mutex<Transaction>>) -> DbResult<Self> {
+        if is_new {
+            let mut schema = Schema::new();
+            schema.add_string_field("viewname", MAX_NAME);
+            schema.add_string_field("viewdef", MAX_VIEWDEF);
+            tbl_mgr.create_table("viewcat", &schema, tx)?;
+        }
+        Ok(ViewMgr { tbl_mgr })
+    }
+
+    /*
+    the planners look for a view before a table, a view named like a table
+    would hide the table
+    */
+    pub fn create_view(&self, view_name: &str, view_def: &str, tx: &Arc<Mutex<Transaction>>) -> DbResult<()> {
+        if self.tbl_mgr.table_exists(view_name, tx)? || self.get_view_def(view_name, tx)?.is_some() {
+            return Err(DbError::TableExists(view_name.to_string()));
+        }
+        let layout = Arc::new(self.tbl_mgr.get_layout("viewcat", tx)?);
+        let mut ts = TableScan::new(tx.clone(), "viewcat", layout)?;
+        ts.insert()?;
+        ts.set_string("viewname", view_name)?;
+        ts.set_string("viewdef", view_def)
+    }
+
+    pub fn get_view_def(&self, view_name: &str, tx: &Arc<Mutex<Transaction>>) -> DbResult<Option<String>> {
+        let layout = Arc::new(self.tbl_mgr.get_layout("viewcat", tx)?);
+        let mut ts = TableScan::new(tx.clone(), "viewcat", layout)?;
+        while ts.next()? {
+            if ts.get_string("viewname")? == view_name {
+                return Ok(Some(ts.get_string("viewdef")?));
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,106 @@
+use crate::index::IndexType;
+use crate::query::{AggregationFn, Constant, Expression, Predicate};
+use crate::record_mgr::Schema;
+
+use std::fmt;
+
+/*
+select fields from tables where pred group by group_fields, an
+aggregate in the select list is in fields by its output name
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryData {
+    pub fields: Vec<String>,
+    pub tables: Vec<String>,
+    pub pred: Predicate,
+    pub group_fields: Vec<String>,
+    pub aggregates: Vec<AggregationFn>,
+}
+
+impl QueryData {
+    //the query groups its records, even without group by when it has aggregates
+    pub fn is_grouped(&self) -> bool {
+        !self.group_fields.is_empty() || !self.aggregates.is_empty()
+    }
+}
+
+//the text is saved as the definition of a view and parsed again when the view is used
+impl fmt::Display for QueryData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let select_list: Vec<String> = self
+            .fields
+            .iter()
+            .map(|field_name| match self.aggregates.iter().find(|a| a.output_name() == *field_name) {
+                Some(agg) => agg.to_string(),
+                None => field_name.clone(),
+            })
+            .collect();
+        write!(f, "select {} from {}", select_list.join(", "), self.tables.join(", "))?;
+        if !self.pred.is_empty() {
+            write!(f, " where {}", self.pred)?;
+        }
+        if !self.group_fields.is_empty() {
+            write!(f, " group by {}", self.group_fields.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InsertData {
+    pub tbl_name: String,
+    pub fields: Vec<String>,
+    pub vals: Vec<Constant>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeleteData {
+    pub tbl_name: String,
+    pub pred: Predicate,
+}
+
+//update tbl_name set field_name = new_val where pred
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModifyData {
+    pub tbl_name: String,
+    pub field_name: String,
+    pub new_val: Expression,
+    pub pred: Predicate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateTableData {
+    pub tbl_name: String,
+    pub schema: Schema,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateViewData {
+    pub view_name: String,
+    pub query: QueryData,
+}
+
+impl CreateViewData {
+    pub fn view_def(&self) -> String {
+        self.query.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateIndexData {
+    pub idx_name: String,
+    pub tbl_name: String,
+    pub field_name: String,
+    pub index_type: IndexType,
+}
+
+//every statement other than select
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateCmd {
+    Insert(InsertData),
+    Delete(DeleteData),
+    Modify(ModifyData),
+    CreateTable(CreateTableData),
+    CreateView(CreateViewData),
+    CreateIndex(CreateIndexData),
+}
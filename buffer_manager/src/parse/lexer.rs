@@ -0,0 +1,204 @@
+use crate::error::{DbError, DbResult};
+
+static KEYWORDS: &[&str] = &[
+    "select", "from", "where", "and", "insert", "into", "values", "delete", "update", "set", "create", "table",
+    "int", "varchar", "view", "as", "index", "on", "using", "group",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    //single character like ',' '(' '='
+    Delim(char),
+    IntConstant(i32),
+    StringConstant(String),
+    Keyword(String),
+    Id(String),
+    Eof,
+}
+
+/*
+split sql text into tokens, keywords and identifiers are case
+insensitive and kept in lower case, string constants are enclosed in
+single quotes and a quote inside is written twice. Every token keeps
+the position of its first character so that syntax errors can point at it
+*/
+#[derive(Debug)]
+pub struct Lexer {
+    tokens: Vec<(Token, usize)>,
+    current: usize,
+}
+
+impl Lexer {
+    pub fn new(sql: &str) -> DbResult<Self> {
+        let chars: Vec<(usize, char)> = sql.char_indices().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let (pos, c) = chars[i];
+            if c.is_whitespace() {
+                i += 1;
+            } else if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).is_some_and(|(_, d)| d.is_ascii_digit())) {
+                let start = i;
+                i += 1;
+                while i < chars.len() && chars[i].1.is_ascii_digit() {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().map(|(_, c)| c).collect();
+                let val = text.parse::<i32>().map_err(|_| syntax_error(pos, &format!("integer {} out of range", text)))?;
+                tokens.push((Token::IntConstant(val), pos));
+            } else if c.is_alphabetic() || c == '_' {
+                let start = i;
+                while i < chars.len() && (chars[i].1.is_alphanumeric() || chars[i].1 == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().map(|(_, c)| c.to_ascii_lowercase()).collect();
+                if KEYWORDS.contains(&word.as_str()) {
+                    tokens.push((Token::Keyword(word), pos));
+                } else {
+                    tokens.push((Token::Id(word), pos));
+                }
+            } else if c == '\'' {
+                let mut val = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(syntax_error(pos, "unterminated string")),
+                        Some((_, '\'')) if chars.get(i + 1).is_some_and(|(_, c)| *c == '\'') => {
+                            val.push('\'');
+                            i += 2;
+                        }
+                        Some((_, '\'')) => {
+                            i += 1;
+                            break;
+                        }
+                        Some((_, c)) => {
+                            val.push(*c);
+                            i += 1;
+                        }
+                    }
+                }
+                tokens.push((Token::StringConstant(val), pos));
+            } else if "(),=".contains(c) {
+                tokens.push((Token::Delim(c), pos));
+                i += 1;
+            } else {
+                return Err(syntax_error(pos, &format!("unexpected character '{}'", c)));
+            }
+        }
+        tokens.push((Token::Eof, sql.len()));
+        Ok(Lexer { tokens, current: 0 })
+    }
+
+    //current token, not consumed
+    pub fn token(&self) -> &Token {
+        &self.tokens[self.current].0
+    }
+
+    //token after the current one, the last token is Eof
+    pub fn next_token(&self) -> &Token {
+        &self.tokens[(self.current + 1).min(self.tokens.len() - 1)].0
+    }
+
+    //position of the current token in the sql text
+    pub fn position(&self) -> usize {
+        self.tokens[self.current].1
+    }
+
+    pub fn match_delim(&self, d: char) -> bool {
+        *self.token() == Token::Delim(d)
+    }
+
+    pub fn match_int_constant(&self) -> bool {
+        matches!(self.token(), Token::IntConstant(_))
+    }
+
+    pub fn match_string_constant(&self) -> bool {
+        matches!(self.token(), Token::StringConstant(_))
+    }
+
+    pub fn match_keyword(&self, w: &str) -> bool {
+        matches!(self.token(), Token::Keyword(k) if k == w)
+    }
+
+    pub fn match_id(&self) -> bool {
+        matches!(self.token(), Token::Id(_))
+    }
+
+    pub fn match_eof(&self) -> bool {
+        *self.token() == Token::Eof
+    }
+
+    pub fn eat_delim(&mut self, d: char) -> DbResult<()> {
+        if !self.match_delim(d) {
+            return Err(self.error(&format!("'{}'", d)));
+        }
+        self.current += 1;
+        Ok(())
+    }
+
+    pub fn eat_int_constant(&mut self) -> DbResult<i32> {
+        match *self.token() {
+            Token::IntConstant(val) => {
+                self.current += 1;
+                Ok(val)
+            }
+            _ => Err(self.error("integer")),
+        }
+    }
+
+    pub fn eat_string_constant(&mut self) -> DbResult<String> {
+        match self.token() {
+            Token::StringConstant(val) => {
+                let val = val.clone();
+                self.current += 1;
+                Ok(val)
+            }
+            _ => Err(self.error("string")),
+        }
+    }
+
+    pub fn eat_keyword(&mut self, w: &str) -> DbResult<()> {
+        if !self.match_keyword(w) {
+            return Err(self.error(w));
+        }
+        self.current += 1;
+        Ok(())
+    }
+
+    pub fn eat_id(&mut self) -> DbResult<String> {
+        match self.token() {
+            Token::Id(id) => {
+                let id = id.clone();
+                self.current += 1;
+                Ok(id)
+            }
+            _ => Err(self.error("identifier")),
+        }
+    }
+
+    pub fn eat_eof(&mut self) -> DbResult<()> {
+        if !self.match_eof() {
+            return Err(self.error("end of statement"));
+        }
+        Ok(())
+    }
+
+    //syntax error at the current token
+    pub fn error(&self, expected: &str) -> DbError {
+        let found = match self.token() {
+            Token::Delim(d) => format!("'{}'", d),
+            Token::IntConstant(val) => val.to_string(),
+            Token::StringConstant(val) => format!("'{}'", val),
+            Token::Keyword(w) | Token::Id(w) => w.clone(),
+            Token::Eof => "end of statement".to_string(),
+        };
+        syntax_error(self.position(), &format!("expect {}, found {}", expected, found))
+    }
+}
+
+fn syntax_error(position: usize, message: &str) -> DbError {
+    DbError::BadSyntax {
+        position,
+        message: message.to_string(),
+    }
+}
@@ -0,0 +1,332 @@
+pub mod data;
+pub mod lexer;
+#[cfg(test)]
+pub mod test;
+
+pub use data::*;
+pub use lexer::{Lexer, Token};
+
+use crate::error::{DbError, DbResult};
+use crate::index::IndexType;
+use crate::query::{AggregationFn, AggregationKind, Constant, Expression, Predicate, Term};
+use crate::record_mgr::Schema;
+
+/*
+recursive descent parser, each method below handles one rule of the
+grammar:
+    <Field>         := IdTok
+    <Constant>      := StrTok | IntTok
+    <Expression>    := <Field> | <Constant>
+    <Term>          := <Expression> = <Expression>
+    <Predicate>     := <Term> [ AND <Predicate> ]
+    <Query>         := SELECT <SelectList> FROM <TableList> [ WHERE <Predicate> ] [ GROUP BY <FieldList> ]
+    <SelectList>    := <SelectItem> [ , <SelectList> ]
+    <SelectItem>    := <Field> | <AggFn> ( <Field> )
+    <AggFn>         := COUNT | SUM | MIN | MAX | AVG
+    <TableList>     := IdTok [ , <TableList> ]
+    <UpdateCmd>     := <Insert> | <Delete> | <Modify> | <Create>
+    <Create>        := <CreateTable> | <CreateView> | <CreateIndex>
+    <Insert>        := INSERT INTO IdTok ( <FieldList> ) VALUES ( <ConstList> )
+    <FieldList>     := <Field> [ , <FieldList> ]
+    <ConstList>     := <Constant> [ , <ConstList> ]
+    <Delete>        := DELETE FROM IdTok [ WHERE <Predicate> ]
+    <Modify>        := UPDATE IdTok SET <Field> = <Expression> [ WHERE <Predicate> ]
+    <CreateTable>   := CREATE TABLE IdTok ( <FieldDefs> )
+    <FieldDefs>     := <FieldDef> [ , <FieldDefs> ]
+    <FieldDef>      := IdTok <TypeDef>
+    <TypeDef>       := INT | VARCHAR ( IntTok )
+    <CreateView>    := CREATE VIEW IdTok AS <Query>
+    <CreateIndex>   := CREATE INDEX IdTok ON IdTok ( <Field> ) [ USING <IndexType> ]
+    <IndexType>     := HASH | BTREE
+*/
+pub struct Parser {
+    lex: Lexer,
+}
+
+impl Parser {
+    pub fn new(sql: &str) -> DbResult<Self> {
+        Ok(Parser { lex: Lexer::new(sql)? })
+    }
+
+    //the whole text is one select statement
+    pub fn parse_query(sql: &str) -> DbResult<QueryData> {
+        let mut parser = Parser::new(sql)?;
+        let data = parser.query()?;
+        parser.lex.eat_eof()?;
+        Ok(data)
+    }
+
+    //the whole text is one statement other than select
+    pub fn parse_update_cmd(sql: &str) -> DbResult<UpdateCmd> {
+        let mut parser = Parser::new(sql)?;
+        let cmd = parser.update_cmd()?;
+        parser.lex.eat_eof()?;
+        Ok(cmd)
+    }
+
+    pub fn field(&mut self) -> DbResult<String> {
+        self.lex.eat_id()
+    }
+
+    pub fn constant(&mut self) -> DbResult<Constant> {
+        if self.lex.match_string_constant() {
+            Ok(Constant::Str(self.lex.eat_string_constant()?))
+        } else if self.lex.match_int_constant() {
+            Ok(Constant::Int(self.lex.eat_int_constant()?))
+        } else {
+            Err(self.lex.error("constant"))
+        }
+    }
+
+    pub fn expression(&mut self) -> DbResult<Expression> {
+        if self.lex.match_id() {
+            Ok(Expression::Field(self.field()?))
+        } else {
+            Ok(Expression::Constant(self.constant()?))
+        }
+    }
+
+    pub fn term(&mut self) -> DbResult<Term> {
+        let lhs = self.expression()?;
+        self.lex.eat_delim('=')?;
+        let rhs = self.expression()?;
+        Ok(Term::new(lhs, rhs))
+    }
+
+    pub fn predicate(&mut self) -> DbResult<Predicate> {
+        let mut pred = Predicate::with_term(self.term()?);
+        while self.lex.match_keyword("and") {
+            self.lex.eat_keyword("and")?;
+            pred.conjoin_with(Predicate::with_term(self.term()?));
+        }
+        Ok(pred)
+    }
+
+    pub fn query(&mut self) -> DbResult<QueryData> {
+        self.lex.eat_keyword("select")?;
+        let mut aggregates = Vec::new();
+        let fields = self.list(|p| p.select_item(&mut aggregates))?;
+        self.lex.eat_keyword("from")?;
+        let tables = self.list(|p| p.lex.eat_id())?;
+        let pred = self.optional_where()?;
+        let mut group_fields = Vec::new();
+        if self.lex.match_keyword("group") {
+            self.lex.eat_keyword("group")?;
+            match self.lex.token() {
+                Token::Id(name) if name == "by" => self.lex.eat_id()?,
+                _ => return Err(self.lex.error("by")),
+            };
+            group_fields = self.list(Self::field)?;
+        }
+        Ok(QueryData {
+            fields,
+            tables,
+            pred,
+            group_fields,
+            aggregates,
+        })
+    }
+
+    /*
+    an aggregate is selected by its output name. Aggregation names are not
+    keywords, a name followed by '(' is an aggregate, so fields can still be
+    named count or max
+    */
+    fn select_item(&mut self, aggregates: &mut Vec<AggregationFn>) -> DbResult<String> {
+        let kind = match (self.lex.token(), self.lex.next_token()) {
+            (Token::Id(name), Token::Delim('(')) => AggregationKind::from_name(name),
+            _ => None,
+        };
+        let Some(kind) = kind else {
+            return self.field();
+        };
+        self.lex.eat_id()?;
+        self.lex.eat_delim('(')?;
+        let agg = AggregationFn::new(kind, &self.field()?);
+        self.lex.eat_delim(')')?;
+        let output_name = agg.output_name();
+        if !aggregates.contains(&agg) {
+            aggregates.push(agg);
+        }
+        Ok(output_name)
+    }
+
+    pub fn update_cmd(&mut self) -> DbResult<UpdateCmd> {
+        if self.lex.match_keyword("insert") {
+            Ok(UpdateCmd::Insert(self.insert()?))
+        } else if self.lex.match_keyword("delete") {
+            Ok(UpdateCmd::Delete(self.delete()?))
+        } else if self.lex.match_keyword("update") {
+            Ok(UpdateCmd::Modify(self.modify()?))
+        } else if self.lex.match_keyword("create") {
+            self.create()
+        } else {
+            Err(self.lex.error("insert, delete, update or create"))
+        }
+    }
+
+    fn create(&mut self) -> DbResult<UpdateCmd> {
+        self.lex.eat_keyword("create")?;
+        if self.lex.match_keyword("table") {
+            Ok(UpdateCmd::CreateTable(self.create_table()?))
+        } else if self.lex.match_keyword("view") {
+            Ok(UpdateCmd::CreateView(self.create_view()?))
+        } else if self.lex.match_keyword("index") {
+            Ok(UpdateCmd::CreateIndex(self.create_index()?))
+        } else {
+            Err(self.lex.error("table, view or index"))
+        }
+    }
+
+    pub fn insert(&mut self) -> DbResult<InsertData> {
+        self.lex.eat_keyword("insert")?;
+        self.lex.eat_keyword("into")?;
+        let tbl_name = self.lex.eat_id()?;
+        self.lex.eat_delim('(')?;
+        let fields = self.list(Self::field)?;
+        self.lex.eat_delim(')')?;
+        self.lex.eat_keyword("values")?;
+        self.lex.eat_delim('(')?;
+        let position = self.lex.position();
+        let vals = self.list(Self::constant)?;
+        if vals.len() != fields.len() {
+            return Err(DbError::BadSyntax {
+                position,
+                message: format!("expect {} values, found {}", fields.len(), vals.len()),
+            });
+        }
+        self.lex.eat_delim(')')?;
+        Ok(InsertData { tbl_name, fields, vals })
+    }
+
+    pub fn delete(&mut self) -> DbResult<DeleteData> {
+        self.lex.eat_keyword("delete")?;
+        self.lex.eat_keyword("from")?;
+        let tbl_name = self.lex.eat_id()?;
+        let pred = self.optional_where()?;
+        Ok(DeleteData { tbl_name, pred })
+    }
+
+    pub fn modify(&mut self) -> DbResult<ModifyData> {
+        self.lex.eat_keyword("update")?;
+        let tbl_name = self.lex.eat_id()?;
+        self.lex.eat_keyword("set")?;
+        let field_name = self.field()?;
+        self.lex.eat_delim('=')?;
+        let new_val = self.expression()?;
+        let pred = self.optional_where()?;
+        Ok(ModifyData {
+            tbl_name,
+            field_name,
+            new_val,
+            pred,
+        })
+    }
+
+    fn create_table(&mut self) -> DbResult<CreateTableData> {
+        self.lex.eat_keyword("table")?;
+        let tbl_name = self.lex.eat_id()?;
+        self.lex.eat_delim('(')?;
+        let mut schema = Schema::new();
+        self.field_def(&mut schema)?;
+        while self.lex.match_delim(',') {
+            self.lex.eat_delim(',')?;
+            self.field_def(&mut schema)?;
+        }
+        self.lex.eat_delim(')')?;
+        Ok(CreateTableData { tbl_name, schema })
+    }
+
+    fn field_def(&mut self, schema: &mut Schema) -> DbResult<()> {
+        let position = self.lex.position();
+        let field_name = self.field()?;
+        if schema.has_field(&field_name) {
+            return Err(DbError::BadSyntax {
+                position,
+                message: format!("field {} is defined twice", field_name),
+            });
+        }
+        if self.lex.match_keyword("int") {
+            self.lex.eat_keyword("int")?;
+            schema.add_int_field(&field_name);
+        } else if self.lex.match_keyword("varchar") {
+            self.lex.eat_keyword("varchar")?;
+            self.lex.eat_delim('(')?;
+            if !self.lex.match_int_constant() {
+                return Err(self.lex.error("length of varchar"));
+            }
+            let position = self.lex.position();
+            let len = self.lex.eat_int_constant()?;
+            if len <= 0 {
+                return Err(DbError::BadSyntax {
+                    position,
+                    message: format!("length of varchar must be positive, found {}", len),
+                });
+            }
+            self.lex.eat_delim(')')?;
+            schema.add_string_field(&field_name, len as usize);
+        } else {
+            return Err(self.lex.error("int or varchar"));
+        }
+        Ok(())
+    }
+
+    fn create_view(&mut self) -> DbResult<CreateViewData> {
+        self.lex.eat_keyword("view")?;
+        let view_name = self.lex.eat_id()?;
+        self.lex.eat_keyword("as")?;
+        let query = self.query()?;
+        Ok(CreateViewData { view_name, query })
+    }
+
+    fn create_index(&mut self) -> DbResult<CreateIndexData> {
+        self.lex.eat_keyword("index")?;
+        let idx_name = self.lex.eat_id()?;
+        self.lex.eat_keyword("on")?;
+        let tbl_name = self.lex.eat_id()?;
+        self.lex.eat_delim('(')?;
+        let field_name = self.field()?;
+        self.lex.eat_delim(')')?;
+        let mut index_type = IndexType::Hash;
+        if self.lex.match_keyword("using") {
+            self.lex.eat_keyword("using")?;
+            index_type = self.index_type()?;
+        }
+        Ok(CreateIndexData {
+            idx_name,
+            tbl_name,
+            field_name,
+            index_type,
+        })
+    }
+
+    //index types are not keywords, so they can still be used as names
+    fn index_type(&mut self) -> DbResult<IndexType> {
+        let index_type = match self.lex.token() {
+            Token::Id(name) if name == "hash" => IndexType::Hash,
+            Token::Id(name) if name == "btree" => IndexType::BTree,
+            _ => return Err(self.lex.error("hash or btree")),
+        };
+        self.lex.eat_id()?;
+        Ok(index_type)
+    }
+
+    fn optional_where(&mut self) -> DbResult<Predicate> {
+        if self.lex.match_keyword("where") {
+            self.lex.eat_keyword("where")?;
+            self.predicate()
+        } else {
+            Ok(Predicate::new())
+        }
+    }
+
+    //one or more items separated by comma
+    fn list<T>(&mut self, mut item: impl FnMut(&mut Self) -> DbResult<T>) -> DbResult<Vec<T>> {
+        let mut items = vec![item(self)?];
+        while self.lex.match_delim(',') {
+            self.lex.eat_delim(',')?;
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+}
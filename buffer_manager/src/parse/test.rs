@@ -0,0 +1,166 @@
+use super::*;
+use crate::index::IndexType;
+use crate::record_mgr::FieldType;
+
+fn syntax_error_at(result: DbResult<impl std::fmt::Debug>) -> usize {
+    match result {
+        Err(DbError::BadSyntax { position, .. }) => position,
+        other => panic!("expect syntax error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_lexer_tokens() {
+    let mut lex = Lexer::new("SELECT Name, -12 FROM t WHERE s = 'it''s'").unwrap();
+    lex.eat_keyword("select").unwrap();
+    assert_eq!(lex.position(), 7);
+    assert_eq!(lex.eat_id().unwrap(), "name");
+    lex.eat_delim(',').unwrap();
+    assert_eq!(lex.eat_int_constant().unwrap(), -12);
+    lex.eat_keyword("from").unwrap();
+    assert!(lex.match_id());
+    assert_eq!(lex.eat_id().unwrap(), "t");
+    lex.eat_keyword("where").unwrap();
+    lex.eat_id().unwrap();
+    lex.eat_delim('=').unwrap();
+    assert_eq!(lex.eat_string_constant().unwrap(), "it's");
+    assert!(lex.match_eof());
+
+    assert_eq!(syntax_error_at(Lexer::new("select a from t where b = 'abc")), 26);
+    assert_eq!(syntax_error_at(Lexer::new("select a; from t")), 8);
+    assert_eq!(syntax_error_at(Lexer::new("select 99999999999 from t")), 7);
+}
+
+#[test]
+fn test_parse_query() {
+    let data = Parser::parse_query("select sname, gradyear from student, dept where majorid = did and sname = 'joe'").unwrap();
+    assert_eq!(data.fields, ["sname", "gradyear"]);
+    assert_eq!(data.tables, ["student", "dept"]);
+    assert_eq!(
+        data.pred.terms(),
+        [
+            Term::new(Expression::Field("majorid".to_string()), Expression::Field("did".to_string())),
+            Term::new(Expression::Field("sname".to_string()), Expression::Constant(Constant::from("joe"))),
+        ]
+    );
+
+    let data = Parser::parse_query("SELECT a FROM t").unwrap();
+    assert!(data.pred.is_empty());
+    assert_eq!(data.to_string(), "select a from t");
+
+    //text of the query is parsed back into the same query
+    let data = Parser::parse_query("select a, b from t, u where a = 'x''y' and b = 3").unwrap();
+    assert_eq!(Parser::parse_query(&data.to_string()).unwrap(), data);
+
+    //an aggregate is selected by its output field, the same one twice is computed once
+    let sql = "select majorid, count(sid), max(gradyear), count(sid) from student group by majorid";
+    let data = Parser::parse_query(sql).unwrap();
+    assert_eq!(data.fields, ["majorid", "countofsid", "maxofgradyear", "countofsid"]);
+    assert_eq!(data.group_fields, ["majorid"]);
+    assert_eq!(
+        data.aggregates,
+        [
+            AggregationFn::new(AggregationKind::Count, "sid"),
+            AggregationFn::new(AggregationKind::Max, "gradyear"),
+        ]
+    );
+    assert!(data.is_grouped());
+    assert_eq!(data.to_string(), sql);
+    assert_eq!(Parser::parse_query(&data.to_string()).unwrap(), data);
+
+    //aggregates without group by make one group of all records
+    let data = Parser::parse_query("select avg(gradyear) from student where majorid = 10").unwrap();
+    assert!(data.group_fields.is_empty());
+    assert!(data.is_grouped());
+    assert!(!Parser::parse_query("select a from t").unwrap().is_grouped());
+
+    //aggregation names and by are not reserved, they can name fields
+    let data = Parser::parse_query("select count, max(by) from t where sum = avg group by count").unwrap();
+    assert_eq!(data.fields, ["count", "maxofby"]);
+    assert_eq!(data.group_fields, ["count"]);
+    assert_eq!(data.aggregates, [AggregationFn::new(AggregationKind::Max, "by")]);
+    assert_eq!(Parser::parse_query(&data.to_string()).unwrap(), data);
+}
+
+#[test]
+fn test_parse_update_cmds() {
+    match Parser::parse_update_cmd("insert into student (sid, sname) values (1, 'joe')").unwrap() {
+        UpdateCmd::Insert(data) => {
+            assert_eq!(data.tbl_name, "student");
+            assert_eq!(data.fields, ["sid", "sname"]);
+            assert_eq!(data.vals, [Constant::Int(1), Constant::from("joe")]);
+        }
+        other => panic!("expect insert, got {:?}", other),
+    }
+
+    match Parser::parse_update_cmd("delete from student where sid = 1").unwrap() {
+        UpdateCmd::Delete(data) => {
+            assert_eq!(data.tbl_name, "student");
+            assert_eq!(data.pred.terms().len(), 1);
+        }
+        other => panic!("expect delete, got {:?}", other),
+    }
+
+    match Parser::parse_update_cmd("update student set majorid = 20 where sname = 'amy'").unwrap() {
+        UpdateCmd::Modify(data) => {
+            assert_eq!(data.field_name, "majorid");
+            assert_eq!(data.new_val, Expression::Constant(Constant::Int(20)));
+            assert_eq!(data.pred.to_string(), "sname = 'amy'");
+        }
+        other => panic!("expect update, got {:?}", other),
+    }
+
+    match Parser::parse_update_cmd("create table student (sid int, sname varchar(10))").unwrap() {
+        UpdateCmd::CreateTable(data) => {
+            assert_eq!(data.tbl_name, "student");
+            assert_eq!(data.schema.fields(), ["sid".to_string(), "sname".to_string()]);
+            assert_eq!(data.schema.field_type("sid"), Some(FieldType::Integer));
+            assert_eq!(data.schema.length("sname"), Some(10));
+        }
+        other => panic!("expect create table, got {:?}", other),
+    }
+
+    match Parser::parse_update_cmd("create view math as select sname from student where majorid = 10").unwrap() {
+        UpdateCmd::CreateView(data) => {
+            assert_eq!(data.view_name, "math");
+            assert_eq!(data.view_def(), "select sname from student where majorid = 10");
+        }
+        other => panic!("expect create view, got {:?}", other),
+    }
+
+    match Parser::parse_update_cmd("create index sididx on student (sid)").unwrap() {
+        UpdateCmd::CreateIndex(data) => {
+            assert_eq!(data.idx_name, "sididx");
+            assert_eq!(data.tbl_name, "student");
+            assert_eq!(data.field_name, "sid");
+            assert_eq!(data.index_type, IndexType::Hash);
+        }
+        other => panic!("expect create index, got {:?}", other),
+    }
+    match Parser::parse_update_cmd("create index sididx on student (sid) using btree").unwrap() {
+        UpdateCmd::CreateIndex(data) => assert_eq!(data.index_type, IndexType::BTree),
+        other => panic!("expect create index, got {:?}", other),
+    }
+    assert_eq!(
+        syntax_error_at(Parser::parse_update_cmd("create index sididx on student (sid) using tree")),
+        43
+    );
+}
+
+#[test]
+fn test_syntax_error_position() {
+    //position of the token where the parser gets stuck
+    assert_eq!(syntax_error_at(Parser::parse_query("select from t")), 7);
+    assert_eq!(syntax_error_at(Parser::parse_query("select a from t where a > 1")), 24);
+    assert_eq!(syntax_error_at(Parser::parse_query("select a from t b")), 16);
+    assert_eq!(syntax_error_at(Parser::parse_update_cmd("insert into t (a, b) values (1)")), 29);
+    assert_eq!(syntax_error_at(Parser::parse_update_cmd("create table t (a int, a int)")), 23);
+    assert_eq!(syntax_error_at(Parser::parse_update_cmd("create table t (a varchar(0))")), 26);
+    assert_eq!(syntax_error_at(Parser::parse_update_cmd("drop table t")), 0);
+    assert_eq!(syntax_error_at(Parser::parse_update_cmd("create view v as select a from")), 30);
+    assert_eq!(syntax_error_at(Parser::parse_query("select count(*) from t")), 13);
+    assert_eq!(syntax_error_at(Parser::parse_query("select a from t group a")), 22);
+
+    let err = Parser::parse_query("select a from t where a = ").unwrap_err();
+    assert_eq!(err.to_string(), "syntax error at position 26: expect constant, found end of statement");
+}
@@ -0,0 +1,51 @@
+use super::planner::QueryPlanner;
+use super::{GroupByPlan, Plan, ProductPlan, ProjectPlan, SelectPlan, TablePlan};
+use crate::error::DbResult;
+use crate::metadata_mgr::MetadataMgr;
+use crate::parse::{Parser, QueryData};
+use crate::tx_mgr::Transaction;
+
+use std::sync::{Arc, Mutex};
+
+/*
+product of the tables in the order of the from clause, then select with
+the whole predicate, group if the query groups, and project on the
+selected fields. A view in the from clause is replaced by the plan of
+its definition
+*/
+pub struct BasicQueryPlanner {
+    mdm: Arc<MetadataMgr>,
+}
+
+impl BasicQueryPlanner {
+    pub fn new(mdm: Arc<MetadataMgr>) -> Self {
+        BasicQueryPlanner { mdm }
+    }
+}
+
+impl QueryPlanner for BasicQueryPlanner {
+    fn create_plan(&self, data: &QueryData, tx: &Arc<Mutex<Transaction>>) -> DbResult<Box<dyn Plan>> {
+        let mut plans: Vec<Box<dyn Plan>> = Vec::new();
+        for tbl_name in &data.tables {
+            match self.mdm.get_view_def(tbl_name, tx)? {
+                Some(view_def) => {
+                    let view_data = Parser::parse_query(&view_def)?;
+                    plans.push(self.create_plan(&view_data, tx)?);
+                }
+                None => plans.push(Box::new(TablePlan::new(tx.clone(), tbl_name, &self.mdm)?)),
+            }
+        }
+
+        //the parser makes sure there is at least one table
+        let mut plans = plans.into_iter();
+        let mut p = plans.next().unwrap();
+        for next in plans {
+            p = Box::new(ProductPlan::new(p, next));
+        }
+        let mut p: Box<dyn Plan> = Box::new(SelectPlan::new(p, data.pred.clone()));
+        if data.is_grouped() {
+            p = Box::new(GroupByPlan::new(tx.clone(), p, &data.group_fields, &data.aggregates)?);
+        }
+        Ok(Box::new(ProjectPlan::new(p, &data.fields)?))
+    }
+}
@@ -0,0 +1,80 @@
+use super::planner::UpdatePlanner;
+use super::TablePlan;
+use crate::error::DbResult;
+use crate::metadata_mgr::MetadataMgr;
+use crate::parse::*;
+use crate::query::{Scan, SelectScan, UpdateScan};
+use crate::tx_mgr::Transaction;
+
+use std::sync::{Arc, Mutex};
+
+/*
+records are modified through a select scan over the table scan, the
+number of modified records is reported to the statistics manager
+*/
+pub struct BasicUpdatePlanner {
+    mdm: Arc<MetadataMgr>,
+}
+
+impl BasicUpdatePlanner {
+    pub fn new(mdm: Arc<MetadataMgr>) -> Self {
+        BasicUpdatePlanner { mdm }
+    }
+}
+
+impl UpdatePlanner for BasicUpdatePlanner {
+    fn execute_insert(&self, data: &InsertData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize> {
+        let p = TablePlan::new(tx.clone(), &data.tbl_name, &self.mdm)?;
+        let mut us = p.open_table_scan()?;
+        us.insert()?;
+        for (field_name, val) in data.fields.iter().zip(&data.vals) {
+            us.set_val(field_name, val)?;
+        }
+        us.close();
+        self.mdm.updated(1);
+        Ok(1)
+    }
+
+    fn execute_delete(&self, data: &DeleteData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize> {
+        let p = TablePlan::new(tx.clone(), &data.tbl_name, &self.mdm)?;
+        let mut us = SelectScan::new(p.open_table_scan()?, data.pred.clone());
+        let mut count = 0;
+        while us.next()? {
+            us.delete()?;
+            count += 1;
+        }
+        us.close();
+        self.mdm.updated(count);
+        Ok(count)
+    }
+
+    fn execute_modify(&self, data: &ModifyData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize> {
+        let p = TablePlan::new(tx.clone(), &data.tbl_name, &self.mdm)?;
+        let mut us = SelectScan::new(p.open_table_scan()?, data.pred.clone());
+        let mut count = 0;
+        while us.next()? {
+            let val = data.new_val.evaluate(&us)?;
+            us.set_val(&data.field_name, &val)?;
+            count += 1;
+        }
+        us.close();
+        self.mdm.updated(count);
+        Ok(count)
+    }
+
+    fn execute_create_table(&self, data: &CreateTableData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize> {
+        self.mdm.create_table(&data.tbl_name, &data.schema, tx)?;
+        Ok(0)
+    }
+
+    fn execute_create_view(&self, data: &CreateViewData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize> {
+        self.mdm.create_view(&data.view_name, &data.view_def(), tx)?;
+        Ok(0)
+    }
+
+    fn execute_create_index(&self, data: &CreateIndexData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize> {
+        self.mdm
+            .create_index(&data.idx_name, &data.tbl_name, &data.field_name, data.index_type, tx)?;
+        Ok(0)
+    }
+}
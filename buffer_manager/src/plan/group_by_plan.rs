@@ -0,0 +1,101 @@
+use super::{Plan, SortPlan};
+use crate::error::{DbError, DbResult};
+use crate::query::{AggregationFn, AggregationKind, GroupByScan, Scan};
+use crate::record_mgr::{FieldType, Schema};
+use crate::tx_mgr::Transaction;
+
+use std::sync::{Arc, Mutex};
+
+/*
+groups the records of p by the group fields and aggregates every group,
+p is sorted by the group fields first so that the records of a group
+come one after another. The output has the group fields followed by the
+aggregations, count, sum and avg are integers, min and max have the type
+of their field
+*/
+pub struct GroupByPlan {
+    p: Box<dyn Plan>,
+    group_fields: Vec<String>,
+    aggregates: Vec<AggregationFn>,
+    schema: Schema,
+}
+
+impl GroupByPlan {
+    pub fn new(
+        tx: Arc<Mutex<Transaction>>,
+        p: Box<dyn Plan>,
+        group_fields: &[String],
+        aggregates: &[AggregationFn],
+    ) -> DbResult<Self> {
+        let mut schema = Schema::new();
+        for field_name in group_fields {
+            if !p.schema().has_field(field_name) {
+                return Err(DbError::FieldNotFound(field_name.clone()));
+            }
+            schema.add(field_name, p.schema());
+        }
+        for agg in aggregates {
+            let field_type = p
+                .schema()
+                .field_type(agg.field_name())
+                .ok_or_else(|| DbError::FieldNotFound(agg.field_name().to_string()))?;
+            match agg.kind() {
+                AggregationKind::Count => schema.add_int_field(&agg.output_name()),
+                AggregationKind::Sum | AggregationKind::Avg => {
+                    if field_type != FieldType::Integer {
+                        return Err(DbError::TypeMismatch(agg.field_name().to_string()));
+                    }
+                    schema.add_int_field(&agg.output_name())
+                }
+                AggregationKind::Min | AggregationKind::Max => {
+                    let length = p.schema().length(agg.field_name()).unwrap_or(0);
+                    schema.add_field(&agg.output_name(), field_type, length)
+                }
+            }
+        }
+        //all records form one group without group fields, there is nothing to sort
+        let p: Box<dyn Plan> = if group_fields.is_empty() {
+            p
+        } else {
+            Box::new(SortPlan::new(tx, p, group_fields)?)
+        };
+        Ok(GroupByPlan {
+            p,
+            group_fields: group_fields.to_vec(),
+            aggregates: aggregates.to_vec(),
+            schema,
+        })
+    }
+}
+
+impl Plan for GroupByPlan {
+    fn open(&self) -> DbResult<Box<dyn Scan>> {
+        let s = self.p.open()?;
+        Ok(Box::new(GroupByScan::new(s, &self.group_fields, &self.aggregates)?))
+    }
+
+    fn blocks_accessed(&self) -> u64 {
+        self.p.blocks_accessed()
+    }
+
+    //every combination of group values is taken to be there, but not more groups than records
+    fn records_output(&self) -> usize {
+        let groups = self
+            .group_fields
+            .iter()
+            .fold(1, |n: usize, f| n.saturating_mul(self.p.distinct_values(f)));
+        groups.min(self.p.records_output()).max(1)
+    }
+
+    fn distinct_values(&self, field_name: &str) -> usize {
+        if self.group_fields.iter().any(|f| f == field_name) {
+            self.p.distinct_values(field_name)
+        } else {
+            self.records_output()
+        }
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+}
@@ -0,0 +1,151 @@
+use super::Plan;
+use crate::error::{DbError, DbResult};
+use crate::query::{HashJoinScan, Scan, UpdateScan};
+use crate::record_mgr::{Layout, Schema, TempTable};
+use crate::tx_mgr::Transaction;
+
+use std::sync::{Arc, Mutex};
+
+/*
+p1 joined with p2 on field1 equal to field2 by a hash table of the
+records of p2. When p2 takes more blocks than the available buffers, it
+is a grace hash join: both sides are written into as many partitions as
+there are buffers left to write them at once after both sides are opened,
+then the partitions are joined one pair at a time. A partition is taken to
+fit into memory, it is not split again. With fewer than two buffers left
+for the partitions, the join is done in memory
+*/
+pub struct HashJoinPlan {
+    tx: Arc<Mutex<Transaction>>,
+    p1: Box<dyn Plan>,
+    p2: Box<dyn Plan>,
+    field1: String,
+    field2: String,
+    schema: Schema,
+}
+
+impl HashJoinPlan {
+    pub fn new(
+        tx: Arc<Mutex<Transaction>>,
+        p1: Box<dyn Plan>,
+        p2: Box<dyn Plan>,
+        field1: &str,
+        field2: &str,
+    ) -> DbResult<Self> {
+        for (p, field_name) in [(&p1, field1), (&p2, field2)] {
+            if !p.schema().has_field(field_name) {
+                return Err(DbError::FieldNotFound(field_name.to_string()));
+            }
+        }
+        let mut schema = Schema::new();
+        schema.add_all(p1.schema());
+        schema.add_all(p2.schema());
+        Ok(HashJoinPlan {
+            tx,
+            p1,
+            p2,
+            field1: field1.to_string(),
+            field2: field2.to_string(),
+            schema,
+        })
+    }
+
+    fn available_buffs(&self) -> usize {
+        self.tx.lock().unwrap().available_buffs() as usize
+    }
+
+    //blocks taken by the records of the plan when they are written into a table
+    fn output_blocks(&self, p: &dyn Plan) -> u64 {
+        let block_size = self.tx.lock().unwrap().block_size() as usize;
+        let recs_per_block = (block_size / Layout::new(p.schema().clone()).slot_size()).max(1);
+        p.records_output().div_ceil(recs_per_block) as u64
+    }
+
+    fn fits_in_memory(&self) -> bool {
+        self.output_blocks(self.p2.as_ref()) <= self.available_buffs() as u64
+    }
+
+    //at least a buffer for reading the records and two partitions
+    fn is_partitioned(&self) -> bool {
+        !self.fits_in_memory() && self.available_buffs() >= 3
+    }
+
+    //one temporary table for every partition, each keeps a buffer pinned while it is written
+    fn partition(
+        &self,
+        mut src: Box<dyn Scan>,
+        schema: &Schema,
+        field_name: &str,
+        num_partitions: usize,
+    ) -> DbResult<Vec<TempTable>> {
+        let partitions: Vec<TempTable> = (0..num_partitions)
+            .map(|_| TempTable::new(self.tx.clone(), schema))
+            .collect();
+        let mut dests = partitions.iter().map(|t| t.open()).collect::<DbResult<Vec<_>>>()?;
+        while src.next()? {
+            let key = src.get_val(field_name)?;
+            let dest = &mut dests[(key.stable_hash() % num_partitions as u64) as usize];
+            dest.insert()?;
+            for f in schema.fields() {
+                dest.set_val(f, &src.get_val(f)?)?;
+            }
+        }
+        src.close();
+        for dest in &mut dests {
+            dest.close();
+        }
+        Ok(partitions)
+    }
+}
+
+impl Plan for HashJoinPlan {
+    fn open(&self) -> DbResult<Box<dyn Scan>> {
+        let build_fields = self.p2.schema().fields();
+        let partitioned = self.is_partitioned();
+        let probe = self.p1.open()?;
+        let build = self.p2.open()?;
+        //the sides may be plan trees pinning several buffers, the partitions get what is left
+        let num_partitions = self.available_buffs();
+        if !partitioned || num_partitions < 2 {
+            let scan = HashJoinScan::new(probe, build, &self.field1, &self.field2, build_fields)?;
+            return Ok(Box::new(scan));
+        }
+        let probe_parts = self.partition(probe, self.p1.schema(), &self.field1, num_partitions)?;
+        let build_parts = self.partition(build, self.p2.schema(), &self.field2, num_partitions)?;
+        let partitions = probe_parts.into_iter().zip(build_parts).collect();
+        let scan = HashJoinScan::with_partitions(partitions, &self.field1, &self.field2, build_fields)?;
+        Ok(Box::new(scan))
+    }
+
+    //partitioning writes both sides and reads them back
+    fn blocks_accessed(&self) -> u64 {
+        let read = self.p1.blocks_accessed().saturating_add(self.p2.blocks_accessed());
+        if !self.is_partitioned() {
+            return read;
+        }
+        let written = self
+            .output_blocks(self.p1.as_ref())
+            .saturating_add(self.output_blocks(self.p2.as_ref()));
+        read.saturating_add(written.saturating_mul(2))
+    }
+
+    fn records_output(&self) -> usize {
+        let max_vals = self
+            .p1
+            .distinct_values(&self.field1)
+            .max(self.p2.distinct_values(&self.field2));
+        self.p1.records_output().saturating_mul(self.p2.records_output()) / max_vals
+    }
+
+    fn distinct_values(&self, field_name: &str) -> usize {
+        if self.p1.schema().has_field(field_name) {
+            self.p1.distinct_values(field_name)
+        } else {
+            self.p2.distinct_values(field_name)
+        }
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+}
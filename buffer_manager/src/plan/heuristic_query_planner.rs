@@ -0,0 +1,97 @@
+use super::planner::QueryPlanner;
+use super::{GroupByPlan, Plan, ProjectPlan, TablePlan, TablePlanner};
+use crate::error::{DbError, DbResult};
+use crate::metadata_mgr::MetadataMgr;
+use crate::parse::{Parser, QueryData};
+use crate::record_mgr::Schema;
+use crate::tx_mgr::Transaction;
+
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+/*
+greedy join ordering instead of the order of the from clause:
+    1. start with the table whose select plan outputs fewest records,
+       so the most restrictive predicate is applied first
+    2. among the tables joined to the current plan by some term, add the
+       one giving the fewest output records
+    3. when no table is joined to the current plan, take the product with
+       the one giving the fewest output records
+the inner side of every product is the new table, so the cheaper the
+current plan the fewer times the new table is gone through. Grouping
+and aggregation come after all the joins
+*/
+pub struct HeuristicQueryPlanner {
+    mdm: Arc<MetadataMgr>,
+}
+
+impl HeuristicQueryPlanner {
+    pub fn new(mdm: Arc<MetadataMgr>) -> Self {
+        HeuristicQueryPlanner { mdm }
+    }
+}
+
+impl QueryPlanner for HeuristicQueryPlanner {
+    fn create_plan(&self, data: &QueryData, tx: &Arc<Mutex<Transaction>>) -> DbResult<Box<dyn Plan>> {
+        let mut planners = Vec::new();
+        let mut schema = Schema::new();
+        for tbl_name in &data.tables {
+            let tp = match self.mdm.get_view_def(tbl_name, tx)? {
+                Some(view_def) => {
+                    let p = self.create_plan(&Parser::parse_query(&view_def)?, tx)?;
+                    TablePlanner::new(tx.clone(), p, data.pred.clone())
+                }
+                None => {
+                    let p = TablePlan::new(tx.clone(), tbl_name, &self.mdm)?;
+                    let indexes = self.mdm.get_index_info(tbl_name, tx)?;
+                    TablePlanner::with_indexes(tx.clone(), p, indexes, data.pred.clone())
+                }
+            };
+            schema.add_all(tp.schema());
+            planners.push(tp);
+        }
+        //a term on a field of no table would never be applied
+        for term in data.pred.terms() {
+            for field_name in [term.lhs(), term.rhs()].into_iter().filter_map(|e| e.as_field_name()) {
+                if !schema.has_field(field_name) {
+                    return Err(DbError::FieldNotFound(field_name.to_string()));
+                }
+            }
+        }
+
+        let mut current = lowest_plan(&mut planners, |tp| Some(tp.make_select_plan())).unwrap();
+        while !planners.is_empty() {
+            current = match lowest_plan(&mut planners, |tp| tp.make_join_plan(&current)) {
+                Some(p) => p,
+                None => lowest_plan(&mut planners, |tp| Some(tp.make_product_plan(&current))).unwrap(),
+            };
+        }
+        let mut p: Box<dyn Plan> = Box::new(current);
+        if data.is_grouped() {
+            p = Box::new(GroupByPlan::new(tx.clone(), p, &data.group_fields, &data.aggregates)?);
+        }
+        Ok(Box::new(ProjectPlan::new(p, &data.fields)?))
+    }
+}
+
+//the candidate with fewest output records, its table planner is used up
+fn lowest_plan(
+    planners: &mut Vec<TablePlanner>,
+    make_plan: impl Fn(&TablePlanner) -> Option<Box<dyn Plan>>,
+) -> Option<Rc<dyn Plan>> {
+    let mut best: Option<(usize, Box<dyn Plan>)> = None;
+    for (i, tp) in planners.iter().enumerate() {
+        let Some(p) = make_plan(tp) else {
+            continue;
+        };
+        if best
+            .as_ref()
+            .is_none_or(|(_, b)| p.records_output() < b.records_output())
+        {
+            best = Some((i, p));
+        }
+    }
+    let (i, p) = best?;
+    planners.remove(i);
+    Some(Rc::from(p))
+}
@@ -0,0 +1,68 @@
+use super::{Plan, TablePlan};
+use crate::error::DbResult;
+use crate::metadata_mgr::IndexInfo;
+use crate::query::{IndexJoinScan, Scan};
+use crate::record_mgr::Schema;
+
+use std::rc::Rc;
+
+/*
+p1 joined with the table of p2 on p1's join field equal to the indexed
+field of p2. The index is searched once for every record of p1, and
+every matching record of p2 costs one block
+*/
+pub struct IndexJoinPlan {
+    p1: Box<dyn Plan>,
+    p2: Rc<TablePlan>,
+    ii: IndexInfo,
+    join_field: String,
+    schema: Schema,
+}
+
+impl IndexJoinPlan {
+    pub fn new(p1: Box<dyn Plan>, p2: Rc<TablePlan>, ii: IndexInfo, join_field: &str) -> Self {
+        let mut schema = Schema::new();
+        schema.add_all(p1.schema());
+        schema.add_all(p2.schema());
+        IndexJoinPlan {
+            p1,
+            p2,
+            ii,
+            join_field: join_field.to_string(),
+            schema,
+        }
+    }
+}
+
+impl Plan for IndexJoinPlan {
+    fn open(&self) -> DbResult<Box<dyn Scan>> {
+        let s = self.p1.open()?;
+        let ts = self.p2.open_table_scan()?;
+        let idx = self.ii.open()?;
+        Ok(Box::new(IndexJoinScan::new(s, idx, &self.join_field, ts)?))
+    }
+
+    fn blocks_accessed(&self) -> u64 {
+        let r1 = self.p1.records_output() as u64;
+        self.p1
+            .blocks_accessed()
+            .saturating_add(r1.saturating_mul(self.ii.blocks_accessed()))
+            .saturating_add(self.records_output() as u64)
+    }
+
+    fn records_output(&self) -> usize {
+        self.p1.records_output().saturating_mul(self.ii.records_output())
+    }
+
+    fn distinct_values(&self, field_name: &str) -> usize {
+        if self.p1.schema().has_field(field_name) {
+            self.p1.distinct_values(field_name)
+        } else {
+            self.p2.distinct_values(field_name)
+        }
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+}
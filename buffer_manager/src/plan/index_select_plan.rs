@@ -0,0 +1,48 @@
+use super::{Plan, TablePlan};
+use crate::error::DbResult;
+use crate::metadata_mgr::IndexInfo;
+use crate::query::{Constant, IndexSelectScan, Scan};
+use crate::record_mgr::Schema;
+
+use std::rc::Rc;
+
+/*
+records of the table whose indexed field equals the value, the cost is
+the index search plus one block for every matching record, as they are
+taken to be in different blocks
+*/
+pub struct IndexSelectPlan {
+    p: Rc<TablePlan>,
+    ii: IndexInfo,
+    val: Constant,
+}
+
+impl IndexSelectPlan {
+    pub fn new(p: Rc<TablePlan>, ii: IndexInfo, val: Constant) -> Self {
+        IndexSelectPlan { p, ii, val }
+    }
+}
+
+impl Plan for IndexSelectPlan {
+    fn open(&self) -> DbResult<Box<dyn Scan>> {
+        let ts = self.p.open_table_scan()?;
+        let idx = self.ii.open()?;
+        Ok(Box::new(IndexSelectScan::new(ts, idx, self.val.clone())?))
+    }
+
+    fn blocks_accessed(&self) -> u64 {
+        self.ii.blocks_accessed().saturating_add(self.records_output() as u64)
+    }
+
+    fn records_output(&self) -> usize {
+        self.ii.records_output()
+    }
+
+    fn distinct_values(&self, field_name: &str) -> usize {
+        self.ii.distinct_values(field_name)
+    }
+
+    fn schema(&self) -> &Schema {
+        self.p.schema()
+    }
+}
@@ -0,0 +1,130 @@
+use super::planner::UpdatePlanner;
+use super::TablePlan;
+use crate::error::DbResult;
+use crate::index::Index;
+use crate::metadata_mgr::MetadataMgr;
+use crate::parse::*;
+use crate::query::{Scan, SelectScan, UpdateScan};
+use crate::tx_mgr::Transaction;
+
+use std::sync::{Arc, Mutex};
+
+/*
+like the basic update planner, but every index of the table is kept up
+to date with the modified records, and a new index is filled with the
+records already in the table
+*/
+pub struct IndexUpdatePlanner {
+    mdm: Arc<MetadataMgr>,
+}
+
+impl IndexUpdatePlanner {
+    pub fn new(mdm: Arc<MetadataMgr>) -> Self {
+        IndexUpdatePlanner { mdm }
+    }
+
+    //every index of the table with the field it is on
+    fn open_indexes(&self, tbl_name: &str, tx: &Arc<Mutex<Transaction>>) -> DbResult<Vec<(String, Box<dyn Index>)>> {
+        let infos = self.mdm.get_index_info(tbl_name, tx)?;
+        infos
+            .into_iter()
+            .map(|(field_name, ii)| Ok((field_name, ii.open()?)))
+            .collect()
+    }
+}
+
+impl UpdatePlanner for IndexUpdatePlanner {
+    fn execute_insert(&self, data: &InsertData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize> {
+        let p = TablePlan::new(tx.clone(), &data.tbl_name, &self.mdm)?;
+        let mut us = p.open_table_scan()?;
+        us.insert()?;
+        for (field_name, val) in data.fields.iter().zip(&data.vals) {
+            us.set_val(field_name, val)?;
+        }
+        //fields missing in the statement are indexed with their initial value
+        let rid = us.get_rid();
+        for (field_name, mut idx) in self.open_indexes(&data.tbl_name, tx)? {
+            idx.insert(&us.get_val(&field_name)?, rid)?;
+            idx.close();
+        }
+        us.close();
+        self.mdm.updated(1);
+        Ok(1)
+    }
+
+    fn execute_delete(&self, data: &DeleteData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize> {
+        let p = TablePlan::new(tx.clone(), &data.tbl_name, &self.mdm)?;
+        let mut indexes = self.open_indexes(&data.tbl_name, tx)?;
+        let mut us = SelectScan::new(p.open_table_scan()?, data.pred.clone());
+        let mut count = 0;
+        while us.next()? {
+            let rid = us.get_rid();
+            for (field_name, idx) in indexes.iter_mut() {
+                idx.delete(&us.get_val(field_name)?, rid)?;
+            }
+            us.delete()?;
+            count += 1;
+        }
+        us.close();
+        for (_, idx) in indexes.iter_mut() {
+            idx.close();
+        }
+        self.mdm.updated(count);
+        Ok(count)
+    }
+
+    fn execute_modify(&self, data: &ModifyData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize> {
+        let p = TablePlan::new(tx.clone(), &data.tbl_name, &self.mdm)?;
+        let mut idx = self
+            .mdm
+            .get_index_info(&data.tbl_name, tx)?
+            .remove(&data.field_name)
+            .map(|ii| ii.open())
+            .transpose()?;
+        let mut us = SelectScan::new(p.open_table_scan()?, data.pred.clone());
+        let mut count = 0;
+        while us.next()? {
+            let new_val = data.new_val.evaluate(&us)?;
+            let old_val = us.get_val(&data.field_name)?;
+            us.set_val(&data.field_name, &new_val)?;
+            if let Some(idx) = idx.as_mut() {
+                let rid = us.get_rid();
+                idx.delete(&old_val, rid)?;
+                idx.insert(&new_val, rid)?;
+            }
+            count += 1;
+        }
+        us.close();
+        if let Some(idx) = idx.as_mut() {
+            idx.close();
+        }
+        self.mdm.updated(count);
+        Ok(count)
+    }
+
+    fn execute_create_table(&self, data: &CreateTableData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize> {
+        self.mdm.create_table(&data.tbl_name, &data.schema, tx)?;
+        Ok(0)
+    }
+
+    fn execute_create_view(&self, data: &CreateViewData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize> {
+        self.mdm.create_view(&data.view_name, &data.view_def(), tx)?;
+        Ok(0)
+    }
+
+    fn execute_create_index(&self, data: &CreateIndexData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize> {
+        self.mdm
+            .create_index(&data.idx_name, &data.tbl_name, &data.field_name, data.index_type, tx)?;
+        //fill the new index with the records already in the table
+        let infos = self.mdm.get_index_info(&data.tbl_name, tx)?;
+        let p = TablePlan::new(tx.clone(), &data.tbl_name, &self.mdm)?;
+        let mut ts = p.open_table_scan()?;
+        let mut records = Vec::new();
+        while ts.next()? {
+            records.push((ts.get_val(&data.field_name)?, ts.get_rid()));
+        }
+        ts.close();
+        infos[&data.field_name].load(records)?;
+        Ok(0)
+    }
+}
@@ -0,0 +1,80 @@
+use super::{Plan, SortPlan};
+use crate::error::DbResult;
+use crate::query::{MergeJoinScan, Scan};
+use crate::record_mgr::Schema;
+use crate::tx_mgr::Transaction;
+
+use std::sync::{Arc, Mutex};
+
+/*
+p1 joined with p2 on field1 equal to field2, both sides are sorted by
+their join field and then merged. The cost counts the sorting, which
+often makes the merge join dearer than reading a small p2 again and again
+*/
+pub struct MergeJoinPlan {
+    sp1: SortPlan,
+    sp2: SortPlan,
+    field1: String,
+    field2: String,
+    schema: Schema,
+}
+
+impl MergeJoinPlan {
+    pub fn new(
+        tx: Arc<Mutex<Transaction>>,
+        p1: Box<dyn Plan>,
+        p2: Box<dyn Plan>,
+        field1: &str,
+        field2: &str,
+    ) -> DbResult<Self> {
+        let mut schema = Schema::new();
+        schema.add_all(p1.schema());
+        schema.add_all(p2.schema());
+        let sp1 = SortPlan::new(tx.clone(), p1, &[field1.to_string()])?;
+        let sp2 = SortPlan::new(tx, p2, &[field2.to_string()])?;
+        Ok(MergeJoinPlan {
+            sp1,
+            sp2,
+            field1: field1.to_string(),
+            field2: field2.to_string(),
+            schema,
+        })
+    }
+}
+
+impl Plan for MergeJoinPlan {
+    fn open(&self) -> DbResult<Box<dyn Scan>> {
+        let s1 = self.sp1.open()?;
+        let s2 = self.sp2.open_sort_scan()?;
+        Ok(Box::new(MergeJoinScan::new(s1, s2, &self.field1, &self.field2)?))
+    }
+
+    fn blocks_accessed(&self) -> u64 {
+        self.sp1
+            .preprocessing_cost()
+            .saturating_add(self.sp1.blocks_accessed())
+            .saturating_add(self.sp2.preprocessing_cost())
+            .saturating_add(self.sp2.blocks_accessed())
+    }
+
+    //every value of the field with fewer values is taken to match
+    fn records_output(&self) -> usize {
+        let max_vals = self
+            .sp1
+            .distinct_values(&self.field1)
+            .max(self.sp2.distinct_values(&self.field2));
+        self.sp1.records_output().saturating_mul(self.sp2.records_output()) / max_vals
+    }
+
+    fn distinct_values(&self, field_name: &str) -> usize {
+        if self.sp1.schema().has_field(field_name) {
+            self.sp1.distinct_values(field_name)
+        } else {
+            self.sp2.distinct_values(field_name)
+        }
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+}
@@ -0,0 +1,79 @@
+pub mod basic_query_planner;
+pub mod basic_update_planner;
+pub mod group_by_plan;
+pub mod hash_join_plan;
+pub mod heuristic_query_planner;
+pub mod index_join_plan;
+pub mod index_select_plan;
+pub mod index_update_planner;
+pub mod merge_join_plan;
+pub mod planner;
+pub mod product_plan;
+pub mod project_plan;
+pub mod select_plan;
+pub mod sort_plan;
+pub mod table_plan;
+pub mod table_planner;
+#[cfg(test)]
+pub mod test;
+
+pub use basic_query_planner::BasicQueryPlanner;
+pub use basic_update_planner::BasicUpdatePlanner;
+pub use group_by_plan::GroupByPlan;
+pub use hash_join_plan::HashJoinPlan;
+pub use heuristic_query_planner::HeuristicQueryPlanner;
+pub use index_join_plan::IndexJoinPlan;
+pub use index_select_plan::IndexSelectPlan;
+pub use index_update_planner::IndexUpdatePlanner;
+pub use merge_join_plan::MergeJoinPlan;
+pub use planner::{Planner, QueryPlanner, UpdatePlanner};
+pub use product_plan::ProductPlan;
+pub use project_plan::ProjectPlan;
+pub use select_plan::SelectPlan;
+pub use sort_plan::SortPlan;
+pub use table_plan::TablePlan;
+pub use table_planner::TablePlanner;
+
+use crate::error::DbResult;
+use crate::query::Scan;
+use crate::record_mgr::Schema;
+
+use std::rc::Rc;
+
+/*
+a tree of relational operators like the scan it opens, but it only
+looks at the metadata, so the planner can compare the estimated cost
+of different trees before opening any of them. blocks_accessed is the
+number of block accesses needed to go through the whole output
+*/
+pub trait Plan {
+    fn open(&self) -> DbResult<Box<dyn Scan>>;
+    fn blocks_accessed(&self) -> u64;
+    fn records_output(&self) -> usize;
+    //never less than 1
+    fn distinct_values(&self, field_name: &str) -> usize;
+    fn schema(&self) -> &Schema;
+}
+
+//a plan shared by several candidate plans while the planner compares them
+impl<P: Plan + ?Sized> Plan for Rc<P> {
+    fn open(&self) -> DbResult<Box<dyn Scan>> {
+        (**self).open()
+    }
+
+    fn blocks_accessed(&self) -> u64 {
+        (**self).blocks_accessed()
+    }
+
+    fn records_output(&self) -> usize {
+        (**self).records_output()
+    }
+
+    fn distinct_values(&self, field_name: &str) -> usize {
+        (**self).distinct_values(field_name)
+    }
+
+    fn schema(&self) -> &Schema {
+        (**self).schema()
+    }
+}
@@ -0,0 +1,52 @@
+use super::Plan;
+use crate::error::DbResult;
+use crate::parse::*;
+use crate::tx_mgr::Transaction;
+
+use std::sync::{Arc, Mutex};
+
+//turn a select statement into a plan
+pub trait QueryPlanner: Send + Sync {
+    fn create_plan(&self, data: &QueryData, tx: &Arc<Mutex<Transaction>>) -> DbResult<Box<dyn Plan>>;
+}
+
+//carry out statements other than select, return the number of records affected
+pub trait UpdatePlanner: Send + Sync {
+    fn execute_insert(&self, data: &InsertData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize>;
+    fn execute_delete(&self, data: &DeleteData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize>;
+    fn execute_modify(&self, data: &ModifyData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize>;
+    fn execute_create_table(&self, data: &CreateTableData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize>;
+    fn execute_create_view(&self, data: &CreateViewData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize>;
+    fn execute_create_index(&self, data: &CreateIndexData, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize>;
+}
+
+/*
+entry point for sql text, the statement is parsed and handed to the
+query planner or update planner, both can be replaced by smarter ones
+*/
+pub struct Planner {
+    qp: Box<dyn QueryPlanner>,
+    up: Box<dyn UpdatePlanner>,
+}
+
+impl Planner {
+    pub fn new(qp: Box<dyn QueryPlanner>, up: Box<dyn UpdatePlanner>) -> Self {
+        Planner { qp, up }
+    }
+
+    pub fn create_query_plan(&self, sql: &str, tx: &Arc<Mutex<Transaction>>) -> DbResult<Box<dyn Plan>> {
+        let data = Parser::parse_query(sql)?;
+        self.qp.create_plan(&data, tx)
+    }
+
+    pub fn execute_update(&self, sql: &str, tx: &Arc<Mutex<Transaction>>) -> DbResult<usize> {
+        match Parser::parse_update_cmd(sql)? {
+            UpdateCmd::Insert(data) => self.up.execute_insert(&data, tx),
+            UpdateCmd::Delete(data) => self.up.execute_delete(&data, tx),
+            UpdateCmd::Modify(data) => self.up.execute_modify(&data, tx),
+            UpdateCmd::CreateTable(data) => self.up.execute_create_table(&data, tx),
+            UpdateCmd::CreateView(data) => self.up.execute_create_view(&data, tx),
+            UpdateCmd::CreateIndex(data) => self.up.execute_create_index(&data, tx),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use super::Plan;
+use crate::error::DbResult;
+use crate::query::{ProductScan, Scan};
+use crate::record_mgr::Schema;
+
+/*
+p2 is gone through once for every record of p1, so the order matters,
+the cheaper product has the plan with fewer records on the left
+*/
+pub struct ProductPlan {
+    p1: Box<dyn Plan>,
+    p2: Box<dyn Plan>,
+    schema: Schema,
+}
+
+impl ProductPlan {
+    pub fn new(p1: Box<dyn Plan>, p2: Box<dyn Plan>) -> Self {
+        let mut schema = Schema::new();
+        schema.add_all(p1.schema());
+        schema.add_all(p2.schema());
+        ProductPlan { p1, p2, schema }
+    }
+}
+
+impl Plan for ProductPlan {
+    fn open(&self) -> DbResult<Box<dyn Scan>> {
+        Ok(Box::new(ProductScan::new(self.p1.open()?, self.p2.open()?)?))
+    }
+
+    fn blocks_accessed(&self) -> u64 {
+        let r1 = self.p1.records_output() as u64;
+        self.p1
+            .blocks_accessed()
+            .saturating_add(r1.saturating_mul(self.p2.blocks_accessed()))
+    }
+
+    fn records_output(&self) -> usize {
+        self.p1.records_output().saturating_mul(self.p2.records_output())
+    }
+
+    fn distinct_values(&self, field_name: &str) -> usize {
+        if self.p1.schema().has_field(field_name) {
+            self.p1.distinct_values(field_name)
+        } else {
+            self.p2.distinct_values(field_name)
+        }
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+}
@@ -0,0 +1,48 @@
+use super::Plan;
+use crate::error::{DbError, DbResult};
+use crate::query::{ProjectScan, Scan};
+use crate::record_mgr::Schema;
+
+//same cost as the underlying plan, only the schema is narrowed
+pub struct ProjectPlan {
+    p: Box<dyn Plan>,
+    schema: Schema,
+}
+
+impl ProjectPlan {
+    pub fn new(p: Box<dyn Plan>, fields: &[String]) -> DbResult<Self> {
+        let mut schema = Schema::new();
+        for field_name in fields {
+            if !p.schema().has_field(field_name) {
+                return Err(DbError::FieldNotFound(field_name.clone()));
+            }
+            schema.add(field_name, p.schema());
+        }
+        Ok(ProjectPlan { p, schema })
+    }
+}
+
+impl Plan for ProjectPlan {
+    fn open(&self) -> DbResult<Box<dyn Scan>> {
+        Ok(Box::new(ProjectScan::new(
+            self.p.open()?,
+            self.schema.fields().to_vec(),
+        )))
+    }
+
+    fn blocks_accessed(&self) -> u64 {
+        self.p.blocks_accessed()
+    }
+
+    fn records_output(&self) -> usize {
+        self.p.records_output()
+    }
+
+    fn distinct_values(&self, field_name: &str) -> usize {
+        self.p.distinct_values(field_name)
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+}
@@ -0,0 +1,48 @@
+use super::Plan;
+use crate::error::DbResult;
+use crate::query::{Predicate, Scan, SelectScan};
+use crate::record_mgr::Schema;
+
+/*
+goes through every record of the underlying plan, each term of the
+predicate is taken to cut the output by its reduction factor
+*/
+pub struct SelectPlan {
+    p: Box<dyn Plan>,
+    pred: Predicate,
+}
+
+impl SelectPlan {
+    pub fn new(p: Box<dyn Plan>, pred: Predicate) -> Self {
+        SelectPlan { p, pred }
+    }
+}
+
+impl Plan for SelectPlan {
+    fn open(&self) -> DbResult<Box<dyn Scan>> {
+        Ok(Box::new(SelectScan::new(self.p.open()?, self.pred.clone())))
+    }
+
+    fn blocks_accessed(&self) -> u64 {
+        self.p.blocks_accessed()
+    }
+
+    fn records_output(&self) -> usize {
+        self.p.records_output() / self.pred.reduction_factor(self.p.as_ref())
+    }
+
+    fn distinct_values(&self, field_name: &str) -> usize {
+        if self.pred.equates_with_constant(field_name).is_some() {
+            return 1;
+        }
+        let dv = self.p.distinct_values(field_name);
+        match self.pred.equates_with_field(field_name) {
+            Some(other) => dv.min(self.p.distinct_values(other)),
+            None => dv,
+        }
+    }
+
+    fn schema(&self) -> &Schema {
+        self.p.schema()
+    }
+}
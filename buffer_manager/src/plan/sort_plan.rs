@@ -0,0 +1,164 @@
+use super::Plan;
+use crate::error::{DbError, DbResult};
+use crate::query::{Constant, RecordComparator, Scan, SortScan, UpdateScan};
+use crate::record_mgr::{Layout, Schema, TempTable};
+use crate::tx_mgr::Transaction;
+
+use std::cmp::Ordering;
+use std::sync::{Arc, Mutex};
+
+/*
+external merge sort of the records of p. Opening the plan does the work:
+    1. the records are read in chunks that fit into the available
+       buffers, each chunk is sorted in memory and saved as a run in a
+       temporary table
+    2. while there are more runs than half of the free buffers, groups
+       of runs are merged into longer runs
+    3. the scan merges the last runs while it is read
+every run being merged keeps one buffer pinned, and a merge into a new
+run needs one more for the output. The other half of the buffers is left
+to the consumer of the scan, which may be another sort like the other
+side of a merge join
+*/
+pub struct SortPlan {
+    tx: Arc<Mutex<Transaction>>,
+    p: Box<dyn Plan>,
+    schema: Schema,
+    comp: RecordComparator,
+}
+
+impl SortPlan {
+    pub fn new(tx: Arc<Mutex<Transaction>>, p: Box<dyn Plan>, sort_fields: &[String]) -> DbResult<Self> {
+        let schema = p.schema().clone();
+        if let Some(field_name) = sort_fields.iter().find(|f| !schema.has_field(f)) {
+            return Err(DbError::FieldNotFound(field_name.clone()));
+        }
+        Ok(SortPlan {
+            tx,
+            p,
+            schema,
+            comp: RecordComparator::new(sort_fields),
+        })
+    }
+
+    //sorted runs of the records open a sort scan over them
+    pub fn open_sort_scan(&self) -> DbResult<SortScan> {
+        //at least 2 runs are merged at a time, otherwise the sort would never end
+        let final_order = (self.available_buffs() / 2).max(2);
+        let mut src = self.p.open()?;
+        let runs = self.split_into_runs(src.as_mut());
+        src.close();
+        let mut runs = runs?;
+        while runs.len() > final_order {
+            runs = self.merge_runs(runs)?;
+        }
+        SortScan::new(runs, &self.schema, self.comp.clone())
+    }
+
+    //reading the records and writing the runs, done once when the plan is opened
+    pub fn preprocessing_cost(&self) -> u64 {
+        self.p.blocks_accessed().saturating_add(self.blocks_accessed())
+    }
+
+    fn available_buffs(&self) -> usize {
+        self.tx.lock().unwrap().available_buffs() as usize
+    }
+
+    fn split_into_runs(&self, src: &mut dyn Scan) -> DbResult<Vec<TempTable>> {
+        let fields = self.schema.fields();
+        let key_pos: Vec<usize> = self
+            .comp
+            .fields()
+            .iter()
+            .filter_map(|f| fields.iter().position(|g| g == f))
+            .collect();
+        let block_size = self.tx.lock().unwrap().block_size() as usize;
+        let recs_per_block = (block_size / Layout::new(self.schema.clone()).slot_size()).max(1);
+        let chunk_size = self.available_buffs().max(1) * recs_per_block;
+
+        let mut runs = Vec::new();
+        let mut records: Vec<Vec<Constant>> = Vec::new();
+        src.before_first()?;
+        while src.next()? {
+            let record = fields.iter().map(|f| src.get_val(f)).collect::<DbResult<_>>()?;
+            records.push(record);
+            if records.len() >= chunk_size {
+                runs.push(self.write_run(&mut records, &key_pos)?);
+            }
+        }
+        if !records.is_empty() {
+            runs.push(self.write_run(&mut records, &key_pos)?);
+        }
+        Ok(runs)
+    }
+
+    fn write_run(&self, records: &mut Vec<Vec<Constant>>, key_pos: &[usize]) -> DbResult<TempTable> {
+        records.sort_by(|r1, r2| {
+            key_pos
+                .iter()
+                .map(|&i| r1[i].cmp(&r2[i]))
+                .find(|ord| *ord != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        });
+        let run = TempTable::new(self.tx.clone(), &self.schema);
+        let mut dest = run.open()?;
+        for record in records.drain(..) {
+            dest.insert()?;
+            for (field_name, val) in self.schema.fields().iter().zip(record.iter()) {
+                dest.set_val(field_name, val)?;
+            }
+        }
+        dest.close();
+        Ok(run)
+    }
+
+    //one buffer is kept for the output run, closing the merge removes the merged runs
+    fn merge_runs(&self, runs: Vec<TempTable>) -> DbResult<Vec<TempTable>> {
+        let order = (self.available_buffs().saturating_sub(1)).max(2);
+        let mut result = Vec::new();
+        for group in runs.chunks(order) {
+            if group.len() == 1 {
+                result.push(group[0].clone());
+                continue;
+            }
+            let mut src = SortScan::new(group.to_vec(), &self.schema, self.comp.clone())?;
+            let run = TempTable::new(self.tx.clone(), &self.schema);
+            let mut dest = run.open()?;
+            while src.next()? {
+                dest.insert()?;
+                for field_name in self.schema.fields() {
+                    dest.set_val(field_name, &src.get_val(field_name)?)?;
+                }
+            }
+            src.close();
+            dest.close();
+            result.push(run);
+        }
+        Ok(result)
+    }
+}
+
+impl Plan for SortPlan {
+    fn open(&self) -> DbResult<Box<dyn Scan>> {
+        Ok(Box::new(self.open_sort_scan()?))
+    }
+
+    //the sorting is done once when the plan is opened, only reading the sorted records is counted
+    fn blocks_accessed(&self) -> u64 {
+        let block_size = self.tx.lock().unwrap().block_size() as usize;
+        let recs_per_block = (block_size / Layout::new(self.schema.clone()).slot_size()).max(1);
+        self.p.records_output().div_ceil(recs_per_block) as u64
+    }
+
+    fn records_output(&self) -> usize {
+        self.p.records_output()
+    }
+
+    fn distinct_values(&self, field_name: &str) -> usize {
+        self.p.distinct_values(field_name)
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+}
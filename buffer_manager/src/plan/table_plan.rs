@@ -0,0 +1,64 @@
+use super::Plan;
+use crate::error::DbResult;
+use crate::metadata_mgr::{MetadataMgr, StatInfo};
+use crate::query::Scan;
+use crate::record_mgr::{Layout, Schema, TableScan};
+use crate::tx_mgr::Transaction;
+
+use std::sync::{Arc, Mutex};
+
+//leaf of a plan tree, the cost comes from the statistics of the table
+pub struct TablePlan {
+    tx: Arc<Mutex<Transaction>>,
+    tbl_name: String,
+    layout: Arc<Layout>,
+    si: StatInfo,
+}
+
+impl TablePlan {
+    pub fn new(tx: Arc<Mutex<Transaction>>, tbl_name: &str, mdm: &MetadataMgr) -> DbResult<Self> {
+        let layout = mdm.get_layout(tbl_name, &tx)?;
+        let si = mdm.get_stat_info(tbl_name, &layout, &tx)?;
+        Ok(TablePlan {
+            tx,
+            tbl_name: tbl_name.to_string(),
+            layout: Arc::new(layout),
+            si,
+        })
+    }
+
+    pub fn table_name(&self) -> &str {
+        &self.tbl_name
+    }
+
+    pub fn layout(&self) -> Arc<Layout> {
+        self.layout.clone()
+    }
+
+    //for callers that modify the table
+    pub fn open_table_scan(&self) -> DbResult<TableScan> {
+        TableScan::new(self.tx.clone(), &self.tbl_name, self.layout.clone())
+    }
+}
+
+impl Plan for TablePlan {
+    fn open(&self) -> DbResult<Box<dyn Scan>> {
+        Ok(Box::new(self.open_table_scan()?))
+    }
+
+    fn blocks_accessed(&self) -> u64 {
+        self.si.blocks_accessed()
+    }
+
+    fn records_output(&self) -> usize {
+        self.si.records_output()
+    }
+
+    fn distinct_values(&self, field_name: &str) -> usize {
+        self.si.distinct_values(field_name)
+    }
+
+    fn schema(&self) -> &Schema {
+        self.layout.schema()
+    }
+}
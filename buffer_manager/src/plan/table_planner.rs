@@ -0,0 +1,209 @@
+use super::{HashJoinPlan, IndexJoinPlan, IndexSelectPlan, MergeJoinPlan, Plan, ProductPlan, SelectPlan, TablePlan};
+use crate::metadata_mgr::IndexInfo;
+use crate::query::Predicate;
+use crate::record_mgr::Schema;
+use crate::tx_mgr::Transaction;
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+/*
+candidate plans for one table of the from clause, each one applies the
+terms of the query predicate as soon as their fields are available.
+my_plan is the plan of the table itself, or of a view. Indexes of a
+table are used for terms equating the indexed field with a constant,
+or with a field of the current plan. Without an index, a term equating
+two fields may be joined by hashing or merging when that reads fewer
+blocks than the product
+*/
+pub struct TablePlanner {
+    tx: Arc<Mutex<Transaction>>,
+    my_plan: Rc<dyn Plan>,
+    my_pred: Predicate,
+    my_schema: Schema,
+    my_table: Option<Rc<TablePlan>>,
+    my_indexes: HashMap<String, IndexInfo>,
+}
+
+impl TablePlanner {
+    pub fn new(tx: Arc<Mutex<Transaction>>, my_plan: Box<dyn Plan>, my_pred: Predicate) -> Self {
+        let my_schema = my_plan.schema().clone();
+        TablePlanner {
+            tx,
+            my_plan: Rc::from(my_plan),
+            my_pred,
+            my_schema,
+            my_table: None,
+            my_indexes: HashMap::new(),
+        }
+    }
+
+    //indexes are keyed by the indexed field like MetadataMgr::get_index_info returns them
+    pub fn with_indexes(
+        tx: Arc<Mutex<Transaction>>,
+        my_table: TablePlan,
+        my_indexes: HashMap<String, IndexInfo>,
+        my_pred: Predicate,
+    ) -> Self {
+        let my_schema = my_table.schema().clone();
+        let my_table = Rc::new(my_table);
+        TablePlanner {
+            tx,
+            my_plan: my_table.clone(),
+            my_pred,
+            my_schema,
+            my_table: Some(my_table),
+            my_indexes,
+        }
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.my_schema
+    }
+
+    //the table on its own with the terms that concern only this table
+    pub fn make_select_plan(&self) -> Box<dyn Plan> {
+        let p = self
+            .make_index_select()
+            .unwrap_or_else(|| Box::new(self.my_plan.clone()));
+        self.add_select_pred(p)
+    }
+
+    //None if no term of the predicate joins the table with the current plan
+    pub fn make_join_plan(&self, current: &Rc<dyn Plan>) -> Option<Box<dyn Plan>> {
+        let join_pred = self.my_pred.join_sub_pred(&self.my_schema, current.schema());
+        if join_pred.is_empty() {
+            return None;
+        }
+        if let Some(p) = self.make_index_join(current) {
+            return Some(p);
+        }
+        let mut best = self.make_product_join(current);
+        for p in [self.make_hash_join(current), self.make_merge_join(current)]
+            .into_iter()
+            .flatten()
+        {
+            if p.blocks_accessed() < best.blocks_accessed() {
+                best = p;
+            }
+        }
+        Some(best)
+    }
+
+    pub fn make_product_plan(&self, current: &Rc<dyn Plan>) -> Box<dyn Plan> {
+        let p = self.add_select_pred(Box::new(self.my_plan.clone()));
+        Box::new(ProductPlan::new(Box::new(current.clone()), p))
+    }
+
+    fn make_index_select(&self) -> Option<Box<dyn Plan>> {
+        let my_table = self.my_table.as_ref()?;
+        self.indexes().find_map(|(field_name, ii)| {
+            let val = self.my_pred.equates_with_constant(field_name)?;
+            let p: Box<dyn Plan> = Box::new(IndexSelectPlan::new(my_table.clone(), ii.clone(), val.clone()));
+            Some(p)
+        })
+    }
+
+    fn make_index_join(&self, current: &Rc<dyn Plan>) -> Option<Box<dyn Plan>> {
+        let my_table = self.my_table.as_ref()?;
+        let (field_name, ii, outer_field) = self.indexes().find_map(|(field_name, ii)| {
+            let outer_field = self.my_pred.equates_with_field(field_name)?;
+            current
+                .schema()
+                .has_field(outer_field)
+                .then_some((field_name, ii, outer_field))
+        })?;
+        let p = IndexJoinPlan::new(Box::new(current.clone()), my_table.clone(), ii.clone(), outer_field);
+        let p = self.add_select_pred(Box::new(p));
+        Some(self.add_other_join_pred(p, current.schema(), field_name, outer_field))
+    }
+
+    //the current plan is probed against a hash table of this table
+    fn make_hash_join(&self, current: &Rc<dyn Plan>) -> Option<Box<dyn Plan>> {
+        let (field_name, outer_field) = self.join_fields(current.schema())?;
+        let p = HashJoinPlan::new(
+            self.tx.clone(),
+            Box::new(current.clone()),
+            self.make_select_plan(),
+            outer_field,
+            field_name,
+        )
+        .ok()?;
+        Some(self.add_other_join_pred(Box::new(p), current.schema(), field_name, outer_field))
+    }
+
+    fn make_merge_join(&self, current: &Rc<dyn Plan>) -> Option<Box<dyn Plan>> {
+        let (field_name, outer_field) = self.join_fields(current.schema())?;
+        let p = MergeJoinPlan::new(
+            self.tx.clone(),
+            Box::new(current.clone()),
+            self.make_select_plan(),
+            outer_field,
+            field_name,
+        )
+        .ok()?;
+        Some(self.add_other_join_pred(Box::new(p), current.schema(), field_name, outer_field))
+    }
+
+    //the first field of this table equated with a field of the current plan
+    fn join_fields<'a>(&'a self, current_schema: &Schema) -> Option<(&'a str, &'a str)> {
+        self.my_schema.fields().iter().find_map(|field_name| {
+            let outer_field = self.my_pred.equates_with_field(field_name)?;
+            current_schema
+                .has_field(outer_field)
+                .then_some((field_name.as_str(), outer_field))
+        })
+    }
+
+    //sorted by field, so the same query always gets the same plan
+    fn indexes(&self) -> impl Iterator<Item = (&String, &IndexInfo)> {
+        let mut indexes: Vec<_> = self.my_indexes.iter().collect();
+        indexes.sort_by_key(|(field_name, _)| *field_name);
+        indexes.into_iter()
+    }
+
+    fn make_product_join(&self, current: &Rc<dyn Plan>) -> Box<dyn Plan> {
+        let p = self.make_product_plan(current);
+        self.add_join_pred(p, current.schema())
+    }
+
+    fn add_select_pred(&self, p: Box<dyn Plan>) -> Box<dyn Plan> {
+        let select_pred = self.my_pred.select_sub_pred(&self.my_schema);
+        if select_pred.is_empty() {
+            p
+        } else {
+            Box::new(SelectPlan::new(p, select_pred))
+        }
+    }
+
+    //the join already matches the join fields, checking that term again would cut the estimate twice
+    fn add_other_join_pred(
+        &self,
+        p: Box<dyn Plan>,
+        current_schema: &Schema,
+        field_name: &str,
+        outer_field: &str,
+    ) -> Box<dyn Plan> {
+        let mut join_pred = Predicate::new();
+        for term in self.my_pred.join_sub_pred(current_schema, &self.my_schema).terms() {
+            if term.equates_with_field(field_name) != Some(outer_field) {
+                join_pred.conjoin_with(Predicate::with_term(term.clone()));
+            }
+        }
+        if join_pred.is_empty() {
+            p
+        } else {
+            Box::new(SelectPlan::new(p, join_pred))
+        }
+    }
+
+    fn add_join_pred(&self, p: Box<dyn Plan>, current_schema: &Schema) -> Box<dyn Plan> {
+        let join_pred = self.my_pred.join_sub_pred(current_schema, &self.my_schema);
+        if join_pred.is_empty() {
+            p
+        } else {
+            Box::new(SelectPlan::new(p, join_pred))
+        }
+    }
+}
@@ -0,0 +1,681 @@
+use super::*;
+use crate::error::DbError;
+use crate::metadata_mgr::REFRESH_LIMIT;
+use crate::parse::Parser;
+use crate::query::Constant;
+use crate::record_mgr::FieldType;
+use crate::server::RustDB;
+use crate::tx_mgr::Transaction;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+fn remove_dir(directory: &str) {
+    if Path::new(directory).exists() {
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
+
+//60 students in majors 10, 20, 30, and 3 departments
+fn create_tables(db: &RustDB, tx: &Arc<Mutex<Transaction>>) {
+    let planner = db.planner();
+    planner
+        .execute_update("create table student (sid int, sname varchar(10), majorid int)", tx)
+        .unwrap();
+    planner
+        .execute_update("create table dept (did int, dname varchar(10))", tx)
+        .unwrap();
+    for i in 0..60 {
+        let sql = format!(
+            "insert into student (sid, sname, majorid) values ({}, 's{}', {})",
+            i,
+            i,
+            (i % 3 + 1) * 10
+        );
+        assert_eq!(planner.execute_update(&sql, tx).unwrap(), 1);
+    }
+    for (did, dname) in [(10, "compsci"), (20, "math"), (30, "drama")] {
+        let sql = format!("insert into dept (did, dname) values ({}, '{}')", did, dname);
+        planner.execute_update(&sql, tx).unwrap();
+    }
+}
+
+//values of every output record in the order of the plan schema
+fn run_query(db: &RustDB, sql: &str, tx: &Arc<Mutex<Transaction>>) -> Vec<Vec<Constant>> {
+    let p = db.planner().create_query_plan(sql, tx).unwrap();
+    let fields = p.schema().fields().to_vec();
+    let mut s = p.open().unwrap();
+    let mut rows = Vec::new();
+    while s.next().unwrap() {
+        rows.push(fields.iter().map(|f| s.get_val(f).unwrap()).collect());
+    }
+    s.close();
+    rows
+}
+
+#[test]
+fn test_query_and_update_planner() {
+    let directory = "plannertest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let planner = db.planner();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    create_tables(&db, &tx);
+
+    let rows = run_query(
+        &db,
+        "select sname, dname from student, dept where majorid = did and sid = 4",
+        &tx,
+    );
+    assert_eq!(rows, [[Constant::from("s4"), Constant::from("math")]]);
+
+    let n = planner
+        .execute_update("update student set majorid = 40 where majorid = 10", &tx)
+        .unwrap();
+    assert_eq!(n, 20);
+    let n = planner
+        .execute_update("delete from student where majorid = 30", &tx)
+        .unwrap();
+    assert_eq!(n, 20);
+    //field to field assignment
+    planner
+        .execute_update("update student set sid = majorid where sname = 's0'", &tx)
+        .unwrap();
+    tx.lock().unwrap().commit().unwrap();
+
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    assert_eq!(run_query(&db, "select sid from student", &tx).len(), 40);
+    assert_eq!(
+        run_query(&db, "select sid from student where majorid = 40", &tx).len(),
+        20
+    );
+    assert_eq!(
+        run_query(&db, "select majorid from student where sname = 's0'", &tx),
+        [[Constant::Int(40)]]
+    );
+    assert_eq!(
+        run_query(&db, "select sid from student where sname = 's0'", &tx),
+        [[Constant::Int(40)]]
+    );
+
+    //errors found while planning or executing
+    let err = planner.create_query_plan("select sid from course", &tx).err().unwrap();
+    assert!(matches!(err, DbError::TableNotFound(_)));
+    let err = planner
+        .create_query_plan("select gradyear from student", &tx)
+        .err()
+        .unwrap();
+    assert!(matches!(err, DbError::FieldNotFound(_)));
+    let err = planner.create_query_plan("select sid from", &tx).err().unwrap();
+    assert!(matches!(err, DbError::BadSyntax { .. }));
+    let err = planner
+        .execute_update("insert into student (sid, sname) values ('x', 'y')", &tx)
+        .unwrap_err();
+    assert!(matches!(err, DbError::TypeMismatch(_)));
+    tx.lock().unwrap().rollback().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_view_is_expanded() {
+    let directory = "viewplannertest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let planner = db.planner();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    create_tables(&db, &tx);
+
+    planner
+        .execute_update(
+            "create view mathstudent as select sid, sname from student, dept where majorid = did and dname = 'math'",
+            &tx,
+        )
+        .unwrap();
+    let rows = run_query(&db, "select sname from mathstudent where sid = 7", &tx);
+    assert_eq!(rows, [[Constant::from("s7")]]);
+    assert_eq!(run_query(&db, "select sid from mathstudent", &tx).len(), 20);
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_plan_cost_estimates() {
+    let directory = "plancosttest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let mdm = db.metadata_mgr();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    create_tables(&db, &tx);
+    //statistics cached by the inserts are refreshed
+    mdm.updated(REFRESH_LIMIT);
+
+    //26 bytes per student slot, 15 students in one block
+    let student = TablePlan::new(tx.clone(), "student", &mdm).unwrap();
+    assert_eq!(student.blocks_accessed(), 4);
+    assert_eq!(student.records_output(), 60);
+    assert_eq!(student.distinct_values("majorid"), 3);
+    let dept = TablePlan::new(tx.clone(), "dept", &mdm).unwrap();
+    assert_eq!(dept.blocks_accessed(), 1);
+
+    let pred = Parser::parse_query("select sid from student where majorid = 20")
+        .unwrap()
+        .pred;
+    let select = SelectPlan::new(Box::new(student), pred);
+    assert_eq!(select.records_output(), 20);
+    assert_eq!(select.blocks_accessed(), 4);
+    assert_eq!(select.distinct_values("majorid"), 1);
+
+    let student = TablePlan::new(tx.clone(), "student", &mdm).unwrap();
+    let product = ProductPlan::new(Box::new(student), Box::new(dept));
+    //dept is read once for every student
+    assert_eq!(product.blocks_accessed(), 4 + 60);
+    assert_eq!(product.records_output(), 180);
+
+    let pred = Parser::parse_query("select sid from student, dept where majorid = did")
+        .unwrap()
+        .pred;
+    let join = SelectPlan::new(Box::new(product), pred);
+    assert_eq!(join.records_output(), 60);
+    assert_eq!(join.distinct_values("did"), 3);
+
+    //the basic planner puts the same tree together
+    let data = Parser::parse_query("select sname, dname from student, dept where majorid = did").unwrap();
+    let p = BasicQueryPlanner::new(mdm.clone()).create_plan(&data, &tx).unwrap();
+    assert_eq!(p.records_output(), 60);
+    assert_eq!(p.blocks_accessed(), 64);
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+fn sorted(mut rows: Vec<Vec<Constant>>) -> Vec<Vec<Constant>> {
+    rows.sort();
+    rows
+}
+
+#[test]
+fn test_heuristic_join_order() {
+    let directory = "heuristicplannertest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let mdm = db.metadata_mgr();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    create_tables(&db, &tx);
+    db.planner()
+        .execute_update("create table course (cid int, deptid int)", &tx)
+        .unwrap();
+    for i in 0..6 {
+        let sql = format!("insert into course (cid, deptid) values ({}, {})", i, (i % 3 + 1) * 10);
+        db.planner().execute_update(&sql, &tx).unwrap();
+    }
+    mdm.updated(REFRESH_LIMIT);
+    let basic = BasicQueryPlanner::new(mdm.clone());
+    let heuristic = HeuristicQueryPlanner::new(mdm.clone());
+
+    //dept has fewer records so it goes outside, student fits in the buffers and is hashed once instead of read 3 times
+    let data = Parser::parse_query("select sname, dname from student, dept where majorid = did").unwrap();
+    let p = heuristic.create_plan(&data, &tx).unwrap();
+    assert_eq!(p.records_output(), 60);
+    assert_eq!(p.blocks_accessed(), 1 + 4);
+
+    //selecting one student makes student the most restrictive
+    let sql = "select sname, dname from student, dept where majorid = did and sid = 4";
+    let data = Parser::parse_query(sql).unwrap();
+    let p = heuristic.create_plan(&data, &tx).unwrap();
+    assert_eq!(p.blocks_accessed(), 4 + 1);
+    assert_eq!(p.records_output(), 1);
+    assert_eq!(
+        run_query(&db, sql, &tx),
+        [[Constant::from("s4"), Constant::from("math")]]
+    );
+
+    //course is only joined to dept, so it waits until dept is in the plan
+    let sql = "select sname, cid from course, student, dept where majorid = did and deptid = did and sid = 5";
+    let data = Parser::parse_query(sql).unwrap();
+    let p = heuristic.create_plan(&data, &tx).unwrap();
+    let b = basic.create_plan(&data, &tx).unwrap();
+    assert!(p.blocks_accessed() < b.blocks_accessed());
+    let rows = sorted(run_query(&db, sql, &tx));
+    assert_eq!(
+        rows,
+        [
+            [Constant::from("s5"), Constant::Int(2)],
+            [Constant::from("s5"), Constant::Int(5)]
+        ]
+    );
+
+    //product without a join term gives the same records as the basic planner
+    let data = Parser::parse_query("select sid, cid from student, course where sid = cid").unwrap();
+    let mut results = Vec::new();
+    for planner in [&basic as &dyn QueryPlanner, &heuristic] {
+        let p = planner.create_plan(&data, &tx).unwrap();
+        let mut s = p.open().unwrap();
+        let mut rows = Vec::new();
+        while s.next().unwrap() {
+            rows.push(vec![s.get_val("sid").unwrap(), s.get_val("cid").unwrap()]);
+        }
+        results.push(sorted(rows));
+    }
+    assert_eq!(results[0].len(), 6);
+    assert_eq!(results[0], results[1]);
+    let data = Parser::parse_query("select sid, cid from student, course").unwrap();
+    assert_eq!(heuristic.create_plan(&data, &tx).unwrap().records_output(), 360);
+
+    let data = Parser::parse_query("select sid from student, dept where gradyear = did").unwrap();
+    assert!(matches!(
+        heuristic.create_plan(&data, &tx),
+        Err(DbError::FieldNotFound(_))
+    ));
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_index_plans() {
+    let directory = "indexplantest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let mdm = db.metadata_mgr();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    create_tables(&db, &tx);
+    let planner = db.planner();
+    planner
+        .execute_update("create index majoridx on student (majorid)", &tx)
+        .unwrap();
+    planner
+        .execute_update("create index dididx on dept (did) using btree", &tx)
+        .unwrap();
+    mdm.updated(REFRESH_LIMIT);
+
+    //16 bytes per index slot, the 60 index records of student fill 2 blocks
+    let student = Rc::new(TablePlan::new(tx.clone(), "student", &mdm).unwrap());
+    let major_ii = mdm.get_index_info("student", &tx).unwrap().remove("majorid").unwrap();
+    let select = IndexSelectPlan::new(student.clone(), major_ii.clone(), Constant::Int(20));
+    assert_eq!(select.records_output(), 20);
+    //one block for each record found, the hash bucket costs nothing as the index is so small
+    assert_eq!(select.blocks_accessed(), 20);
+    assert_eq!(select.distinct_values("majorid"), 1);
+    assert_eq!(select.distinct_values("sid"), 60);
+
+    let dept = Rc::new(TablePlan::new(tx.clone(), "dept", &mdm).unwrap());
+    let did_ii = mdm.get_index_info("dept", &tx).unwrap().remove("did").unwrap();
+    let join = IndexJoinPlan::new(Box::new(student.clone()), dept, did_ii, "majorid");
+    assert_eq!(join.records_output(), 60);
+    //the b-tree of 3 departments is one leaf, read once for every student
+    assert_eq!(join.blocks_accessed(), 4 + 60 + 60);
+    assert_eq!(join.distinct_values("did"), 3);
+    assert!(join.schema().has_field("sname") && join.schema().has_field("dname"));
+
+    //the planner selects through the index
+    let heuristic = HeuristicQueryPlanner::new(mdm.clone());
+    let sql = "select sid from student where majorid = 20";
+    let p = heuristic.create_plan(&Parser::parse_query(sql).unwrap(), &tx).unwrap();
+    assert_eq!(p.blocks_accessed(), 20);
+    let rows = sorted(run_query(&db, sql, &tx));
+    let expected: Vec<Vec<Constant>> = (0..60).filter(|i| i % 3 == 1).map(|i| vec![Constant::Int(i)]).collect();
+    assert_eq!(rows, expected);
+
+    //dept goes outside and student is searched through its free hash index for each department
+    let sql = "select sname, dname from student, dept where majorid = did";
+    let data = Parser::parse_query(sql).unwrap();
+    let p = heuristic.create_plan(&data, &tx).unwrap();
+    assert_eq!(p.records_output(), 60);
+    assert_eq!(p.blocks_accessed(), 1 + 60);
+    let b = BasicQueryPlanner::new(mdm.clone()).create_plan(&data, &tx).unwrap();
+    let mut s = b.open().unwrap();
+    let mut expected = Vec::new();
+    while s.next().unwrap() {
+        expected.push(vec![s.get_val("sname").unwrap(), s.get_val("dname").unwrap()]);
+    }
+    s.close();
+    assert_eq!(sorted(run_query(&db, sql, &tx)), sorted(expected));
+
+    //join terms besides the indexed one are still checked, no student has the id of its major
+    let sql = "select sname, dname from student, dept where majorid = did and sid = did";
+    assert!(run_query(&db, sql, &tx).is_empty());
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_sort_plan() {
+    let directory = "sortplantest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let mdm = db.metadata_mgr();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let planner = db.planner();
+    planner
+        .execute_update("create table enroll (eid int, grade varchar(2))", &tx)
+        .unwrap();
+    let grades = ["b", "a", "c", "a+", "b-"];
+    for i in 0..300 {
+        let sql = format!(
+            "insert into enroll (eid, grade) values ({}, '{}')",
+            i * 7919 % 300,
+            grades[i % 5]
+        );
+        planner.execute_update(&sql, &tx).unwrap();
+    }
+    mdm.updated(REFRESH_LIMIT);
+
+    //few buffers are left, so the runs are short and merged more than once
+    let pinned: Vec<_> = (0..5).map(|i| crate::file_mgr::BlockId::new("pinned", i)).collect();
+    for blk in &pinned {
+        tx.lock().unwrap().append("pinned").unwrap();
+        tx.lock().unwrap().pin(blk).unwrap();
+    }
+    let sort_fields = ["grade".to_string(), "eid".to_string()];
+    let enroll = TablePlan::new(tx.clone(), "enroll", &mdm).unwrap();
+    let p = SortPlan::new(tx.clone(), Box::new(enroll), &sort_fields).unwrap();
+    assert_eq!(p.records_output(), 300);
+    let temp_files = || {
+        fs::read_dir(directory)
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().ends_with("temp"))
+            .count()
+    };
+    let mut s = p.open().unwrap();
+    //the runs were merged down to 2, the merged ones are removed
+    assert!((1..=2).contains(&temp_files()));
+    let mut rows = Vec::new();
+    while s.next().unwrap() {
+        rows.push((s.get_string("grade").unwrap(), s.get_int("eid").unwrap()));
+    }
+    s.close();
+    assert_eq!(temp_files(), 0);
+    let mut expected: Vec<(String, i32)> = (0..300)
+        .map(|i| (grades[i % 5].to_string(), (i * 7919 % 300) as i32))
+        .collect();
+    expected.sort();
+    assert_eq!(rows, expected);
+    for blk in &pinned {
+        tx.lock().unwrap().unpin(blk);
+    }
+    assert_eq!(tx.lock().unwrap().available_buffs(), 8);
+    tx.lock().unwrap().commit().unwrap();
+
+    //a run left behind by a failed query is gone when the database starts again
+    fs::write(format!("{}/1temp", directory), [0; 400]).unwrap();
+    drop(db);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    assert!(fs::read_dir(directory)
+        .unwrap()
+        .all(|e| !e.unwrap().file_name().to_string_lossy().ends_with("temp")));
+
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let p = TablePlan::new(tx.clone(), "enroll", &db.metadata_mgr()).unwrap();
+    let err = SortPlan::new(tx.clone(), Box::new(p), &["gradyear".to_string()]).err();
+    assert!(matches!(err, Some(DbError::FieldNotFound(_))));
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+//rows of the scan with the fields, in the order they come out
+fn scan_rows(p: &dyn Plan, fields: &[&str]) -> Vec<Vec<Constant>> {
+    let mut s = p.open().unwrap();
+    let mut rows = Vec::new();
+    while s.next().unwrap() {
+        rows.push(fields.iter().map(|f| s.get_val(f).unwrap()).collect());
+    }
+    s.close();
+    rows
+}
+
+#[test]
+fn test_join_plans() {
+    let directory = "joinplantest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let mdm = db.metadata_mgr();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    create_tables(&db, &tx);
+    mdm.updated(REFRESH_LIMIT);
+    let expected: Vec<Vec<Constant>> = sorted(
+        (0..60)
+            .map(|i| vec![Constant::Int(i), Constant::from(["compsci", "math", "drama"][i as usize % 3])])
+            .collect(),
+    );
+
+    //both sides are sorted first, writing the sorted runs and reading them back
+    let student = TablePlan::new(tx.clone(), "student", &mdm).unwrap();
+    let dept = TablePlan::new(tx.clone(), "dept", &mdm).unwrap();
+    let merge = MergeJoinPlan::new(tx.clone(), Box::new(student), Box::new(dept), "majorid", "did").unwrap();
+    assert_eq!(merge.blocks_accessed(), (4 + 4 + 4) + (1 + 1 + 1));
+    assert_eq!(merge.records_output(), 60);
+    let rows = scan_rows(&merge, &["sid", "dname"]);
+    let majors: Vec<_> = rows.iter().map(|r| r[1].clone()).collect();
+    assert!(majors.windows(2).all(|w| {
+        let order = ["compsci", "math", "drama"].map(Constant::from);
+        order.iter().position(|c| *c == w[0]) <= order.iter().position(|c| *c == w[1])
+    }));
+    assert_eq!(sorted(rows), expected);
+
+    //dept is hashed in memory, each side is read once
+    let student = TablePlan::new(tx.clone(), "student", &mdm).unwrap();
+    let dept = TablePlan::new(tx.clone(), "dept", &mdm).unwrap();
+    let hash = HashJoinPlan::new(tx.clone(), Box::new(student), Box::new(dept), "majorid", "did").unwrap();
+    assert_eq!(hash.blocks_accessed(), 4 + 1);
+    assert_eq!(hash.records_output(), 60);
+    assert_eq!(sorted(scan_rows(&hash, &["sid", "dname"])), expected);
+
+    let student = TablePlan::new(tx.clone(), "student", &mdm).unwrap();
+    let dept = TablePlan::new(tx.clone(), "dept", &mdm).unwrap();
+    let err = HashJoinPlan::new(tx.clone(), Box::new(student), Box::new(dept), "majorid", "sid").err();
+    assert!(matches!(err, Some(DbError::FieldNotFound(_))));
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_merge_join_in_small_pool() {
+    let directory = "mergejointest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 6).unwrap();
+    let mdm = db.metadata_mgr();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let planner = db.planner();
+    planner
+        .execute_update("create table enroll (eid int, sectid int, grade varchar(20))", &tx)
+        .unwrap();
+    planner
+        .execute_update("create table section (sid int, prof varchar(20))", &tx)
+        .unwrap();
+    //enroll is read through one buffer, each run has the records of the other 5, 6 runs in all
+    let slot_size = mdm.get_layout("enroll", &tx).unwrap().slot_size();
+    let num_enrolls = 6 * 5 * (400 / slot_size) as i32;
+    for i in 0..num_enrolls {
+        let sql = format!(
+            "insert into enroll (eid, sectid, grade) values ({}, {}, 'a')",
+            i,
+            i * 7919 % 200
+        );
+        planner.execute_update(&sql, &tx).unwrap();
+    }
+    for i in 0..200 {
+        let sql = format!("insert into section (sid, prof) values ({}, 'p{}')", i * 7919 % 200, i);
+        planner.execute_update(&sql, &tx).unwrap();
+    }
+    mdm.updated(REFRESH_LIMIT);
+    assert_eq!(tx.lock().unwrap().available_buffs(), 6);
+
+    //the 6 runs of enroll are merged to leave buffers for sorting section, instead of pinning all of them
+    let enroll = TablePlan::new(tx.clone(), "enroll", &mdm).unwrap();
+    let section = TablePlan::new(tx.clone(), "section", &mdm).unwrap();
+    let merge = MergeJoinPlan::new(tx.clone(), Box::new(enroll), Box::new(section), "sectid", "sid").unwrap();
+    let temp_files = || {
+        fs::read_dir(directory)
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().ends_with("temp"))
+            .count()
+    };
+    let mut s = merge.open().unwrap();
+    //only the last runs of both sides are left, at most 2 each
+    assert!((2..=4).contains(&temp_files()));
+    let mut rows = Vec::new();
+    while s.next().unwrap() {
+        rows.push((s.get_int("eid").unwrap(), s.get_int("sid").unwrap()));
+    }
+    s.close();
+    assert_eq!(temp_files(), 0);
+    rows.sort();
+    let expected: Vec<(i32, i32)> = (0..num_enrolls).map(|i| (i, i * 7919 % 200)).collect();
+    assert_eq!(rows, expected);
+    assert_eq!(tx.lock().unwrap().available_buffs(), 6);
+
+    //writes into the runs are logged, rolling back must not bring the removed runs back
+    tx.lock().unwrap().rollback().unwrap();
+    assert_eq!(temp_files(), 0);
+    remove_dir(directory);
+}
+
+#[test]
+fn test_grace_hash_join_in_small_pool() {
+    let directory = "gracejointest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let mdm = db.metadata_mgr();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    create_tables(&db, &tx);
+    let planner = db.planner();
+    planner
+        .execute_update("create table enroll (eid int, studentid int)", &tx)
+        .unwrap();
+    for i in 0..300 {
+        let sql = format!("insert into enroll (eid, studentid) values ({}, {})", i, i % 60);
+        planner.execute_update(&sql, &tx).unwrap();
+    }
+    mdm.updated(REFRESH_LIMIT);
+    let expected: Vec<(i32, String)> = (0..300).map(|i| (i, format!("s{}", i % 60))).collect();
+    let temp_files = || {
+        fs::read_dir(directory)
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().ends_with("temp"))
+            .count()
+    };
+    //the rows and the number of partition files while the scan is open, closing removes them
+    let join_rows = |p: &dyn Plan| {
+        let mut s = p.open().unwrap();
+        let partitions = temp_files();
+        let mut rows = Vec::new();
+        while s.next().unwrap() {
+            rows.push((s.get_int("eid").unwrap(), s.get_string("sname").unwrap()));
+        }
+        s.close();
+        assert_eq!(temp_files(), 0);
+        rows.sort();
+        (rows, partitions)
+    };
+
+    //5 buffers are left and enroll does not fit, the 3 left after opening both sides are the partitions
+    let pinned: Vec<_> = (0..5).map(|i| crate::file_mgr::BlockId::new("pinned", i)).collect();
+    for blk in &pinned[..3] {
+        tx.lock().unwrap().append("pinned").unwrap();
+        tx.lock().unwrap().pin(blk).unwrap();
+    }
+    let student = TablePlan::new(tx.clone(), "student", &mdm).unwrap();
+    let enroll = TablePlan::new(tx.clone(), "enroll", &mdm).unwrap();
+    let enroll_blocks = enroll.blocks_accessed();
+    assert!(enroll_blocks > 5);
+    let grace = HashJoinPlan::new(tx.clone(), Box::new(student), Box::new(enroll), "sid", "studentid").unwrap();
+    assert_eq!(grace.blocks_accessed(), 4 + enroll_blocks + 2 * (4 + enroll_blocks));
+    assert_eq!(join_rows(&grace), (expected.clone(), 2 * 3));
+
+    //the product pins a buffer for each of its sides, 2 buffers are left for the partitions
+    let dept = TablePlan::new(tx.clone(), "dept", &mdm).unwrap();
+    let student = TablePlan::new(tx.clone(), "student", &mdm).unwrap();
+    let product = ProductPlan::new(Box::new(dept), Box::new(student));
+    let enroll = TablePlan::new(tx.clone(), "enroll", &mdm).unwrap();
+    let grace = HashJoinPlan::new(tx.clone(), Box::new(product), Box::new(enroll), "sid", "studentid").unwrap();
+    let tripled: Vec<_> = expected.iter().flat_map(|r| [r.clone(), r.clone(), r.clone()]).collect();
+    assert_eq!(join_rows(&grace), (tripled.clone(), 2 * 2));
+
+    //no buffer is left once both sides are open, enroll is hashed in memory
+    for blk in &pinned[3..] {
+        tx.lock().unwrap().append("pinned").unwrap();
+        tx.lock().unwrap().pin(blk).unwrap();
+    }
+    let dept = TablePlan::new(tx.clone(), "dept", &mdm).unwrap();
+    let student = TablePlan::new(tx.clone(), "student", &mdm).unwrap();
+    let product = ProductPlan::new(Box::new(dept), Box::new(student));
+    let enroll = TablePlan::new(tx.clone(), "enroll", &mdm).unwrap();
+    let grace = HashJoinPlan::new(tx.clone(), Box::new(product), Box::new(enroll), "sid", "studentid").unwrap();
+    assert_eq!(join_rows(&grace), (tripled, 0));
+    for blk in &pinned {
+        tx.lock().unwrap().unpin(blk);
+    }
+    assert_eq!(tx.lock().unwrap().available_buffs(), 8);
+    tx.lock().unwrap().commit().unwrap();
+    remove_dir(directory);
+}
+
+#[test]
+fn test_group_by_plan() {
+    let directory = "groupbyplantest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let mdm = db.metadata_mgr();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    create_tables(&db, &tx);
+    mdm.updated(REFRESH_LIMIT);
+
+    //sid 0, 3, ..., 57 are in major 10, the next ones in 20 and 30
+    let sql = "select majorid, count(sid), sum(sid), avg(sid), min(sname), max(sid) from student group by majorid";
+    assert_eq!(
+        run_query(&db, sql, &tx),
+        [
+            vec![10.into(), 20.into(), 570.into(), 28.into(), "s0".into(), 57.into()],
+            vec![20.into(), 20.into(), 590.into(), 29.into(), "s1".into(), 58.into()],
+            vec![30.into(), 20.into(), 610.into(), 30.into(), "s11".into(), 59.into()],
+        ]
+    );
+    let data = Parser::parse_query(sql).unwrap();
+    let p = db.planner().create_query_plan(sql, &tx).unwrap();
+    assert_eq!(p.records_output(), 3);
+    assert_eq!(p.schema().field_type("minofsname"), Some(FieldType::Varchar));
+    assert_eq!(p.schema().length("minofsname"), Some(10));
+    let b = BasicQueryPlanner::new(mdm.clone()).create_plan(&data, &tx).unwrap();
+    assert_eq!(b.records_output(), 3);
+
+    //groups of a join, and all records as one group
+    let sql = "select dname, count(sid) from student, dept where majorid = did and dname = 'math' group by dname";
+    assert_eq!(run_query(&db, sql, &tx), [[Constant::from("math"), Constant::Int(20)]]);
+    let sql = "select count(sid), max(sname) from student where majorid = 30";
+    assert_eq!(run_query(&db, sql, &tx), [[Constant::Int(20), Constant::from("s8")]]);
+    //no records give one group without group fields, and no group with them
+    let sql = "select count(sid), sum(sid) from student where majorid = 40";
+    assert_eq!(run_query(&db, sql, &tx), [[Constant::Int(0), Constant::Int(0)]]);
+    let sql = "select majorid, count(sid) from student where majorid = 40 group by majorid";
+    assert!(run_query(&db, sql, &tx).is_empty());
+    //min, max and avg of no records have no value
+    let sql = "select min(sname), max(sid), avg(sid) from student where majorid = 40";
+    let mut s = db.planner().create_query_plan(sql, &tx).unwrap().open().unwrap();
+    assert!(s.next().unwrap());
+    for field_name in ["minofsname", "maxofsid", "avgofsid"] {
+        assert!(matches!(s.get_val(field_name), Err(DbError::EmptyAggregation(_))));
+    }
+    assert!(!s.next().unwrap());
+    s.close();
+
+    //a selected field has to be grouped, and only ints are summed
+    let err = db
+        .planner()
+        .create_query_plan("select sname, count(sid) from student group by majorid", &tx)
+        .err();
+    assert!(matches!(err, Some(DbError::FieldNotFound(_))));
+    let err = db
+        .planner()
+        .create_query_plan("select sum(sname) from student", &tx)
+        .err();
+    assert!(matches!(err, Some(DbError::TypeMismatch(_))));
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
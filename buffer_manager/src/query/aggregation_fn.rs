@@ -0,0 +1,72 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregationKind {
+    Count,
+    Sum,
+    Min,
+    Max,
+    Avg,
+}
+
+impl AggregationKind {
+    //the name written in sql, also used for the output field
+    pub fn name(self) -> &'static str {
+        match self {
+            AggregationKind::Count => "count",
+            AggregationKind::Sum => "sum",
+            AggregationKind::Min => "min",
+            AggregationKind::Max => "max",
+            AggregationKind::Avg => "avg",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "count" => Some(AggregationKind::Count),
+            "sum" => Some(AggregationKind::Sum),
+            "min" => Some(AggregationKind::Min),
+            "max" => Some(AggregationKind::Max),
+            "avg" => Some(AggregationKind::Avg),
+            _ => None,
+        }
+    }
+}
+
+/*
+aggregation of one field over the records of a group. The output field
+is named like countofsid for count(sid), that is the name a query
+selects it by
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregationFn {
+    kind: AggregationKind,
+    field_name: String,
+}
+
+impl AggregationFn {
+    pub fn new(kind: AggregationKind, field_name: &str) -> Self {
+        AggregationFn {
+            kind,
+            field_name: field_name.to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> AggregationKind {
+        self.kind
+    }
+
+    pub fn field_name(&self) -> &str {
+        &self.field_name
+    }
+
+    pub fn output_name(&self) -> String {
+        format!("{}of{}", self.kind.name(), self.field_name)
+    }
+}
+
+impl fmt::Display for AggregationFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.kind.name(), self.field_name)
+    }
+}
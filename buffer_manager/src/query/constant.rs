@@ -0,0 +1,60 @@
+use std::fmt;
+
+//value of a field, or a literal written in sql
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Constant {
+    Int(i32),
+    Str(String),
+}
+
+impl Constant {
+    pub fn as_int(&self) -> Option<i32> {
+        match self {
+            Constant::Int(val) => Some(*val),
+            Constant::Str(_) => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Constant::Int(_) => None,
+            Constant::Str(val) => Some(val),
+        }
+    }
+
+    /*
+    the hash goes into file names and temporary partitions, so it must not
+    change between runs, fnv-1a is used instead of the randomly seeded std hasher
+    */
+    pub fn stable_hash(&self) -> u64 {
+        let bytes = match self {
+            Constant::Int(val) => val.to_be_bytes().to_vec(),
+            Constant::Str(val) => val.as_bytes().to_vec(),
+        };
+        bytes
+            .iter()
+            .fold(0xcbf29ce484222325u64, |h, b| (h ^ *b as u64).wrapping_mul(0x100000001b3))
+    }
+}
+
+impl From<i32> for Constant {
+    fn from(val: i32) -> Self {
+        Constant::Int(val)
+    }
+}
+
+impl From<&str> for Constant {
+    fn from(val: &str) -> Self {
+        Constant::Str(val.to_string())
+    }
+}
+
+//strings are quoted the way sql writes them, so that a query can be parsed again from its text
+impl fmt::Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constant::Int(val) => write!(f, "{}", val),
+            Constant::Str(val) => write!(f, "'{}'", val.replace('\'', "''")),
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use super::aggregation_fn::{AggregationFn, AggregationKind};
+use super::constant::Constant;
+use super::scan::Scan;
+use crate::error::{DbError, DbResult};
+
+//running value of one aggregation over the records of the current group
+struct Accumulator {
+    agg: AggregationFn,
+    output_name: String,
+    count: i64,
+    sum: i64,
+    val: Option<Constant>,
+}
+
+impl Accumulator {
+    fn new(agg: &AggregationFn) -> Self {
+        Accumulator {
+            agg: agg.clone(),
+            output_name: agg.output_name(),
+            count: 0,
+            sum: 0,
+            val: None,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.count = 0;
+        self.sum = 0;
+        self.val = None;
+    }
+
+    fn process_first(&mut self, s: &dyn Scan) -> DbResult<()> {
+        self.reset();
+        self.process_next(s)
+    }
+
+    fn process_next(&mut self, s: &dyn Scan) -> DbResult<()> {
+        let val = s.get_val(self.agg.field_name())?;
+        self.count += 1;
+        match self.agg.kind() {
+            AggregationKind::Count => {}
+            AggregationKind::Sum | AggregationKind::Avg => {
+                let val = val
+                    .as_int()
+                    .ok_or_else(|| DbError::TypeMismatch(self.agg.field_name().to_string()))?;
+                self.sum += val as i64;
+            }
+            AggregationKind::Min => {
+                if self.val.as_ref().is_none_or(|min| val < *min) {
+                    self.val = Some(val);
+                }
+            }
+            AggregationKind::Max => {
+                if self.val.as_ref().is_none_or(|max| val > *max) {
+                    self.val = Some(val);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /*
+    sum and avg are integers, they saturate instead of overflowing and avg
+    is rounded toward zero. Over no records count and sum are 0, the others
+    have no value
+    */
+    fn value(&self) -> DbResult<Constant> {
+        let clamp = |val: i64| val.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        let empty = || DbError::EmptyAggregation(self.agg.to_string());
+        match self.agg.kind() {
+            AggregationKind::Count => Ok(Constant::Int(clamp(self.count))),
+            AggregationKind::Sum => Ok(Constant::Int(clamp(self.sum))),
+            AggregationKind::Avg if self.count == 0 => Err(empty()),
+            AggregationKind::Avg => Ok(Constant::Int(clamp(self.sum / self.count))),
+            AggregationKind::Min | AggregationKind::Max => self.val.clone().ok_or_else(empty),
+        }
+    }
+}
+
+/*
+one record for every group of records of the underlying scan, which must
+be sorted by the group fields so that the records of a group are next to
+each other. The record has the group fields and the aggregations. With no
+group fields all records form one group, there is one even without any
+record, like sql's select count(sid) from an empty table
+*/
+pub struct GroupByScan {
+    s: Box<dyn Scan>,
+    group_fields: Vec<String>,
+    accumulators: Vec<Accumulator>,
+    group_val: Vec<Constant>,
+    //s is on the first record of the next group
+    more_groups: bool,
+    //no group fields and no records, the one empty group is still to come
+    empty_group: bool,
+}
+
+impl GroupByScan {
+    pub fn new(s: Box<dyn Scan>, group_fields: &[String], aggregates: &[AggregationFn]) -> DbResult<Self> {
+        let mut scan = GroupByScan {
+            s,
+            group_fields: group_fields.to_vec(),
+            accumulators: aggregates.iter().map(Accumulator::new).collect(),
+            group_val: Vec::new(),
+            more_groups: false,
+            empty_group: false,
+        };
+        scan.before_first()?;
+        Ok(scan)
+    }
+
+    fn current_group_val(&self) -> DbResult<Vec<Constant>> {
+        self.group_fields.iter().map(|f| self.s.get_val(f)).collect()
+    }
+}
+
+impl Scan for GroupByScan {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.s.before_first()?;
+        self.more_groups = self.s.next()?;
+        self.empty_group = self.group_fields.is_empty() && !self.more_groups;
+        Ok(())
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        if std::mem::take(&mut self.empty_group) {
+            for acc in &mut self.accumulators {
+                acc.reset();
+            }
+            self.group_val = Vec::new();
+            return Ok(true);
+        }
+        if !self.more_groups {
+            return Ok(false);
+        }
+        for acc in &mut self.accumulators {
+            acc.process_first(self.s.as_ref())?;
+        }
+        self.group_val = self.current_group_val()?;
+        loop {
+            self.more_groups = self.s.next()?;
+            if !self.more_groups || self.current_group_val()? != self.group_val {
+                return Ok(true);
+            }
+            for acc in &mut self.accumulators {
+                acc.process_next(self.s.as_ref())?;
+            }
+        }
+    }
+
+    fn get_int(&self, field_name: &str) -> DbResult<i32> {
+        self.get_val(field_name)?
+            .as_int()
+            .ok_or_else(|| DbError::TypeMismatch(field_name.to_string()))
+    }
+
+    fn get_string(&self, field_name: &str) -> DbResult<String> {
+        match self.get_val(field_name)? {
+            Constant::Str(val) => Ok(val),
+            Constant::Int(_) => Err(DbError::TypeMismatch(field_name.to_string())),
+        }
+    }
+
+    fn get_val(&self, field_name: &str) -> DbResult<Constant> {
+        if let Some(pos) = self.group_fields.iter().position(|f| f == field_name) {
+            return Ok(self.group_val[pos].clone());
+        }
+        match self.accumulators.iter().find(|acc| acc.output_name == field_name) {
+            Some(acc) => acc.value(),
+            None => Err(DbError::FieldNotFound(field_name.to_string())),
+        }
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.group_fields.iter().any(|f| f == field_name)
+            || self.accumulators.iter().any(|acc| acc.output_name == field_name)
+    }
+
+    fn close(&mut self) {
+        self.s.close()
+    }
+}
@@ -0,0 +1,176 @@
+use super::constant::Constant;
+use super::scan::Scan;
+use crate::error::{DbError, DbResult};
+use crate::record_mgr::TempTable;
+
+use log::warn;
+use std::collections::HashMap;
+
+/*
+join of a probe scan with the records of a build scan kept in a hash
+table by their join value, every probe record looks up its matches.
+When the build side is too big for memory, both sides are split into
+partitions by the hash of the join value first, records of a partition
+only match records of the same partition of the other side, so the
+partitions are joined one after another. The scan owns the partitions,
+they are removed when it is closed
+*/
+pub struct HashJoinScan {
+    probe: Box<dyn Scan>,
+    probe_field: String,
+    build_field: String,
+    build_fields: Vec<String>,
+    table: HashMap<Constant, Vec<Vec<Constant>>>,
+    //probe and build side of every partition, empty when not partitioned
+    partitions: Vec<(TempTable, TempTable)>,
+    current_partition: usize,
+    //join value of the probe record and the number of its matches returned
+    matches: Option<(Constant, usize)>,
+}
+
+impl HashJoinScan {
+    pub fn new(
+        probe: Box<dyn Scan>,
+        mut build: Box<dyn Scan>,
+        probe_field: &str,
+        build_field: &str,
+        build_fields: &[String],
+    ) -> DbResult<Self> {
+        let table = Self::load(build.as_mut(), build_field, build_fields);
+        build.close();
+        let mut scan = HashJoinScan {
+            probe,
+            probe_field: probe_field.to_string(),
+            build_field: build_field.to_string(),
+            build_fields: build_fields.to_vec(),
+            table: table?,
+            partitions: Vec::new(),
+            current_partition: 0,
+            matches: None,
+        };
+        scan.before_first()?;
+        Ok(scan)
+    }
+
+    //there must be at least one partition
+    pub fn with_partitions(
+        partitions: Vec<(TempTable, TempTable)>,
+        probe_field: &str,
+        build_field: &str,
+        build_fields: &[String],
+    ) -> DbResult<Self> {
+        let (probe, build) = &partitions[0];
+        let mut build = build.open()?;
+        let table = Self::load(&mut build, build_field, build_fields);
+        build.close();
+        Ok(HashJoinScan {
+            probe: Box::new(probe.open()?),
+            probe_field: probe_field.to_string(),
+            build_field: build_field.to_string(),
+            build_fields: build_fields.to_vec(),
+            table: table?,
+            partitions,
+            current_partition: 0,
+            matches: None,
+        })
+    }
+
+    fn load(
+        build: &mut dyn Scan,
+        build_field: &str,
+        build_fields: &[String],
+    ) -> DbResult<HashMap<Constant, Vec<Vec<Constant>>>> {
+        let mut table: HashMap<Constant, Vec<Vec<Constant>>> = HashMap::new();
+        build.before_first()?;
+        while build.next()? {
+            let record = build_fields.iter().map(|f| build.get_val(f)).collect::<DbResult<_>>()?;
+            table.entry(build.get_val(build_field)?).or_default().push(record);
+        }
+        Ok(table)
+    }
+
+    fn load_partition(&mut self, i: usize) -> DbResult<()> {
+        self.probe.close();
+        let (probe, build) = &self.partitions[i];
+        let mut build = build.open()?;
+        let table = Self::load(&mut build, &self.build_field, &self.build_fields);
+        build.close();
+        self.table = table?;
+        self.probe = Box::new(probe.open()?);
+        self.current_partition = i;
+        self.matches = None;
+        Ok(())
+    }
+
+    fn build_record(&self) -> &[Constant] {
+        let (key, count) = self.matches.as_ref().expect("hash join scan is not on a record");
+        &self.table[key][count - 1]
+    }
+}
+
+impl Scan for HashJoinScan {
+    fn before_first(&mut self) -> DbResult<()> {
+        if self.current_partition != 0 {
+            return self.load_partition(0);
+        }
+        self.matches = None;
+        self.probe.before_first()
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        loop {
+            if let Some((key, count)) = self.matches.as_mut() {
+                if *count < self.table.get(key).map_or(0, |records| records.len()) {
+                    *count += 1;
+                    return Ok(true);
+                }
+            }
+            if self.probe.next()? {
+                self.matches = Some((self.probe.get_val(&self.probe_field)?, 0));
+                continue;
+            }
+            if self.current_partition + 1 >= self.partitions.len() {
+                self.matches = None;
+                return Ok(false);
+            }
+            self.load_partition(self.current_partition + 1)?;
+        }
+    }
+
+    fn get_int(&self, field_name: &str) -> DbResult<i32> {
+        self.get_val(field_name)?
+            .as_int()
+            .ok_or_else(|| DbError::TypeMismatch(field_name.to_string()))
+    }
+
+    fn get_string(&self, field_name: &str) -> DbResult<String> {
+        match self.get_val(field_name)? {
+            Constant::Str(val) => Ok(val),
+            Constant::Int(_) => Err(DbError::TypeMismatch(field_name.to_string())),
+        }
+    }
+
+    fn get_val(&self, field_name: &str) -> DbResult<Constant> {
+        match self.build_fields.iter().position(|f| f == field_name) {
+            Some(pos) => Ok(self.build_record()[pos].clone()),
+            None => self.probe.get_val(field_name),
+        }
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.build_fields.iter().any(|f| f == field_name) || self.probe.has_field(field_name)
+    }
+
+    fn close(&mut self) {
+        self.probe.close();
+        //close can't fail, a partition left behind is deleted at the next start
+        for (probe, build) in self.partitions.drain(..) {
+            for part in [probe, build] {
+                if let Err(err) = part.remove() {
+                    warn!("remove hash join partition: {}, with err: {}", part.file_name(), err);
+                }
+            }
+        }
+        self.current_partition = 0;
+    }
+}
@@ -0,0 +1,97 @@
+use super::constant::Constant;
+use super::scan::{Scan, UpdateScan};
+use crate::error::DbResult;
+use crate::index::Index;
+use crate::record_mgr::TableScan;
+
+/*
+join of the lhs scan with a table having an index on the join field, for
+each lhs record the index is searched with the value of the lhs join
+field and the table scan is moved to the matching records
+*/
+pub struct IndexJoinScan {
+    lhs: Box<dyn Scan>,
+    idx: Box<dyn Index>,
+    join_field: String,
+    rhs: TableScan,
+    //lhs is on a record, false once lhs runs out
+    lhs_on_record: bool,
+}
+
+impl IndexJoinScan {
+    pub fn new(lhs: Box<dyn Scan>, idx: Box<dyn Index>, join_field: &str, rhs: TableScan) -> DbResult<Self> {
+        let mut scan = IndexJoinScan {
+            lhs,
+            idx,
+            join_field: join_field.to_string(),
+            rhs,
+            lhs_on_record: false,
+        };
+        scan.before_first()?;
+        Ok(scan)
+    }
+
+    fn reset_index(&mut self) -> DbResult<()> {
+        let search_key = self.lhs.get_val(&self.join_field)?;
+        self.idx.before_first(&search_key)
+    }
+}
+
+impl Scan for IndexJoinScan {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.lhs.before_first()?;
+        self.lhs_on_record = self.lhs.next()?;
+        if self.lhs_on_record {
+            self.reset_index()?;
+        }
+        Ok(())
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        while self.lhs_on_record {
+            if self.idx.next()? {
+                self.rhs.move_to_rid(self.idx.get_data_rid()?)?;
+                return Ok(true);
+            }
+            self.lhs_on_record = self.lhs.next()?;
+            if self.lhs_on_record {
+                self.reset_index()?;
+            }
+        }
+        Ok(false)
+    }
+
+    fn get_int(&self, field_name: &str) -> DbResult<i32> {
+        if self.rhs.has_field(field_name) {
+            self.rhs.get_int(field_name)
+        } else {
+            self.lhs.get_int(field_name)
+        }
+    }
+
+    fn get_string(&self, field_name: &str) -> DbResult<String> {
+        if self.rhs.has_field(field_name) {
+            self.rhs.get_string(field_name)
+        } else {
+            self.lhs.get_string(field_name)
+        }
+    }
+
+    fn get_val(&self, field_name: &str) -> DbResult<Constant> {
+        if self.rhs.has_field(field_name) {
+            self.rhs.get_val(field_name)
+        } else {
+            self.lhs.get_val(field_name)
+        }
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.rhs.has_field(field_name) || self.lhs.has_field(field_name)
+    }
+
+    fn close(&mut self) {
+        self.lhs.close();
+        self.idx.close();
+        self.rhs.close();
+    }
+}
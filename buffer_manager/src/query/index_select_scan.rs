@@ -0,0 +1,59 @@
+use super::constant::Constant;
+use super::scan::{Scan, UpdateScan};
+use crate::error::DbResult;
+use crate::index::Index;
+use crate::record_mgr::TableScan;
+
+/*
+records of the table whose indexed field equals the value, the index
+gives the record ids and the table scan is moved to each of them, so
+only the blocks holding matching records are read
+*/
+pub struct IndexSelectScan {
+    ts: TableScan,
+    idx: Box<dyn Index>,
+    val: Constant,
+}
+
+impl IndexSelectScan {
+    pub fn new(ts: TableScan, idx: Box<dyn Index>, val: Constant) -> DbResult<Self> {
+        let mut scan = IndexSelectScan { ts, idx, val };
+        scan.before_first()?;
+        Ok(scan)
+    }
+}
+
+impl Scan for IndexSelectScan {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.idx.before_first(&self.val)
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        if !self.idx.next()? {
+            return Ok(false);
+        }
+        self.ts.move_to_rid(self.idx.get_data_rid()?)?;
+        Ok(true)
+    }
+
+    fn get_int(&self, field_name: &str) -> DbResult<i32> {
+        self.ts.get_int(field_name)
+    }
+
+    fn get_string(&self, field_name: &str) -> DbResult<String> {
+        self.ts.get_string(field_name)
+    }
+
+    fn get_val(&self, field_name: &str) -> DbResult<Constant> {
+        self.ts.get_val(field_name)
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.ts.has_field(field_name)
+    }
+
+    fn close(&mut self) {
+        self.idx.close();
+        self.ts.close();
+    }
+}
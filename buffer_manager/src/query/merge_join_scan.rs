@@ -0,0 +1,100 @@
+use super::constant::Constant;
+use super::scan::Scan;
+use super::sort_scan::SortScan;
+use crate::error::DbResult;
+
+/*
+join of two scans sorted by their join fields, they are read side by
+side like a merge. The records of s2 with the same join value are gone
+through again for every record of s1 having that value, so the position
+of the first of them is saved in s2
+*/
+pub struct MergeJoinScan {
+    s1: Box<dyn Scan>,
+    s2: SortScan,
+    field1: String,
+    field2: String,
+    //join value of the current records
+    join_val: Option<Constant>,
+}
+
+impl MergeJoinScan {
+    pub fn new(s1: Box<dyn Scan>, s2: SortScan, field1: &str, field2: &str) -> DbResult<Self> {
+        let mut scan = MergeJoinScan {
+            s1,
+            s2,
+            field1: field1.to_string(),
+            field2: field2.to_string(),
+            join_val: None,
+        };
+        scan.before_first()?;
+        Ok(scan)
+    }
+}
+
+impl Scan for MergeJoinScan {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.join_val = None;
+        self.s1.before_first()?;
+        self.s2.before_first()
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        let mut has_more2 = self.s2.next()?;
+        if has_more2 && Some(self.s2.get_val(&self.field2)?) == self.join_val {
+            return Ok(true);
+        }
+        let mut has_more1 = self.s1.next()?;
+        if has_more1 && Some(self.s1.get_val(&self.field1)?) == self.join_val {
+            self.s2.restore_position()?;
+            return Ok(true);
+        }
+        while has_more1 && has_more2 {
+            let val1 = self.s1.get_val(&self.field1)?;
+            let val2 = self.s2.get_val(&self.field2)?;
+            if val1 < val2 {
+                has_more1 = self.s1.next()?;
+            } else if val1 > val2 {
+                has_more2 = self.s2.next()?;
+            } else {
+                self.s2.save_position();
+                self.join_val = Some(val2);
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn get_int(&self, field_name: &str) -> DbResult<i32> {
+        if self.s1.has_field(field_name) {
+            self.s1.get_int(field_name)
+        } else {
+            self.s2.get_int(field_name)
+        }
+    }
+
+    fn get_string(&self, field_name: &str) -> DbResult<String> {
+        if self.s1.has_field(field_name) {
+            self.s1.get_string(field_name)
+        } else {
+            self.s2.get_string(field_name)
+        }
+    }
+
+    fn get_val(&self, field_name: &str) -> DbResult<Constant> {
+        if self.s1.has_field(field_name) {
+            self.s1.get_val(field_name)
+        } else {
+            self.s2.get_val(field_name)
+        }
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.s1.has_field(field_name) || self.s2.has_field(field_name)
+    }
+
+    fn close(&mut self) {
+        self.s1.close();
+        self.s2.close();
+    }
+}
@@ -0,0 +1,31 @@
+pub mod aggregation_fn;
+pub mod constant;
+pub mod group_by_scan;
+pub mod hash_join_scan;
+pub mod index_join_scan;
+pub mod index_select_scan;
+pub mod merge_join_scan;
+pub mod predicate;
+pub mod product_scan;
+pub mod project_scan;
+pub mod record_comparator;
+pub mod scan;
+pub mod select_scan;
+pub mod sort_scan;
+#[cfg(test)]
+pub mod test;
+
+pub use aggregation_fn::{AggregationFn, AggregationKind};
+pub use constant::Constant;
+pub use group_by_scan::GroupByScan;
+pub use hash_join_scan::HashJoinScan;
+pub use index_join_scan::IndexJoinScan;
+pub use index_select_scan::IndexSelectScan;
+pub use merge_join_scan::MergeJoinScan;
+pub use predicate::{Expression, Predicate, Term};
+pub use product_scan::ProductScan;
+pub use project_scan::ProjectScan;
+pub use record_comparator::RecordComparator;
+pub use scan::{Scan, UpdateScan};
+pub use select_scan::SelectScan;
+pub use sort_scan::SortScan;
@@ -0,0 +1,207 @@
+use super::constant::Constant;
+use super::scan::Scan;
+use crate::error::DbResult;
+use crate::plan::Plan;
+use crate::record_mgr::Schema;
+
+use std::fmt;
+
+//one side of a term, either a literal or the value of a field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+    Constant(Constant),
+    Field(String),
+}
+
+impl Expression {
+    pub fn as_constant(&self) -> Option<&Constant> {
+        match self {
+            Expression::Constant(val) => Some(val),
+            Expression::Field(_) => None,
+        }
+    }
+
+    pub fn as_field_name(&self) -> Option<&str> {
+        match self {
+            Expression::Constant(_) => None,
+            Expression::Field(field_name) => Some(field_name),
+        }
+    }
+
+    //value of the expression for the current record of the scan
+    pub fn evaluate(&self, s: &dyn Scan) -> DbResult<Constant> {
+        match self {
+            Expression::Constant(val) => Ok(val.clone()),
+            Expression::Field(field_name) => s.get_val(field_name),
+        }
+    }
+
+    //fields in the expression are all in the schema
+    pub fn applies_to(&self, schema: &Schema) -> bool {
+        match self {
+            Expression::Constant(_) => true,
+            Expression::Field(field_name) => schema.has_field(field_name),
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Constant(val) => write!(f, "{}", val),
+            Expression::Field(field_name) => write!(f, "{}", field_name),
+        }
+    }
+}
+
+//equality of two expressions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    lhs: Expression,
+    rhs: Expression,
+}
+
+impl Term {
+    pub fn new(lhs: Expression, rhs: Expression) -> Self {
+        Term { lhs, rhs }
+    }
+
+    pub fn lhs(&self) -> &Expression {
+        &self.lhs
+    }
+
+    pub fn rhs(&self) -> &Expression {
+        &self.rhs
+    }
+
+    pub fn is_satisfied(&self, s: &dyn Scan) -> DbResult<bool> {
+        Ok(self.lhs.evaluate(s)? == self.rhs.evaluate(s)?)
+    }
+
+    pub fn applies_to(&self, schema: &Schema) -> bool {
+        self.lhs.applies_to(schema) && self.rhs.applies_to(schema)
+    }
+
+    /*
+    how many times fewer records satisfy the term than the plan outputs,
+    values of a field are taken as evenly spread
+    */
+    pub fn reduction_factor(&self, p: &dyn Plan) -> usize {
+        match (&self.lhs, &self.rhs) {
+            (Expression::Field(l), Expression::Field(r)) => p.distinct_values(l).max(p.distinct_values(r)),
+            (Expression::Field(f), _) | (_, Expression::Field(f)) => p.distinct_values(f),
+            (Expression::Constant(l), Expression::Constant(r)) => {
+                if l == r {
+                    1
+                } else {
+                    usize::MAX
+                }
+            }
+        }
+    }
+
+    //the constant if the term is field = constant
+    pub fn equates_with_constant(&self, field_name: &str) -> Option<&Constant> {
+        match (&self.lhs, &self.rhs) {
+            (Expression::Field(f), Expression::Constant(val)) | (Expression::Constant(val), Expression::Field(f))
+                if f == field_name =>
+            {
+                Some(val)
+            }
+            _ => None,
+        }
+    }
+
+    //the other field if the term is field = another field
+    pub fn equates_with_field(&self, field_name: &str) -> Option<&str> {
+        match (&self.lhs, &self.rhs) {
+            (Expression::Field(l), Expression::Field(r)) if l == field_name => Some(r),
+            (Expression::Field(l), Expression::Field(r)) if r == field_name => Some(l),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.lhs, self.rhs)
+    }
+}
+
+//conjunction of terms, a predicate without terms is always true
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Predicate {
+    terms: Vec<Term>,
+}
+
+impl Predicate {
+    pub fn new() -> Self {
+        Predicate { terms: Vec::new() }
+    }
+
+    pub fn with_term(term: Term) -> Self {
+        Predicate { terms: vec![term] }
+    }
+
+    pub fn conjoin_with(&mut self, pred: Predicate) {
+        self.terms.extend(pred.terms);
+    }
+
+    pub fn terms(&self) -> &[Term] {
+        &self.terms
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn reduction_factor(&self, p: &dyn Plan) -> usize {
+        self.terms
+            .iter()
+            .fold(1, |factor: usize, term| factor.saturating_mul(term.reduction_factor(p)))
+    }
+
+    //terms that can be checked on records of the schema alone
+    pub fn select_sub_pred(&self, schema: &Schema) -> Predicate {
+        let terms = self.terms.iter().filter(|t| t.applies_to(schema)).cloned().collect();
+        Predicate { terms }
+    }
+
+    //terms that need fields from both schemas
+    pub fn join_sub_pred(&self, schema1: &Schema, schema2: &Schema) -> Predicate {
+        let mut schema = Schema::new();
+        schema.add_all(schema1);
+        schema.add_all(schema2);
+        let terms = self
+            .terms
+            .iter()
+            .filter(|t| !t.applies_to(schema1) && !t.applies_to(schema2) && t.applies_to(&schema))
+            .cloned()
+            .collect();
+        Predicate { terms }
+    }
+
+    pub fn equates_with_constant(&self, field_name: &str) -> Option<&Constant> {
+        self.terms.iter().find_map(|term| term.equates_with_constant(field_name))
+    }
+
+    pub fn equates_with_field(&self, field_name: &str) -> Option<&str> {
+        self.terms.iter().find_map(|term| term.equates_with_field(field_name))
+    }
+
+    pub fn is_satisfied(&self, s: &dyn Scan) -> DbResult<bool> {
+        for term in &self.terms {
+            if !term.is_satisfied(s)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let terms: Vec<String> = self.terms.iter().map(|t| t.to_string()).collect();
+        write!(f, "{}", terms.join(" and "))
+    }
+}
@@ -0,0 +1,79 @@
+use super::constant::Constant;
+use super::scan::Scan;
+use crate::error::DbResult;
+
+/*
+every combination of records from the two scans, the second scan is
+gone through once for each record of the first one
+*/
+pub struct ProductScan {
+    s1: Box<dyn Scan>,
+    s2: Box<dyn Scan>,
+    //s1 is on a record, false once s1 runs out
+    s1_on_record: bool,
+}
+
+impl ProductScan {
+    pub fn new(s1: Box<dyn Scan>, s2: Box<dyn Scan>) -> DbResult<Self> {
+        let mut scan = ProductScan {
+            s1,
+            s2,
+            s1_on_record: false,
+        };
+        scan.before_first()?;
+        Ok(scan)
+    }
+}
+
+impl Scan for ProductScan {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.s1.before_first()?;
+        self.s1_on_record = self.s1.next()?;
+        self.s2.before_first()
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        if !self.s1_on_record {
+            return Ok(false);
+        }
+        if self.s2.next()? {
+            return Ok(true);
+        }
+        self.s2.before_first()?;
+        self.s1_on_record = self.s1.next()?;
+        Ok(self.s1_on_record && self.s2.next()?)
+    }
+
+    fn get_int(&self, field_name: &str) -> DbResult<i32> {
+        if self.s1.has_field(field_name) {
+            self.s1.get_int(field_name)
+        } else {
+            self.s2.get_int(field_name)
+        }
+    }
+
+    fn get_string(&self, field_name: &str) -> DbResult<String> {
+        if self.s1.has_field(field_name) {
+            self.s1.get_string(field_name)
+        } else {
+            self.s2.get_string(field_name)
+        }
+    }
+
+    fn get_val(&self, field_name: &str) -> DbResult<Constant> {
+        if self.s1.has_field(field_name) {
+            self.s1.get_val(field_name)
+        } else {
+            self.s2.get_val(field_name)
+        }
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.s1.has_field(field_name) || self.s2.has_field(field_name)
+    }
+
+    fn close(&mut self) {
+        self.s1.close();
+        self.s2.close();
+    }
+}
@@ -0,0 +1,56 @@
+use super::constant::Constant;
+use super::scan::Scan;
+use crate::error::{DbError, DbResult};
+
+//records of the underlying scan with only the given fields visible
+pub struct ProjectScan {
+    s: Box<dyn Scan>,
+    fields: Vec<String>,
+}
+
+impl ProjectScan {
+    pub fn new(s: Box<dyn Scan>, fields: Vec<String>) -> Self {
+        ProjectScan { s, fields }
+    }
+
+    fn check_field(&self, field_name: &str) -> DbResult<()> {
+        if self.has_field(field_name) {
+            Ok(())
+        } else {
+            Err(DbError::FieldNotFound(field_name.to_string()))
+        }
+    }
+}
+
+impl Scan for ProjectScan {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.s.before_first()
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        self.s.next()
+    }
+
+    fn get_int(&self, field_name: &str) -> DbResult<i32> {
+        self.check_field(field_name)?;
+        self.s.get_int(field_name)
+    }
+
+    fn get_string(&self, field_name: &str) -> DbResult<String> {
+        self.check_field(field_name)?;
+        self.s.get_string(field_name)
+    }
+
+    fn get_val(&self, field_name: &str) -> DbResult<Constant> {
+        self.check_field(field_name)?;
+        self.s.get_val(field_name)
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.fields.iter().any(|f| f == field_name)
+    }
+
+    fn close(&mut self) {
+        self.s.close()
+    }
+}
@@ -0,0 +1,33 @@
+use super::scan::Scan;
+use crate::error::DbResult;
+
+use std::cmp::Ordering;
+
+//orders records by the sort fields, the first field that differs decides
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordComparator {
+    fields: Vec<String>,
+}
+
+impl RecordComparator {
+    pub fn new(fields: &[String]) -> Self {
+        RecordComparator {
+            fields: fields.to_vec(),
+        }
+    }
+
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    //compare the current records of the two scans
+    pub fn compare(&self, s1: &dyn Scan, s2: &dyn Scan) -> DbResult<Ordering> {
+        for field_name in &self.fields {
+            let ord = s1.get_val(field_name)?.cmp(&s2.get_val(field_name)?);
+            if ord != Ordering::Equal {
+                return Ok(ord);
+            }
+        }
+        Ok(Ordering::Equal)
+    }
+}
@@ -0,0 +1,90 @@
+use super::constant::Constant;
+use crate::error::DbResult;
+use crate::record_mgr::RecordId;
+
+/*
+cursor over the records produced by a relational operator, scans are
+put together into a tree whose leaves are table scans. next moves to
+the following record and returns false when there is no more, values
+of the current record are read by the getters
+*/
+pub trait Scan {
+    fn before_first(&mut self) -> DbResult<()>;
+    fn next(&mut self) -> DbResult<bool>;
+    fn get_int(&self, field_name: &str) -> DbResult<i32>;
+    fn get_string(&self, field_name: &str) -> DbResult<String>;
+    fn get_val(&self, field_name: &str) -> DbResult<Constant>;
+    fn has_field(&self, field_name: &str) -> bool;
+    fn close(&mut self);
+}
+
+//scan whose records map to records of one table, so they can be modified
+pub trait UpdateScan: Scan {
+    fn set_int(&mut self, field_name: &str, val: i32) -> DbResult<()>;
+    fn set_string(&mut self, field_name: &str, val: &str) -> DbResult<()>;
+    fn set_val(&mut self, field_name: &str, val: &Constant) -> DbResult<()>;
+    fn insert(&mut self) -> DbResult<()>;
+    fn delete(&mut self) -> DbResult<()>;
+    fn get_rid(&self) -> RecordId;
+    fn move_to_rid(&mut self, rid: RecordId) -> DbResult<()>;
+}
+
+impl<S: Scan + ?Sized> Scan for Box<S> {
+    fn before_first(&mut self) -> DbResult<()> {
+        (**self).before_first()
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        (**self).next()
+    }
+
+    fn get_int(&self, field_name: &str) -> DbResult<i32> {
+        (**self).get_int(field_name)
+    }
+
+    fn get_string(&self, field_name: &str) -> DbResult<String> {
+        (**self).get_string(field_name)
+    }
+
+    fn get_val(&self, field_name: &str) -> DbResult<Constant> {
+        (**self).get_val(field_name)
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        (**self).has_field(field_name)
+    }
+
+    fn close(&mut self) {
+        (**self).close()
+    }
+}
+
+impl<S: UpdateScan + ?Sized> UpdateScan for Box<S> {
+    fn set_int(&mut self, field_name: &str, val: i32) -> DbResult<()> {
+        (**self).set_int(field_name, val)
+    }
+
+    fn set_string(&mut self, field_name: &str, val: &str) -> DbResult<()> {
+        (**self).set_string(field_name, val)
+    }
+
+    fn set_val(&mut self, field_name: &str, val: &Constant) -> DbResult<()> {
+        (**self).set_val(field_name, val)
+    }
+
+    fn insert(&mut self) -> DbResult<()> {
+        (**self).insert()
+    }
+
+    fn delete(&mut self) -> DbResult<()> {
+        (**self).delete()
+    }
+
+    fn get_rid(&self) -> RecordId {
+        (**self).get_rid()
+    }
+
+    fn move_to_rid(&mut self, rid: RecordId) -> DbResult<()> {
+        (**self).move_to_rid(rid)
+    }
+}
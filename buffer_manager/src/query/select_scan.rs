@@ -0,0 +1,86 @@
+use super::constant::Constant;
+use super::predicate::Predicate;
+use super::scan::{Scan, UpdateScan};
+use crate::error::DbResult;
+use crate::record_mgr::RecordId;
+
+/*
+records of the underlying scan satisfying the predicate. When the
+underlying scan is updatable so is the select, modifications go to the
+current record of the underlying scan
+*/
+pub struct SelectScan<S: Scan> {
+    s: S,
+    pred: Predicate,
+}
+
+impl<S: Scan> SelectScan<S> {
+    pub fn new(s: S, pred: Predicate) -> Self {
+        SelectScan { s, pred }
+    }
+}
+
+impl<S: Scan> Scan for SelectScan<S> {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.s.before_first()
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        while self.s.next()? {
+            if self.pred.is_satisfied(&self.s)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn get_int(&self, field_name: &str) -> DbResult<i32> {
+        self.s.get_int(field_name)
+    }
+
+    fn get_string(&self, field_name: &str) -> DbResult<String> {
+        self.s.get_string(field_name)
+    }
+
+    fn get_val(&self, field_name: &str) -> DbResult<Constant> {
+        self.s.get_val(field_name)
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.s.has_field(field_name)
+    }
+
+    fn close(&mut self) {
+        self.s.close()
+    }
+}
+
+impl<S: UpdateScan> UpdateScan for SelectScan<S> {
+    fn set_int(&mut self, field_name: &str, val: i32) -> DbResult<()> {
+        self.s.set_int(field_name, val)
+    }
+
+    fn set_string(&mut self, field_name: &str, val: &str) -> DbResult<()> {
+        self.s.set_string(field_name, val)
+    }
+
+    fn set_val(&mut self, field_name: &str, val: &Constant) -> DbResult<()> {
+        self.s.set_val(field_name, val)
+    }
+
+    fn insert(&mut self) -> DbResult<()> {
+        self.s.insert()
+    }
+
+    fn delete(&mut self) -> DbResult<()> {
+        self.s.delete()
+    }
+
+    fn get_rid(&self) -> RecordId {
+        self.s.get_rid()
+    }
+
+    fn move_to_rid(&mut self, rid: RecordId) -> DbResult<()> {
+        self.s.move_to_rid(rid)
+    }
+}
@@ -0,0 +1,140 @@
+use super::constant::Constant;
+use super::record_comparator::RecordComparator;
+use super::scan::{Scan, UpdateScan};
+use crate::error::DbResult;
+use crate::record_mgr::{RecordId, Schema, TableScan, TempTable};
+
+use log::warn;
+
+/*
+merge of sorted runs, each run is a temporary table sorted by the
+comparator and keeps one block pinned while it is read. next moves the
+run of the current record forward and takes the run with the smallest
+record, so the records come out sorted. The scan owns the runs, they are
+removed when it is closed
+*/
+pub struct SortScan {
+    runs: Vec<TempTable>,
+    scans: Vec<TableScan>,
+    //the scan is on a record, false once it runs out
+    has_more: Vec<bool>,
+    current: Option<usize>,
+    comp: RecordComparator,
+    schema: Schema,
+    saved_position: Option<SavedPosition>,
+}
+
+//record of every run and which run has the current record
+struct SavedPosition {
+    rids: Vec<Option<RecordId>>,
+    current: Option<usize>,
+}
+
+impl SortScan {
+    pub fn new(runs: Vec<TempTable>, schema: &Schema, comp: RecordComparator) -> DbResult<Self> {
+        let scans = runs.iter().map(|run| run.open()).collect::<DbResult<Vec<_>>>()?;
+        let mut scan = SortScan {
+            runs,
+            has_more: vec![false; scans.len()],
+            scans,
+            current: None,
+            comp,
+            schema: schema.clone(),
+            saved_position: None,
+        };
+        scan.before_first()?;
+        Ok(scan)
+    }
+
+    //remember the position so that the scan can come back to the current record
+    pub fn save_position(&mut self) {
+        let rids = self
+            .scans
+            .iter()
+            .zip(&self.has_more)
+            .map(|(scan, has_more)| has_more.then(|| scan.get_rid()))
+            .collect();
+        self.saved_position = Some(SavedPosition {
+            rids,
+            current: self.current,
+        });
+    }
+
+    //back to the record of the last save_position
+    pub fn restore_position(&mut self) -> DbResult<()> {
+        let saved = self.saved_position.as_ref().expect("sort scan position is not saved");
+        for (i, rid) in saved.rids.iter().enumerate() {
+            if let Some(rid) = rid {
+                self.scans[i].move_to_rid(*rid)?;
+            }
+            self.has_more[i] = rid.is_some();
+        }
+        self.current = saved.current;
+        Ok(())
+    }
+
+    fn current_scan(&self) -> &TableScan {
+        &self.scans[self.current.expect("sort scan is not on a record")]
+    }
+}
+
+impl Scan for SortScan {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.current = None;
+        for (scan, has_more) in self.scans.iter_mut().zip(self.has_more.iter_mut()) {
+            scan.before_first()?;
+            *has_more = scan.next()?;
+        }
+        Ok(())
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        if let Some(i) = self.current {
+            self.has_more[i] = self.scans[i].next()?;
+        }
+        //the earlier run wins a tie, so records equal by the comparator keep their order
+        let mut smallest: Option<usize> = None;
+        for i in 0..self.scans.len() {
+            if !self.has_more[i] {
+                continue;
+            }
+            let is_smaller = match smallest {
+                Some(j) => self.comp.compare(&self.scans[i], &self.scans[j])?.is_lt(),
+                None => true,
+            };
+            if is_smaller {
+                smallest = Some(i);
+            }
+        }
+        self.current = smallest;
+        Ok(smallest.is_some())
+    }
+
+    fn get_int(&self, field_name: &str) -> DbResult<i32> {
+        self.current_scan().get_int(field_name)
+    }
+
+    fn get_string(&self, field_name: &str) -> DbResult<String> {
+        self.current_scan().get_string(field_name)
+    }
+
+    fn get_val(&self, field_name: &str) -> DbResult<Constant> {
+        self.current_scan().get_val(field_name)
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.schema.has_field(field_name)
+    }
+
+    fn close(&mut self) {
+        for scan in &mut self.scans {
+            scan.close();
+        }
+        //close can't fail, a run left behind is deleted at the next start
+        for run in self.runs.drain(..) {
+            if let Err(err) = run.remove() {
+                warn!("remove sorted run: {}, with err: {}", run.file_name(), err);
+            }
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use super::*;
+use crate::error::DbError;
+use crate::index::IndexType;
+use crate::metadata_mgr::{IndexInfo, StatInfo};
+use crate::record_mgr::{Layout, Schema, TableScan};
+use crate::server::RustDB;
+use crate::tx_mgr::Transaction;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+fn remove_dir(directory: &str) {
+    if Path::new(directory).exists() {
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
+
+fn student_layout() -> Arc<Layout> {
+    let mut schema = Schema::new();
+    schema.add_int_field("sid");
+    schema.add_string_field("sname", 10);
+    schema.add_int_field("majorid");
+    Arc::new(Layout::new(schema))
+}
+
+fn dept_layout() -> Arc<Layout> {
+    let mut schema = Schema::new();
+    schema.add_int_field("did");
+    schema.add_string_field("dname", 10);
+    Arc::new(Layout::new(schema))
+}
+
+//50 students in majors 10, 20, 30, and 3 departments
+fn create_tables(tx: &Arc<Mutex<Transaction>>) {
+    let mut ts = TableScan::new(tx.clone(), "student", student_layout()).unwrap();
+    for i in 0..50 {
+        ts.insert().unwrap();
+        ts.set_int("sid", i).unwrap();
+        ts.set_string("sname", &format!("s{}", i)).unwrap();
+        ts.set_int("majorid", (i % 3 + 1) * 10).unwrap();
+    }
+    let mut ts = TableScan::new(tx.clone(), "dept", dept_layout()).unwrap();
+    for (did, dname) in [(10, "compsci"), (20, "math"), (30, "drama")] {
+        ts.insert().unwrap();
+        ts.set_int("did", did).unwrap();
+        ts.set_string("dname", dname).unwrap();
+    }
+}
+
+fn field(name: &str) -> Expression {
+    Expression::Field(name.to_string())
+}
+
+fn constant(val: impl Into<Constant>) -> Expression {
+    Expression::Constant(val.into())
+}
+
+fn count(s: &mut dyn Scan) -> usize {
+    s.before_first().unwrap();
+    let mut n = 0;
+    while s.next().unwrap() {
+        n += 1;
+    }
+    n
+}
+
+#[test]
+fn test_select_scan() {
+    let directory = "selectscantest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    create_tables(&tx);
+
+    let ts = TableScan::new(tx.clone(), "student", student_layout()).unwrap();
+    let pred = Predicate::with_term(Term::new(field("majorid"), constant(20)));
+    let mut s = SelectScan::new(ts, pred);
+    let mut sids = Vec::new();
+    while s.next().unwrap() {
+        assert_eq!(s.get_val("majorid").unwrap(), Constant::Int(20));
+        sids.push(s.get_int("sid").unwrap());
+    }
+    assert_eq!(sids, (0..50).filter(|i| i % 3 == 1).collect::<Vec<_>>());
+
+    //conjunction with a string constant
+    let ts = TableScan::new(tx.clone(), "student", student_layout()).unwrap();
+    let mut pred = Predicate::with_term(Term::new(field("majorid"), constant(20)));
+    pred.conjoin_with(Predicate::with_term(Term::new(constant("s4"), field("sname"))));
+    let mut s = SelectScan::new(ts, pred);
+    assert!(s.next().unwrap());
+    assert_eq!(s.get_int("sid").unwrap(), 4);
+    assert!(!s.next().unwrap());
+
+    //unknown field in the predicate
+    let ts = TableScan::new(tx.clone(), "student", student_layout()).unwrap();
+    let mut s = SelectScan::new(ts, Predicate::with_term(Term::new(field("gradyear"), constant(2020))));
+    assert!(matches!(s.next(), Err(DbError::FieldNotFound(_))));
+    s.close();
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_update_through_select_scan() {
+    let directory = "updatescantest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    create_tables(&tx);
+
+    //move major 10 to 40, then delete major 30
+    let ts = TableScan::new(tx.clone(), "student", student_layout()).unwrap();
+    let mut s = SelectScan::new(ts, Predicate::with_term(Term::new(field("majorid"), constant(10))));
+    while s.next().unwrap() {
+        s.set_val("majorid", &Constant::Int(40)).unwrap();
+    }
+    assert!(matches!(
+        s.set_val("majorid", &Constant::from("forty")),
+        Err(DbError::TypeMismatch(_))
+    ));
+    let ts = TableScan::new(tx.clone(), "student", student_layout()).unwrap();
+    let mut s: Box<dyn UpdateScan> = Box::new(SelectScan::new(
+        ts,
+        Predicate::with_term(Term::new(field("majorid"), constant(30))),
+    ));
+    while s.next().unwrap() {
+        s.delete().unwrap();
+    }
+    tx.lock().unwrap().commit().unwrap();
+
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let mut ts = TableScan::new(tx.clone(), "student", student_layout()).unwrap();
+    let mut majors = Vec::new();
+    while ts.next().unwrap() {
+        majors.push(ts.get_int("majorid").unwrap());
+    }
+    assert_eq!(majors.len(), 34);
+    assert_eq!(majors.iter().filter(|m| **m == 40).count(), 17);
+    assert_eq!(majors.iter().filter(|m| **m == 20).count(), 17);
+    ts.close();
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_project_and_product_scan() {
+    let directory = "productscantest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    create_tables(&tx);
+
+    let student = Box::new(TableScan::new(tx.clone(), "student", student_layout()).unwrap());
+    let dept = Box::new(TableScan::new(tx.clone(), "dept", dept_layout()).unwrap());
+    let mut product = ProductScan::new(student, dept).unwrap();
+    assert_eq!(count(&mut product), 150);
+    assert!(product.has_field("sname") && product.has_field("dname"));
+
+    //join students with their departments and keep names only
+    let pred = Predicate::with_term(Term::new(field("majorid"), field("did")));
+    let select = Box::new(SelectScan::new(product, pred));
+    let mut project = ProjectScan::new(select, vec!["sname".to_string(), "dname".to_string()]);
+    let mut rows = Vec::new();
+    project.before_first().unwrap();
+    while project.next().unwrap() {
+        rows.push((project.get_string("sname").unwrap(), project.get_string("dname").unwrap()));
+    }
+    assert_eq!(rows.len(), 50);
+    assert!(rows.contains(&("s4".to_string(), "math".to_string())));
+    assert!(rows.contains(&("s5".to_string(), "drama".to_string())));
+    assert!(!project.has_field("majorid"));
+    assert!(matches!(project.get_int("majorid"), Err(DbError::FieldNotFound(_))));
+
+    //nothing comes out when one side is empty
+    let empty = Box::new(TableScan::new(tx.clone(), "empty", dept_layout()).unwrap());
+    let dept = Box::new(TableScan::new(tx.clone(), "dept", dept_layout()).unwrap());
+    assert_eq!(count(&mut ProductScan::new(empty, dept).unwrap()), 0);
+    let empty = Box::new(TableScan::new(tx.clone(), "empty", dept_layout()).unwrap());
+    let dept = Box::new(TableScan::new(tx.clone(), "dept", dept_layout()).unwrap());
+    assert_eq!(count(&mut ProductScan::new(dept, empty).unwrap()), 0);
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
+
+//index on the field with a record for every record of the table
+fn build_index(tx: &Arc<Mutex<Transaction>>, tbl_name: &str, layout: Arc<Layout>, ii: &IndexInfo) {
+    let mut idx = ii.open().unwrap();
+    let mut ts = TableScan::new(tx.clone(), tbl_name, layout).unwrap();
+    while ts.next().unwrap() {
+        idx.insert(&ts.get_val(ii.field_name()).unwrap(), ts.get_rid()).unwrap();
+    }
+    idx.close();
+}
+
+#[test]
+fn test_index_select_and_join_scan() {
+    let directory = "indexscantest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    create_tables(&tx);
+    let student_schema = student_layout().schema().clone();
+    let major_ii = IndexInfo::new(tx.clone(), "majoridx", "majorid", student_schema, StatInfo::default());
+    build_index(&tx, "student", student_layout(), &major_ii);
+    let dept_schema = dept_layout().schema().clone();
+    let did_ii = IndexInfo::new(tx.clone(), "dididx", "did", dept_schema, StatInfo::default())
+        .with_index_type(IndexType::BTree);
+    build_index(&tx, "dept", dept_layout(), &did_ii);
+
+    let ts = TableScan::new(tx.clone(), "student", student_layout()).unwrap();
+    let mut s = IndexSelectScan::new(ts, major_ii.open().unwrap(), Constant::Int(20)).unwrap();
+    let mut sids = Vec::new();
+    while s.next().unwrap() {
+        assert_eq!(s.get_int("majorid").unwrap(), 20);
+        sids.push(s.get_int("sid").unwrap());
+    }
+    sids.sort();
+    assert_eq!(sids, (0..50).filter(|i| i % 3 == 1).collect::<Vec<_>>());
+    //scanned again from the beginning
+    assert_eq!(count(&mut s), 17);
+    s.close();
+
+    //every student finds its department through the index on did
+    let lhs = Box::new(TableScan::new(tx.clone(), "student", student_layout()).unwrap());
+    let rhs = TableScan::new(tx.clone(), "dept", dept_layout()).unwrap();
+    let mut s = IndexJoinScan::new(lhs, did_ii.open().unwrap(), "majorid", rhs).unwrap();
+    let mut n = 0;
+    while s.next().unwrap() {
+        assert_eq!(s.get_int("majorid").unwrap(), s.get_int("did").unwrap());
+        let expected = ["compsci", "math", "drama"][(s.get_int("sid").unwrap() % 3) as usize];
+        assert_eq!(s.get_string("dname").unwrap(), expected);
+        n += 1;
+    }
+    assert_eq!(n, 50);
+    assert!(s.has_field("sname") && s.has_field("dname"));
+    s.close();
+
+    //the inner side has no match for the outer records
+    let lhs = Box::new(TableScan::new(tx.clone(), "dept", dept_layout()).unwrap());
+    let rhs = TableScan::new(tx.clone(), "dept", dept_layout()).unwrap();
+    let pred = Predicate::with_term(Term::new(field("did"), constant(99)));
+    let lhs = Box::new(SelectScan::new(lhs as Box<dyn Scan>, pred));
+    let mut s = IndexJoinScan::new(lhs, did_ii.open().unwrap(), "did", rhs).unwrap();
+    assert!(!s.next().unwrap());
+    s.close();
+    tx.lock().unwrap().commit().unwrap();
+
+    remove_dir(directory);
+}
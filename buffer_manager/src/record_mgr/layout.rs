@@ -0,0 +1,66 @@
+use super::schema::{FieldType, Schema};
+use crate::file_mgr::Page;
+
+use std::collections::HashMap;
+
+//the first 4 bytes of a slot tell whether the slot is empty or used
+pub const FLAG_SIZE: usize = 4;
+
+/*
+physical layout of a record, every record takes a slot of the same size,
+the slot begins with the empty/used flag followed by the fields in the
+order of the schema, a varchar field takes the room of its longest value
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    schema: Schema,
+    offsets: HashMap<String, usize>,
+    slot_size: usize,
+}
+
+impl Layout {
+    pub fn new(schema: Schema) -> Self {
+        let mut offsets = HashMap::new();
+        let mut pos = FLAG_SIZE;
+        for field_name in schema.fields() {
+            offsets.insert(field_name.clone(), pos);
+            pos += Self::length_in_bytes(&schema, field_name);
+        }
+        Layout {
+            schema,
+            offsets,
+            slot_size: pos,
+        }
+    }
+
+    //layout saved in the catalog, offsets are not computed again
+    pub fn from_metadata(schema: Schema, offsets: HashMap<String, usize>, slot_size: usize) -> Self {
+        Layout {
+            schema,
+            offsets,
+            slot_size,
+        }
+    }
+
+    fn length_in_bytes(schema: &Schema, field_name: &str) -> usize {
+        match schema.field_type(field_name) {
+            Some(FieldType::Integer) => 4,
+            Some(FieldType::Varchar) => {
+                Page::max_length(schema.length(field_name).unwrap_or(0) as u64) as usize
+            }
+            None => 0,
+        }
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    pub fn offset(&self, field_name: &str) -> Option<usize> {
+        self.offsets.get(field_name).copied()
+    }
+
+    pub fn slot_size(&self) -> usize {
+        self.slot_size
+    }
+}
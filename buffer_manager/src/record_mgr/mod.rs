@@ -0,0 +1,15 @@
+pub mod layout;
+pub mod record_page;
+pub mod schema;
+pub mod slotted_page;
+pub mod table_scan;
+pub mod temp_table;
+#[cfg(test)]
+pub mod test;
+
+pub use layout::Layout;
+pub use record_page::RecordPage;
+pub use schema::{FieldType, Schema};
+pub use slotted_page::SlottedPage;
+pub use table_scan::{RecordId, TableScan};
+pub use temp_table::TempTable;
@@ -0,0 +1,141 @@
+use super::layout::Layout;
+use super::schema::FieldType;
+use crate::error::{DbError, DbResult};
+use crate::file_mgr::BlockId;
+use crate::tx_mgr::Transaction;
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+const EMPTY: i32 = 0;
+const USED: i32 = 1;
+
+/*
+records of one block, the block is divided into slots of the size given
+by the layout, slot i begins at offset i * slot_size. Values are read and
+written through the transaction, therefore they are locked and logged.
+The block is pinned as long as the record page is alive
+*/
+pub struct RecordPage {
+    tx: Arc<Mutex<Transaction>>,
+    blk: BlockId,
+    layout: Arc<Layout>,
+}
+
+impl RecordPage {
+    pub fn new(tx: Arc<Mutex<Transaction>>, blk: BlockId, layout: Arc<Layout>) -> DbResult<Self> {
+        tx.lock().unwrap().pin(&blk)?;
+        Ok(RecordPage { tx, blk, layout })
+    }
+
+    pub fn block(&self) -> &BlockId {
+        &self.blk
+    }
+
+    pub fn get_int(&self, slot: usize, field_name: &str) -> DbResult<i32> {
+        let offset = self.field_offset(slot, field_name)?;
+        self.tx.lock().unwrap().get_int(&self.blk, offset)
+    }
+
+    pub fn get_string(&self, slot: usize, field_name: &str) -> DbResult<String> {
+        let offset = self.field_offset(slot, field_name)?;
+        self.tx.lock().unwrap().get_string(&self.blk, offset)
+    }
+
+    pub fn set_int(&self, slot: usize, field_name: &str, val: i32) -> DbResult<()> {
+        let offset = self.field_offset(slot, field_name)?;
+        self.tx.lock().unwrap().set_int(&self.blk, offset, val, true)
+    }
+
+    pub fn set_string(&self, slot: usize, field_name: &str, val: &str) -> DbResult<()> {
+        let offset = self.field_offset(slot, field_name)?;
+        let max_len = self.layout.schema().length(field_name).unwrap_or(0);
+        if val.len() > max_len {
+            return Err(DbError::FieldTooLong {
+                field_name: field_name.to_string(),
+                len: val.len(),
+                max_len,
+            });
+        }
+        self.tx.lock().unwrap().set_string(&self.blk, offset, val, true)
+    }
+
+    pub fn delete(&self, slot: usize) -> DbResult<()> {
+        self.set_flag(slot, EMPTY)
+    }
+
+    /*
+    mark every slot empty and give the fields initial values, the block
+    is new and nobody has seen it, the changes need not to be logged
+    */
+    pub fn format(&self) -> DbResult<()> {
+        let mut tx = self.tx.lock().unwrap();
+        let mut slot = 0;
+        while self.is_valid_slot(&tx, slot) {
+            let slot_offset = self.offset(slot);
+            tx.set_int(&self.blk, slot_offset, EMPTY, false)?;
+            let schema = self.layout.schema();
+            for field_name in schema.fields() {
+                let field_offset = slot_offset + self.layout.offset(field_name).unwrap_or(0);
+                match schema.field_type(field_name) {
+                    Some(FieldType::Integer) => tx.set_int(&self.blk, field_offset, 0, false)?,
+                    Some(FieldType::Varchar) => tx.set_string(&self.blk, field_offset, "", false)?,
+                    None => {}
+                }
+            }
+            slot += 1;
+        }
+        Ok(())
+    }
+
+    //the first used slot after the given one, None to begin from the first slot
+    pub fn next_after(&self, slot: Option<usize>) -> DbResult<Option<usize>> {
+        self.search_after(slot, USED)
+    }
+
+    //take the first empty slot after the given one and mark it used
+    pub fn insert_after(&self, slot: Option<usize>) -> DbResult<Option<usize>> {
+        let new_slot = self.search_after(slot, EMPTY)?;
+        if let Some(new_slot) = new_slot {
+            self.set_flag(new_slot, USED)?;
+        }
+        Ok(new_slot)
+    }
+
+    fn search_after(&self, slot: Option<usize>, flag: i32) -> DbResult<Option<usize>> {
+        let mut tx = self.tx.lock().unwrap();
+        let mut slot = slot.map_or(0, |slot| slot + 1);
+        while self.is_valid_slot(&tx, slot) {
+            if tx.get_int(&self.blk, self.offset(slot))? == flag {
+                return Ok(Some(slot));
+            }
+            slot += 1;
+        }
+        Ok(None)
+    }
+
+    fn set_flag(&self, slot: usize, flag: i32) -> DbResult<()> {
+        self.tx.lock().unwrap().set_int(&self.blk, self.offset(slot), flag, true)
+    }
+
+    fn is_valid_slot(&self, tx: &Transaction, slot: usize) -> bool {
+        self.offset(slot + 1) <= tx.block_size() as usize
+    }
+
+    fn offset(&self, slot: usize) -> usize {
+        slot * self.layout.slot_size()
+    }
+
+    fn field_offset(&self, slot: usize, field_name: &str) -> DbResult<usize> {
+        let field_offset = self
+            .layout
+            .offset(field_name)
+            .ok_or_else(|| DbError::FieldNotFound(field_name.to_string()))?;
+        Ok(self.offset(slot) + field_offset)
+    }
+}
+
+impl Drop for RecordPage {
+    fn drop(&mut self) {
+        self.tx.lock().unwrap_or_else(PoisonError::into_inner).unpin(&self.blk);
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Integer,
+    Varchar,
+}
+
+impl FieldType {
+    //code saved in the catalog for the type
+    pub fn code(self) -> i32 {
+        match self {
+            FieldType::Integer => 0,
+            FieldType::Varchar => 1,
+        }
+    }
+
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(FieldType::Integer),
+            1 => Some(FieldType::Varchar),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInfo {
+    pub field_type: FieldType,
+    //number of characters for varchar, not used for integer
+    pub length: usize,
+}
+
+/*
+name and type of each field in a record, for varchar fields the schema
+also records the max number of characters, fields keep the order in
+which they are added
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schema {
+    fields: Vec<String>,
+    info: HashMap<String, FieldInfo>,
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Schema::default()
+    }
+
+    pub fn add_field(&mut self, field_name: &str, field_type: FieldType, length: usize) {
+        if !self.info.contains_key(field_name) {
+            self.fields.push(field_name.to_string());
+        }
+        self.info.insert(field_name.to_string(), FieldInfo { field_type, length });
+    }
+
+    pub fn add_int_field(&mut self, field_name: &str) {
+        self.add_field(field_name, FieldType::Integer, 0);
+    }
+
+    pub fn add_string_field(&mut self, field_name: &str, length: usize) {
+        self.add_field(field_name, FieldType::Varchar, length);
+    }
+
+    //copy the definition of the given field from another schema
+    pub fn add(&mut self, field_name: &str, schema: &Schema) {
+        if let Some(info) = schema.info.get(field_name) {
+            self.add_field(field_name, info.field_type, info.length);
+        }
+    }
+
+    pub fn add_all(&mut self, schema: &Schema) {
+        for field_name in schema.fields() {
+            self.add(field_name, schema);
+        }
+    }
+
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    pub fn has_field(&self, field_name: &str) -> bool {
+        self.info.contains_key(field_name)
+    }
+
+    pub fn field_type(&self, field_name: &str) -> Option<FieldType> {
+        self.info.get(field_name).map(|info| info.field_type)
+    }
+
+    pub fn length(&self, field_name: &str) -> Option<usize> {
+        self.info.get(field_name).map(|info| info.length)
+    }
+}
@@ -0,0 +1,316 @@
+use super::layout::Layout;
+use super::schema::FieldType;
+use crate::error::{DbError, DbResult};
+use crate::file_mgr::BlockId;
+use crate::tx_mgr::Transaction;
+
+use byteorder::{BigEndian, ByteOrder};
+use std::sync::{Arc, Mutex, PoisonError};
+
+//number of slots in the directory
+const SLOT_COUNT_POS: usize = 0;
+//beginning of the latest tuple, tuples grow from the end of the block
+const FREE_END_POS: usize = 4;
+const HEADER_SIZE: usize = 8;
+//each slot keeps offset and length of its tuple
+const SLOT_SIZE: usize = 8;
+//offset of a deleted slot, no tuple begins inside the header
+const TOMBSTONE: i32 = 0;
+
+/*
+records of variable length in one block, the same trick as the boundary
+of LogMgr::append:
+
+| slot count | free end | slot 0 | slot 1 | ... free ... | tuple 1 | tuple 0 |
+
+the slot directory grows from the head and tuples grow from the tail,
+a record keeps its slot number as long as it lives however its tuple
+moves around. In a tuple an integer takes 4 bytes and a varchar takes
+4 bytes for its length and then the bytes of the string.
+
+deleting a record leaves a tombstone in its slot, the tuple becomes a
+hole and a record growing out of its place leaves a hole as well, holes
+are squeezed out by compaction when the free room in the middle is not
+enough for a new or growing tuple
+*/
+pub struct SlottedPage {
+    tx: Arc<Mutex<Transaction>>,
+    blk: BlockId,
+    layout: Arc<Layout>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    offset: usize,
+    len: usize,
+}
+
+impl SlottedPage {
+    pub fn new(tx: Arc<Mutex<Transaction>>, blk: BlockId, layout: Arc<Layout>) -> DbResult<Self> {
+        tx.lock().unwrap().pin(&blk)?;
+        Ok(SlottedPage { tx, blk, layout })
+    }
+
+    pub fn block(&self) -> &BlockId {
+        &self.blk
+    }
+
+    //the block is new, an empty directory and all the room is free
+    pub fn format(&self) -> DbResult<()> {
+        let mut tx = self.tx.lock().unwrap();
+        let block_size = tx.block_size() as i32;
+        tx.set_int(&self.blk, SLOT_COUNT_POS, 0, false)?;
+        tx.set_int(&self.blk, FREE_END_POS, block_size, false)
+    }
+
+    pub fn get_int(&self, slot: usize, field_name: &str) -> DbResult<i32> {
+        let (offset, _) = self.field_pos(slot, field_name)?;
+        self.tx.lock().unwrap().get_int(&self.blk, offset)
+    }
+
+    pub fn get_string(&self, slot: usize, field_name: &str) -> DbResult<String> {
+        let (offset, _) = self.field_pos(slot, field_name)?;
+        self.tx.lock().unwrap().get_string(&self.blk, offset)
+    }
+
+    pub fn set_int(&self, slot: usize, field_name: &str, val: i32) -> DbResult<()> {
+        let (offset, _) = self.field_pos(slot, field_name)?;
+        self.tx.lock().unwrap().set_int(&self.blk, offset, val, true)
+    }
+
+    /*
+    a value of the same length is written in place, otherwise the tuple is
+    built again, a shorter tuple stays where it is and a longer one moves
+    to the free room
+    */
+    pub fn set_string(&self, slot: usize, field_name: &str, val: &str) -> DbResult<()> {
+        let max_len = self.layout.schema().length(field_name).unwrap_or(0);
+        if val.len() > max_len {
+            return Err(DbError::FieldTooLong {
+                field_name: field_name.to_string(),
+                len: val.len(),
+                max_len,
+            });
+        }
+        let (offset, field_len) = self.field_pos(slot, field_name)?;
+        if field_len == 4 + val.len() {
+            return self.tx.lock().unwrap().set_string(&self.blk, offset, val, true);
+        }
+
+        let tuple = self.read_tuple(slot)?;
+        let old_slot = self.slot(slot)?;
+        let field_begin = offset - old_slot.offset;
+        let mut new_tuple = tuple[..field_begin].to_vec();
+        new_tuple.extend_from_slice(&(val.len() as u32).to_be_bytes());
+        new_tuple.extend_from_slice(val.as_bytes());
+        new_tuple.extend_from_slice(&tuple[field_begin + field_len..]);
+        self.write_tuple(slot, &new_tuple)
+    }
+
+    //the tuple becomes a hole, the slot can be taken by a later insert
+    pub fn delete(&self, slot: usize) -> DbResult<()> {
+        self.slot(slot)?;
+        self.set_slot(slot, TOMBSTONE as usize, 0)
+    }
+
+    //the first used slot after the given one, None to begin from the first slot
+    pub fn next_after(&self, slot: Option<usize>) -> DbResult<Option<usize>> {
+        let slot_count = self.slot_count()?;
+        let mut slot = slot.map_or(0, |slot| slot + 1);
+        while slot < slot_count {
+            if self.slot(slot)?.offset != TOMBSTONE as usize {
+                return Ok(Some(slot));
+            }
+            slot += 1;
+        }
+        Ok(None)
+    }
+
+    /*
+    insert a record with integers of 0 and empty strings, a tombstone after
+    the given slot is taken first, otherwise a slot is added at the end of
+    the directory, None if the block has no room for the record
+    */
+    pub fn insert_after(&self, slot: Option<usize>) -> DbResult<Option<usize>> {
+        let slot_count = self.slot_count()?;
+        let mut new_slot = slot.map_or(0, |slot| slot + 1);
+        while new_slot < slot_count && self.slot(new_slot)?.offset != TOMBSTONE as usize {
+            new_slot += 1;
+        }
+
+        let tuple = self.empty_tuple();
+        let dir_growth = if new_slot < slot_count {
+            0
+        } else {
+            (new_slot - slot_count + 1) * SLOT_SIZE
+        };
+        if self.total_free()? < tuple.len() + dir_growth {
+            return Ok(None);
+        }
+        //the directory takes room from the free space in the middle
+        if self.contiguous_free()? < tuple.len() + dir_growth {
+            self.compact()?;
+        }
+        if dir_growth > 0 {
+            let mut tx = self.tx.lock().unwrap();
+            for i in slot_count..=new_slot {
+                let pos = HEADER_SIZE + i * SLOT_SIZE;
+                tx.set_int(&self.blk, pos, TOMBSTONE, true)?;
+                tx.set_int(&self.blk, pos + 4, 0, true)?;
+            }
+            tx.set_int(&self.blk, SLOT_COUNT_POS, (new_slot + 1) as i32, true)?;
+        }
+        let offset = self.allocate(tuple.len())?;
+        self.tx.lock().unwrap().set_bytes(&self.blk, offset, &tuple, true)?;
+        self.set_slot(new_slot, offset, tuple.len())?;
+        Ok(Some(new_slot))
+    }
+
+    /*
+    move all tuples to the end of the block one after another, the holes
+    left by deleted and moved tuples join the free room in the middle.
+    Slot numbers don't change, only the offsets in the directory
+    */
+    pub fn compact(&self) -> DbResult<()> {
+        let slot_count = self.slot_count()?;
+        let mut tuples = Vec::new();
+        for slot in 0..slot_count {
+            let s = self.slot(slot)?;
+            if s.offset != TOMBSTONE as usize {
+                tuples.push((slot, self.read_tuple(slot)?));
+            }
+        }
+
+        let mut tx = self.tx.lock().unwrap();
+        let mut free_end = tx.block_size() as usize;
+        let mut image = Vec::new();
+        for (slot, tuple) in tuples.iter() {
+            free_end -= tuple.len();
+            let pos = HEADER_SIZE + slot * SLOT_SIZE;
+            tx.set_int(&self.blk, pos, free_end as i32, true)?;
+            image.splice(0..0, tuple.iter().copied());
+        }
+        tx.set_bytes(&self.blk, free_end, &image, true)?;
+        tx.set_int(&self.blk, FREE_END_POS, free_end as i32, true)
+    }
+
+    //room between the directory and the tuples
+    pub fn contiguous_free(&self) -> DbResult<usize> {
+        let dir_end = HEADER_SIZE + self.slot_count()? * SLOT_SIZE;
+        Ok(self.free_end()?.saturating_sub(dir_end))
+    }
+
+    //room after compaction
+    pub fn total_free(&self) -> DbResult<usize> {
+        let slot_count = self.slot_count()?;
+        let mut used = HEADER_SIZE + slot_count * SLOT_SIZE;
+        for slot in 0..slot_count {
+            used += self.slot(slot)?.len;
+        }
+        let block_size = self.tx.lock().unwrap().block_size() as usize;
+        Ok(block_size.saturating_sub(used))
+    }
+
+    fn write_tuple(&self, slot: usize, tuple: &[u8]) -> DbResult<()> {
+        let old_slot = self.slot(slot)?;
+        if tuple.len() <= old_slot.len {
+            //the rest of the old tuple turns into a hole
+            self.tx.lock().unwrap().set_bytes(&self.blk, old_slot.offset, tuple, true)?;
+            return self.set_slot(slot, old_slot.offset, tuple.len());
+        }
+
+        //the old tuple is a hole once the record moves, count it as free
+        if self.total_free()? + old_slot.len < tuple.len() {
+            return Err(DbError::PageFull(self.blk.clone()));
+        }
+        self.set_slot(slot, TOMBSTONE as usize, 0)?;
+        if self.contiguous_free()? < tuple.len() {
+            self.compact()?;
+        }
+        let offset = self.allocate(tuple.len())?;
+        self.tx.lock().unwrap().set_bytes(&self.blk, offset, tuple, true)?;
+        self.set_slot(slot, offset, tuple.len())
+    }
+
+    //take len bytes at the end of the free room, the caller makes sure they fit
+    fn allocate(&self, len: usize) -> DbResult<usize> {
+        let offset = self.free_end()? - len;
+        self.tx.lock().unwrap().set_int(&self.blk, FREE_END_POS, offset as i32, true)?;
+        Ok(offset)
+    }
+
+    fn read_tuple(&self, slot: usize) -> DbResult<Vec<u8>> {
+        let s = self.slot(slot)?;
+        self.tx.lock().unwrap().get_bytes(&self.blk, s.offset, s.len)
+    }
+
+    fn empty_tuple(&self) -> Vec<u8> {
+        //both integer 0 and empty string take 4 bytes of 0
+        vec![0u8; 4 * self.layout.schema().fields().len()]
+    }
+
+    /*
+    offset of the field in the block and the bytes it takes, fields before
+    it are walked through since a varchar takes as many bytes as it has
+    */
+    fn field_pos(&self, slot: usize, field_name: &str) -> DbResult<(usize, usize)> {
+        let schema = self.layout.schema();
+        if !schema.has_field(field_name) {
+            return Err(DbError::FieldNotFound(field_name.to_string()));
+        }
+        let s = self.slot(slot)?;
+        if s.offset == TOMBSTONE as usize {
+            return Err(DbError::SlotNotFound { blk: self.blk.clone(), slot });
+        }
+        let tuple = self.tx.lock().unwrap().get_bytes(&self.blk, s.offset, s.len)?;
+        let mut pos = 0;
+        for name in schema.fields() {
+            let len = match schema.field_type(name) {
+                Some(FieldType::Varchar) => {
+                    if pos + 4 > tuple.len() {
+                        return Err(DbError::PageOverflow { offset: pos, size: 4, capacity: tuple.len() });
+                    }
+                    4 + BigEndian::read_u32(&tuple[pos..pos + 4]) as usize
+                }
+                _ => 4,
+            };
+            if name == field_name {
+                return Ok((s.offset + pos, len));
+            }
+            pos += len;
+        }
+        unreachable!("field {} is in the schema", field_name)
+    }
+
+    fn slot(&self, slot: usize) -> DbResult<Slot> {
+        if slot >= self.slot_count()? {
+            return Err(DbError::SlotNotFound { blk: self.blk.clone(), slot });
+        }
+        let pos = HEADER_SIZE + slot * SLOT_SIZE;
+        let mut tx = self.tx.lock().unwrap();
+        let offset = tx.get_int(&self.blk, pos)? as usize;
+        let len = tx.get_int(&self.blk, pos + 4)? as usize;
+        Ok(Slot { offset, len })
+    }
+
+    fn set_slot(&self, slot: usize, offset: usize, len: usize) -> DbResult<()> {
+        let pos = HEADER_SIZE + slot * SLOT_SIZE;
+        let mut tx = self.tx.lock().unwrap();
+        tx.set_int(&self.blk, pos, offset as i32, true)?;
+        tx.set_int(&self.blk, pos + 4, len as i32, true)
+    }
+
+    fn slot_count(&self) -> DbResult<usize> {
+        Ok(self.tx.lock().unwrap().get_int(&self.blk, SLOT_COUNT_POS)? as usize)
+    }
+
+    fn free_end(&self) -> DbResult<usize> {
+        Ok(self.tx.lock().unwrap().get_int(&self.blk, FREE_END_POS)? as usize)
+    }
+}
+
+impl Drop for SlottedPage {
+    fn drop(&mut self) {
+        self.tx.lock().unwrap_or_else(PoisonError::into_inner).unpin(&self.blk);
+    }
+}
@@ -0,0 +1,207 @@
+use super::layout::Layout;
+use super::record_page::RecordPage;
+use super::schema::FieldType;
+use crate::error::{DbError, DbResult};
+use crate::file_mgr::BlockId;
+use crate::query::{Constant, Scan, UpdateScan};
+use crate::tx_mgr::Transaction;
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+//position of a record in the table file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RecordId {
+    blk_num: u64,
+    slot: usize,
+}
+
+impl RecordId {
+    pub fn new(blk_num: u64, slot: usize) -> Self {
+        RecordId { blk_num, slot }
+    }
+
+    pub fn block_number(&self) -> u64 {
+        self.blk_num
+    }
+
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
+}
+
+impl fmt::Display for RecordId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {}]", self.blk_num, self.slot)
+    }
+}
+
+/*
+go through records of a table saved in file <table name>.tbl, one block
+is pinned at a time, moving to the next block unpins the current one.
+Inserting at the end of the file appends a new block
+*/
+pub struct TableScan {
+    tx: Arc<Mutex<Transaction>>,
+    layout: Arc<Layout>,
+    file_name: String,
+    rp: Option<RecordPage>,
+    //None is the position before the first record of the block
+    current_slot: Option<usize>,
+}
+
+impl TableScan {
+    pub fn new(tx: Arc<Mutex<Transaction>>, table_name: &str, layout: Arc<Layout>) -> DbResult<Self> {
+        Self::with_file_name(tx, &format!("{}.tbl", table_name), layout)
+    }
+
+    //records saved in a file not named after a table, like a temporary table
+    pub fn with_file_name(tx: Arc<Mutex<Transaction>>, file_name: &str, layout: Arc<Layout>) -> DbResult<Self> {
+        let mut scan = TableScan {
+            tx,
+            layout,
+            file_name: file_name.to_string(),
+            rp: None,
+            current_slot: None,
+        };
+        if scan.size()? == 0 {
+            scan.move_to_new_block()?;
+        } else {
+            scan.move_to_block(0)?;
+        }
+        Ok(scan)
+    }
+
+    fn record_page(&self) -> &RecordPage {
+        self.rp.as_ref().expect("table scan is closed")
+    }
+
+    fn slot(&self) -> usize {
+        self.current_slot.expect("table scan is not on a record")
+    }
+
+    fn block_number(&self) -> u64 {
+        self.record_page().block().number()
+    }
+
+    fn size(&self) -> DbResult<u64> {
+        self.tx.lock().unwrap().size(&self.file_name)
+    }
+
+    fn at_last_block(&self) -> DbResult<bool> {
+        Ok(self.block_number() + 1 >= self.size()?)
+    }
+
+    fn move_to_block(&mut self, blk_num: u64) -> DbResult<()> {
+        self.close();
+        let blk = BlockId::new(&self.file_name, blk_num);
+        self.rp = Some(RecordPage::new(self.tx.clone(), blk, self.layout.clone())?);
+        self.current_slot = None;
+        Ok(())
+    }
+
+    fn move_to_new_block(&mut self) -> DbResult<()> {
+        self.close();
+        let blk = self.tx.lock().unwrap().append(&self.file_name)?;
+        let rp = RecordPage::new(self.tx.clone(), blk, self.layout.clone())?;
+        rp.format()?;
+        self.rp = Some(rp);
+        self.current_slot = None;
+        Ok(())
+    }
+}
+
+impl Scan for TableScan {
+    fn before_first(&mut self) -> DbResult<()> {
+        self.move_to_block(0)
+    }
+
+    fn next(&mut self) -> DbResult<bool> {
+        self.current_slot = self.record_page().next_after(self.current_slot)?;
+        while self.current_slot.is_none() {
+            if self.at_last_block()? {
+                return Ok(false);
+            }
+            self.move_to_block(self.block_number() + 1)?;
+            self.current_slot = self.record_page().next_after(None)?;
+        }
+        Ok(true)
+    }
+
+    fn get_int(&self, field_name: &str) -> DbResult<i32> {
+        self.record_page().get_int(self.slot(), field_name)
+    }
+
+    fn get_string(&self, field_name: &str) -> DbResult<String> {
+        self.record_page().get_string(self.slot(), field_name)
+    }
+
+    fn get_val(&self, field_name: &str) -> DbResult<Constant> {
+        match self.layout.schema().field_type(field_name) {
+            Some(FieldType::Integer) => Ok(Constant::Int(self.get_int(field_name)?)),
+            Some(FieldType::Varchar) => Ok(Constant::Str(self.get_string(field_name)?)),
+            None => Err(DbError::FieldNotFound(field_name.to_string())),
+        }
+    }
+
+    fn has_field(&self, field_name: &str) -> bool {
+        self.layout.schema().has_field(field_name)
+    }
+
+    //unpin the current block, the scan is also closed when it is dropped
+    fn close(&mut self) {
+        self.rp = None;
+    }
+}
+
+impl UpdateScan for TableScan {
+    fn set_int(&mut self, field_name: &str, val: i32) -> DbResult<()> {
+        self.record_page().set_int(self.slot(), field_name, val)
+    }
+
+    fn set_string(&mut self, field_name: &str, val: &str) -> DbResult<()> {
+        self.record_page().set_string(self.slot(), field_name, val)
+    }
+
+    fn set_val(&mut self, field_name: &str, val: &Constant) -> DbResult<()> {
+        match (self.layout.schema().field_type(field_name), val) {
+            (Some(FieldType::Integer), Constant::Int(val)) => self.set_int(field_name, *val),
+            (Some(FieldType::Varchar), Constant::Str(val)) => self.set_string(field_name, val),
+            (Some(_), _) => Err(DbError::TypeMismatch(field_name.to_string())),
+            (None, _) => Err(DbError::FieldNotFound(field_name.to_string())),
+        }
+    }
+
+    /*
+    take an empty slot after the current record, the following blocks are
+    searched and a new block is appended when all of them are full
+    */
+    fn insert(&mut self) -> DbResult<()> {
+        self.current_slot = self.record_page().insert_after(self.current_slot)?;
+        while self.current_slot.is_none() {
+            if self.at_last_block()? {
+                self.move_to_new_block()?;
+            } else {
+                self.move_to_block(self.block_number() + 1)?;
+            }
+            self.current_slot = self.record_page().insert_after(None)?;
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self) -> DbResult<()> {
+        self.record_page().delete(self.slot())
+    }
+
+    fn get_rid(&self) -> RecordId {
+        RecordId::new(self.block_number(), self.slot())
+    }
+
+    fn move_to_rid(&mut self, rid: RecordId) -> DbResult<()> {
+        self.close();
+        let blk = BlockId::new(&self.file_name, rid.block_number());
+        self.rp = Some(RecordPage::new(self.tx.clone(), blk, self.layout.clone())?);
+        self.current_slot = Some(rid.slot());
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use super::{Layout, Schema, TableScan};
+use crate::error::DbResult;
+use crate::tx_mgr::Transaction;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+static NEXT_TABLE_NUM: AtomicUsize = AtomicUsize::new(0);
+
+/*
+table for intermediate results of a query, it is not in the catalog and
+its file is named <number>temp. The query removes it once the table is no
+longer read, its blocks are dropped from the buffer pool with it. A
+file left behind by a failed query is deleted by FileMgr the next time
+the database starts
+*/
+#[derive(Clone)]
+pub struct TempTable {
+    tx: Arc<Mutex<Transaction>>,
+    file_name: String,
+    layout: Arc<Layout>,
+}
+
+impl TempTable {
+    pub fn new(tx: Arc<Mutex<Transaction>>, schema: &Schema) -> Self {
+        let num = NEXT_TABLE_NUM.fetch_add(1, Ordering::SeqCst) + 1;
+        TempTable {
+            tx,
+            file_name: format!("{}temp", num),
+            layout: Arc::new(Layout::new(schema.clone())),
+        }
+    }
+
+    pub fn open(&self) -> DbResult<TableScan> {
+        TableScan::with_file_name(self.tx.clone(), &self.file_name, self.layout.clone())
+    }
+
+    //every scan on the table must be closed before
+    pub fn remove(&self) -> DbResult<()> {
+        self.tx.lock().unwrap().remove_file(&self.file_name)
+    }
+
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    pub fn layout(&self) -> Arc<Layout> {
+        self.layout.clone()
+    }
+}
@@ -0,0 +1,345 @@
+use super::*;
+use crate::error::DbError;
+use crate::query::{Scan, UpdateScan};
+use crate::server::RustDB;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+fn remove_dir(directory: &str) {
+    if Path::new(directory).exists() {
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
+
+fn test_schema() -> Schema {
+    let mut schema = Schema::new();
+    schema.add_int_field("A");
+    schema.add_string_field("B", 9);
+    schema
+}
+
+#[test]
+fn test_schema_fields() {
+    let schema = test_schema();
+    assert_eq!(schema.fields(), ["A".to_string(), "B".to_string()]);
+    assert_eq!(schema.field_type("B"), Some(FieldType::Varchar));
+    assert_eq!(schema.length("B"), Some(9));
+    assert!(!schema.has_field("C"));
+
+    let mut other = Schema::new();
+    other.add_int_field("C");
+    other.add("B", &schema);
+    other.add_all(&schema);
+    //B is not added twice
+    assert_eq!(other.fields(), ["C".to_string(), "B".to_string(), "A".to_string()]);
+}
+
+#[test]
+fn test_layout_offsets() {
+    let layout = Layout::new(test_schema());
+    //flag takes 4 bytes, A takes 4 bytes, B takes 4 bytes length plus 9 bytes
+    assert_eq!(layout.offset("A"), Some(4));
+    assert_eq!(layout.offset("B"), Some(8));
+    assert_eq!(layout.offset("C"), None);
+    assert_eq!(layout.slot_size(), 21);
+}
+
+#[test]
+fn test_record_page() {
+    let directory = "recordpagetest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let layout = Arc::new(Layout::new(test_schema()));
+    let blk = tx.lock().unwrap().append("testfile").unwrap();
+
+    let rp = RecordPage::new(tx.clone(), blk.clone(), layout.clone()).unwrap();
+    rp.format().unwrap();
+    //400 / 21 slots fit in the block
+    let mut slots = Vec::new();
+    let mut slot = rp.insert_after(None).unwrap();
+    while let Some(s) = slot {
+        rp.set_int(s, "A", s as i32 * 10).unwrap();
+        rp.set_string(s, "B", &format!("rec{}", s)).unwrap();
+        slots.push(s);
+        slot = rp.insert_after(slot).unwrap();
+    }
+    assert_eq!(slots, (0..19).collect::<Vec<_>>());
+
+    //delete records whose A is less than 50
+    let mut slot = rp.next_after(None).unwrap();
+    while let Some(s) = slot {
+        if rp.get_int(s, "A").unwrap() < 50 {
+            rp.delete(s).unwrap();
+        }
+        slot = rp.next_after(slot).unwrap();
+    }
+
+    let mut remaining = Vec::new();
+    let mut slot = rp.next_after(None).unwrap();
+    while let Some(s) = slot {
+        assert_eq!(rp.get_string(s, "B").unwrap(), format!("rec{}", s));
+        remaining.push(rp.get_int(s, "A").unwrap());
+        slot = rp.next_after(slot).unwrap();
+    }
+    assert_eq!(remaining, (5..19).map(|i| i * 10).collect::<Vec<_>>());
+
+    //the deleted slot is reused by the next insert
+    assert_eq!(rp.insert_after(None).unwrap(), Some(0));
+
+    //dropping the record page unpins the block
+    drop(rp);
+    assert_eq!(tx.lock().unwrap().available_buffs(), 8);
+    tx.lock().unwrap().commit().unwrap();
+    remove_dir(directory);
+}
+
+#[test]
+fn test_record_page_field_errors() {
+    let directory = "recordpageerrtest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let layout = Arc::new(Layout::new(test_schema()));
+    let blk = tx.lock().unwrap().append("testfile").unwrap();
+    let rp = RecordPage::new(tx.clone(), blk, layout).unwrap();
+    rp.format().unwrap();
+    let slot = rp.insert_after(None).unwrap().unwrap();
+
+    assert!(matches!(rp.get_int(slot, "C"), Err(DbError::FieldNotFound(_))));
+    match rp.set_string(slot, "B", "longer than nine") {
+        Err(DbError::FieldTooLong { field_name, len, max_len }) => {
+            assert_eq!(field_name, "B");
+            assert_eq!(len, 16);
+            assert_eq!(max_len, 9);
+        }
+        other => panic!("expect field too long, got {:?}", other),
+    }
+    drop(rp);
+    tx.lock().unwrap().commit().unwrap();
+    remove_dir(directory);
+}
+
+#[test]
+fn test_table_scan() {
+    let directory = "tablescantest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let layout = Arc::new(Layout::new(test_schema()));
+
+    let mut ts = TableScan::new(tx.clone(), "T", layout.clone()).unwrap();
+    for i in 0..50 {
+        ts.insert().unwrap();
+        ts.set_int("A", i).unwrap();
+        ts.set_string("B", &format!("rec{}", i)).unwrap();
+    }
+    //19 records fit in one block
+    assert_eq!(tx.lock().unwrap().size("T.tbl").unwrap(), 3);
+    assert_eq!(ts.get_rid(), RecordId::new(2, 11));
+
+    //delete the records with odd A, remember where record 20 is
+    ts.before_first().unwrap();
+    let mut rid20 = None;
+    while ts.next().unwrap() {
+        let a = ts.get_int("A").unwrap();
+        if a % 2 == 1 {
+            ts.delete().unwrap();
+        } else if a == 20 {
+            rid20 = Some(ts.get_rid());
+        }
+    }
+
+    ts.before_first().unwrap();
+    let mut remaining = Vec::new();
+    while ts.next().unwrap() {
+        let a = ts.get_int("A").unwrap();
+        assert_eq!(ts.get_string("B").unwrap(), format!("rec{}", a));
+        remaining.push(a);
+    }
+    assert_eq!(remaining, (0..50).filter(|i| i % 2 == 0).collect::<Vec<_>>());
+
+    let rid20 = rid20.unwrap();
+    assert_eq!(rid20, RecordId::new(1, 1));
+    ts.move_to_rid(rid20).unwrap();
+    assert_eq!(ts.get_int("A").unwrap(), 20);
+
+    //only one block is pinned at a time
+    assert_eq!(tx.lock().unwrap().available_buffs(), 7);
+    ts.close();
+    assert_eq!(tx.lock().unwrap().available_buffs(), 8);
+    tx.lock().unwrap().commit().unwrap();
+
+    //a new scan sees the committed records and fills the deleted slots first
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let mut ts = TableScan::new(tx.clone(), "T", layout).unwrap();
+    assert!(ts.has_field("B"));
+    assert!(!ts.has_field("C"));
+    ts.insert().unwrap();
+    assert_eq!(ts.get_rid(), RecordId::new(0, 1));
+    ts.set_int("A", 100).unwrap();
+    let mut count = 0;
+    ts.before_first().unwrap();
+    while ts.next().unwrap() {
+        count += 1;
+    }
+    assert_eq!(count, 26);
+    drop(ts);
+    tx.lock().unwrap().rollback().unwrap();
+    remove_dir(directory);
+}
+
+fn name_schema() -> Schema {
+    let mut schema = Schema::new();
+    schema.add_int_field("id");
+    schema.add_string_field("name", 100);
+    schema
+}
+
+fn new_slotted_page(tx: &Arc<Mutex<crate::tx_mgr::Transaction>>) -> SlottedPage {
+    let layout = Arc::new(Layout::new(name_schema()));
+    let blk = tx.lock().unwrap().append("slotted").unwrap();
+    let sp = SlottedPage::new(tx.clone(), blk, layout).unwrap();
+    sp.format().unwrap();
+    sp
+}
+
+#[test]
+fn test_slotted_page_short_records() {
+    let directory = "slottedpagetest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let sp = new_slotted_page(&tx);
+
+    let mut slot = None;
+    let mut count = 0;
+    while let Some(s) = sp.insert_after(slot).unwrap() {
+        sp.set_int(s, "id", s as i32).unwrap();
+        sp.set_string(s, "name", "abcde").unwrap();
+        slot = Some(s);
+        count += 1;
+    }
+    //a fixed slot takes 108 bytes, only 3 of them fit in the block
+    let fixed_count = 400 / Layout::new(name_schema()).slot_size();
+    assert_eq!(fixed_count, 3);
+    //8 bytes of slot plus 4 + 4 + 5 bytes of tuple
+    assert_eq!(count, (400 - 8) / 21);
+
+    let mut slot = sp.next_after(None).unwrap();
+    let mut ids = Vec::new();
+    while let Some(s) = slot {
+        assert_eq!(sp.get_string(s, "name").unwrap(), "abcde");
+        ids.push(sp.get_int(s, "id").unwrap());
+        slot = sp.next_after(slot).unwrap();
+    }
+    assert_eq!(ids, (0..count).collect::<Vec<_>>());
+
+    drop(sp);
+    tx.lock().unwrap().commit().unwrap();
+    remove_dir(directory);
+}
+
+#[test]
+fn test_slotted_page_grow_delete_and_compact() {
+    let directory = "slottedcompacttest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let sp = new_slotted_page(&tx);
+
+    for i in 0..10 {
+        let s = sp.insert_after(None).unwrap().unwrap();
+        assert_eq!(s, i);
+        sp.set_int(s, "id", i as i32).unwrap();
+        sp.set_string(s, "name", &"x".repeat(20)).unwrap();
+    }
+    //10 slots of 8 bytes and 10 tuples of 28 bytes
+    assert_eq!(sp.total_free().unwrap(), 400 - 8 - 80 - 280);
+    //the empty tuple of the last record is left as a hole when it grows
+    assert_eq!(sp.contiguous_free().unwrap(), sp.total_free().unwrap() - 8);
+
+    //shrinking stays in place and leaves a hole
+    sp.set_string(1, "name", "short").unwrap();
+    assert_eq!(sp.get_string(1, "name").unwrap(), "short");
+    assert_eq!(sp.total_free().unwrap(), sp.contiguous_free().unwrap() + 8 + 15);
+
+    //tombstones keep the slot numbers of the others
+    for s in [0, 2, 4, 6] {
+        sp.delete(s).unwrap();
+    }
+    assert!(matches!(sp.get_int(2, "id"), Err(DbError::SlotNotFound { slot: 2, .. })));
+    assert_eq!(sp.next_after(None).unwrap(), Some(1));
+    assert_eq!(sp.next_after(Some(1)).unwrap(), Some(3));
+
+    //growing needs more than the free room in the middle, the page is compacted
+    let contiguous = sp.contiguous_free().unwrap();
+    let long_name = "y".repeat(100);
+    assert!(contiguous < 108);
+    sp.set_string(9, "name", &long_name).unwrap();
+    assert_eq!(sp.get_string(9, "name").unwrap(), long_name);
+    assert_eq!(sp.get_int(9, "id").unwrap(), 9);
+    assert_eq!(sp.contiguous_free().unwrap(), sp.total_free().unwrap());
+    for s in [1, 3, 5, 7, 8] {
+        assert_eq!(sp.get_int(s, "id").unwrap(), s as i32);
+    }
+    assert_eq!(sp.get_string(1, "name").unwrap(), "short");
+    assert_eq!(sp.get_string(3, "name").unwrap(), "x".repeat(20));
+
+    //a tombstone is taken by the next insert
+    assert_eq!(sp.insert_after(None).unwrap(), Some(0));
+    assert_eq!(sp.get_string(0, "name").unwrap(), "");
+
+    //growing record 1 by 95 bytes needs more than the room left
+    assert_eq!(sp.total_free().unwrap(), 71);
+    assert!(matches!(sp.set_string(1, "name", &"z".repeat(100)), Err(DbError::PageFull(_))));
+    assert_eq!(sp.get_string(1, "name").unwrap(), "short");
+
+    drop(sp);
+    tx.lock().unwrap().commit().unwrap();
+    remove_dir(directory);
+}
+
+#[test]
+fn test_slotted_page_rollback() {
+    let directory = "slottedrollbacktest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let sp = new_slotted_page(&tx);
+    for i in 0..5 {
+        let s = sp.insert_after(None).unwrap().unwrap();
+        sp.set_int(s, "id", i).unwrap();
+        sp.set_string(s, "name", &format!("name{}", i)).unwrap();
+    }
+    let blk = sp.block().clone();
+    drop(sp);
+    tx.lock().unwrap().commit().unwrap();
+
+    //moving and compacting records are undone like any other change
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let layout = Arc::new(Layout::new(name_schema()));
+    let sp = SlottedPage::new(tx.clone(), blk.clone(), layout.clone()).unwrap();
+    sp.delete(1).unwrap();
+    sp.delete(3).unwrap();
+    sp.set_string(2, "name", &"n".repeat(100)).unwrap();
+    sp.compact().unwrap();
+    sp.set_string(4, "name", &"m".repeat(100)).unwrap();
+    drop(sp);
+    tx.lock().unwrap().rollback().unwrap();
+
+    let tx = Arc::new(Mutex::new(db.new_tx().unwrap()));
+    let sp = SlottedPage::new(tx.clone(), blk, layout).unwrap();
+    let mut slot = sp.next_after(None).unwrap();
+    let mut names = Vec::new();
+    while let Some(s) = slot {
+        assert_eq!(sp.get_int(s, "id").unwrap(), s as i32);
+        names.push(sp.get_string(s, "name").unwrap());
+        slot = sp.next_after(slot).unwrap();
+    }
+    assert_eq!(names, (0..5).map(|i| format!("name{}", i)).collect::<Vec<_>>());
+    drop(sp);
+    tx.lock().unwrap().commit().unwrap();
+    remove_dir(directory);
+}
@@ -0,0 +1,94 @@
+use crate::buf_mgr::*;
+use crate::error::DbResult;
+use crate::file_mgr::*;
+use crate::log_mgr::*;
+use crate::metadata_mgr::MetadataMgr;
+use crate::plan::{HeuristicQueryPlanner, IndexUpdatePlanner, Planner};
+use crate::tx_mgr::concurrency_mgr::LockTable;
+use crate::tx_mgr::Transaction;
+
+use log::info;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub static LOG_FILE: &str = "rustdb.log";
+//how long a transaction waits for a lock before it aborts
+pub const LOCK_MAX_WAIT: Duration = Duration::from_secs(10);
+
+/*
+put file manager, log manager and buffer manager together, when the
+database directory already exists, the system may went down last time
+without finishing some transactions, then we need to recover before
+handing out new transactions. The catalog tables are created together
+with a new database
+*/
+pub struct RustDB {
+    fm: Arc<Mutex<FileMgr>>,
+    lm: Arc<Mutex<LogMgr>>,
+    bm: Arc<BufferMgr>,
+    lock_table: Arc<LockTable>,
+    mdm: Arc<MetadataMgr>,
+    planner: Arc<Planner>,
+}
+
+impl RustDB {
+    pub fn new(db_directory: &str, block_size: u64, buffer_size: u32) -> DbResult<Self> {
+        Self::with_lock_wait(db_directory, block_size, buffer_size, LOCK_MAX_WAIT)
+    }
+
+    pub fn with_lock_wait(db_directory: &str, block_size: u64, buffer_size: u32, lock_max_wait: Duration) -> DbResult<Self> {
+        let fm = Arc::new(Mutex::new(FileMgr::new(db_directory.to_string(), block_size)?));
+        let lm = Arc::new(Mutex::new(LogMgr::new(fm.clone(), LOG_FILE.to_string())?));
+        let bm = Arc::new(BufferMgr::new(fm.clone(), lm.clone(), buffer_size, ReplacementStrategy::Lru));
+        let lock_table = Arc::new(LockTable::new(lock_max_wait));
+
+        let is_new = fm.lock().unwrap().is_new();
+        let mut tx = Transaction::new(fm.clone(), lm.clone(), bm.clone(), lock_table.clone())?;
+        if is_new {
+            info!("creating new database at {}", db_directory);
+        } else {
+            info!("recovering existing database at {}", db_directory);
+            tx.recover()?;
+        }
+        let tx = Arc::new(Mutex::new(tx));
+        let mdm = Arc::new(MetadataMgr::new(is_new, &tx)?);
+        tx.lock().unwrap().commit()?;
+        let planner = Arc::new(Planner::new(
+            Box::new(HeuristicQueryPlanner::new(mdm.clone())),
+            Box::new(IndexUpdatePlanner::new(mdm.clone())),
+        ));
+
+        Ok(RustDB {
+            fm,
+            lm,
+            bm,
+            lock_table,
+            mdm,
+            planner,
+        })
+    }
+
+    pub fn new_tx(&self) -> DbResult<Transaction> {
+        Transaction::new(self.fm.clone(), self.lm.clone(), self.bm.clone(), self.lock_table.clone())
+    }
+
+    pub fn file_mgr(&self) -> Arc<Mutex<FileMgr>> {
+        self.fm.clone()
+    }
+
+    pub fn log_mgr(&self) -> Arc<Mutex<LogMgr>> {
+        self.lm.clone()
+    }
+
+    pub fn buffer_mgr(&self) -> Arc<BufferMgr> {
+        self.bm.clone()
+    }
+
+    pub fn metadata_mgr(&self) -> Arc<MetadataMgr> {
+        self.mdm.clone()
+    }
+
+    pub fn planner(&self) -> Arc<Planner> {
+        self.planner.clone()
+    }
+}
@@ -0,0 +1,49 @@
+use crate::buf_mgr::*;
+use crate::error::DbResult;
+use crate::file_mgr::*;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/*
+buffers pinned by one transaction, a block can be pinned several times
+by the same transaction, each pin is kept as a PinnedBuffer and all of
+them are released when the transaction finishes or is dropped
+*/
+pub struct BufferList {
+    pins: HashMap<BlockId, Vec<PinnedBuffer>>,
+    bm: Arc<BufferMgr>,
+}
+
+impl BufferList {
+    pub fn new(bm: Arc<BufferMgr>) -> Self {
+        BufferList {
+            pins: HashMap::new(),
+            bm,
+        }
+    }
+
+    pub fn get_buffer(&self, blk: &BlockId) -> Option<&PinnedBuffer> {
+        self.pins.get(blk).and_then(|pins| pins.last())
+    }
+
+    pub fn pin(&mut self, blk: &BlockId) -> DbResult<()> {
+        let buff = self.bm.pin(blk.clone())?;
+        self.pins.entry(blk.clone()).or_default().push(buff);
+        Ok(())
+    }
+
+    pub fn unpin(&mut self, blk: &BlockId) {
+        let Some(pins) = self.pins.get_mut(blk) else {
+            return;
+        };
+        pins.pop();
+        if pins.is_empty() {
+            self.pins.remove(blk);
+        }
+    }
+
+    pub fn unpin_all(&mut self) {
+        self.pins.clear();
+    }
+}
@@ -0,0 +1,256 @@
+use crate::error::{DbError, DbResult};
+use crate::file_mgr::*;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LockType {
+    Shared,
+    Exclusive,
+}
+
+#[derive(Debug, Default)]
+struct LockEntry {
+    //transactions holding shared lock on the block
+    shared: HashSet<i32>,
+    //transaction holding exclusive lock on the block
+    exclusive: Option<i32>,
+}
+
+impl LockEntry {
+    /*
+    transactions preventing the given transaction from getting the lock,
+    exclusive lock is granted when no other transaction holds any lock on
+    the block, a transaction holding the only shared lock can upgrade it
+    */
+    fn blockers(&self, tx_num: i32, lock_type: LockType) -> HashSet<i32> {
+        let mut blockers = HashSet::new();
+        if let Some(holder) = self.exclusive {
+            if holder != tx_num {
+                blockers.insert(holder);
+            }
+        }
+        if lock_type == LockType::Exclusive {
+            blockers.extend(self.shared.iter().filter(|holder| **holder != tx_num));
+        }
+        blockers
+    }
+
+    fn grant(&mut self, tx_num: i32, lock_type: LockType) {
+        match lock_type {
+            LockType::Shared => {
+                self.shared.insert(tx_num);
+            }
+            LockType::Exclusive => self.exclusive = Some(tx_num),
+        }
+    }
+
+    fn is_free(&self) -> bool {
+        self.shared.is_empty() && self.exclusive.is_none()
+    }
+}
+
+#[derive(Debug)]
+struct Waiting {
+    blk: BlockId,
+    holders: HashSet<i32>,
+}
+
+#[derive(Debug, Default)]
+struct LockState {
+    entries: HashMap<BlockId, LockEntry>,
+    /*
+    waits-for graph, a blocked transaction points to the transactions
+    holding the lock it asks for
+    */
+    waits_for: HashMap<i32, Waiting>,
+    //transactions chosen to break a deadlock, with the cycle they are in
+    victims: HashMap<i32, Vec<i32>>,
+}
+
+impl LockState {
+    /*
+    search the waits-for graph for a path leading back to the given
+    transaction, the returned cycle begins with the given transaction
+    */
+    fn find_cycle(&self, tx_num: i32) -> Option<Vec<i32>> {
+        let mut path = vec![tx_num];
+        let mut visited = HashSet::new();
+        if self.search_cycle(tx_num, tx_num, &mut path, &mut visited) {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    fn search_cycle(&self, start: i32, current: i32, path: &mut Vec<i32>, visited: &mut HashSet<i32>) -> bool {
+        let Some(waiting) = self.waits_for.get(&current) else {
+            return false;
+        };
+        for next in waiting.holders.iter() {
+            if *next == start {
+                return true;
+            }
+            if visited.insert(*next) {
+                path.push(*next);
+                if self.search_cycle(start, *next, path, visited) {
+                    return true;
+                }
+                path.pop();
+            }
+        }
+        false
+    }
+}
+
+/*
+lock table shared by all transactions, a transaction asking for a lock
+that conflicts with others waits until the lock is released or the
+waiting time runs out, in the later case the transaction should abort
+and release all its locks.
+
+every blocked request is recorded in a waits-for graph, when the request
+closes a cycle the youngest transaction in the cycle, the one with the
+largest transaction number, is chosen as victim and gets a deadlock error
+at once instead of waiting for the time out
+*/
+pub struct LockTable {
+    state: Mutex<LockState>,
+    lock_released: Condvar,
+    max_wait: Duration,
+}
+
+impl LockTable {
+    pub fn new(max_wait: Duration) -> Self {
+        LockTable {
+            state: Mutex::new(LockState::default()),
+            lock_released: Condvar::new(),
+            max_wait,
+        }
+    }
+
+    pub fn slock(&self, blk: &BlockId, tx_num: i32) -> DbResult<()> {
+        self.acquire(blk, tx_num, LockType::Shared)
+    }
+
+    pub fn xlock(&self, blk: &BlockId, tx_num: i32) -> DbResult<()> {
+        self.acquire(blk, tx_num, LockType::Exclusive)
+    }
+
+    fn acquire(&self, blk: &BlockId, tx_num: i32, lock_type: LockType) -> DbResult<()> {
+        let start = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(cycle) = state.victims.remove(&tx_num) {
+                state.waits_for.remove(&tx_num);
+                return Err(DbError::Deadlock { blk: blk.clone(), cycle });
+            }
+
+            let entry = state.entries.entry(blk.clone()).or_default();
+            let holders = entry.blockers(tx_num, lock_type);
+            if holders.is_empty() {
+                entry.grant(tx_num, lock_type);
+                state.waits_for.remove(&tx_num);
+                return Ok(());
+            }
+
+            state.waits_for.insert(tx_num, Waiting { blk: blk.clone(), holders });
+            if let Some(mut cycle) = state.find_cycle(tx_num) {
+                let victim = *cycle.iter().max().unwrap();
+                let victim_pos = cycle.iter().position(|tx| *tx == victim).unwrap();
+                cycle.rotate_left(victim_pos);
+                if victim == tx_num {
+                    state.waits_for.remove(&tx_num);
+                    return Err(DbError::Deadlock { blk: blk.clone(), cycle });
+                }
+                //the victim is blocked in the cycle, wake it up to abort
+                state.waits_for.remove(&victim);
+                state.victims.insert(victim, cycle);
+                self.lock_released.notify_all();
+            }
+
+            let waited = start.elapsed();
+            if waited >= self.max_wait {
+                state.waits_for.remove(&tx_num);
+                return Err(DbError::LockAbort(blk.clone()));
+            }
+            state = self
+                .lock_released
+                .wait_timeout(state, self.max_wait - waited)
+                .unwrap()
+                .0;
+        }
+    }
+
+    //release all locks held by the transaction on the block
+    pub fn unlock(&self, blk: &BlockId, tx_num: i32) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(entry) = state.entries.get_mut(blk) {
+            entry.shared.remove(&tx_num);
+            if entry.exclusive == Some(tx_num) {
+                entry.exclusive = None;
+            }
+            if entry.is_free() {
+                state.entries.remove(blk);
+            }
+        }
+        //waiters on the block no longer wait for the transaction
+        for waiting in state.waits_for.values_mut() {
+            if waiting.blk == *blk {
+                waiting.holders.remove(&tx_num);
+            }
+        }
+        self.lock_released.notify_all();
+    }
+}
+
+/*
+locks of one transaction, locks are only released when the transaction
+commits or rolls back, that is strict two phase locking
+*/
+pub struct ConcurrencyMgr {
+    lock_table: Arc<LockTable>,
+    locks: HashMap<BlockId, LockType>,
+    tx_num: i32,
+}
+
+impl ConcurrencyMgr {
+    pub fn new(tx_num: i32, lock_table: Arc<LockTable>) -> Self {
+        ConcurrencyMgr {
+            lock_table,
+            locks: HashMap::new(),
+            tx_num,
+        }
+    }
+
+    pub fn slock(&mut self, blk: &BlockId) -> DbResult<()> {
+        if !self.locks.contains_key(blk) {
+            self.lock_table.slock(blk, self.tx_num)?;
+            self.locks.insert(blk.clone(), LockType::Shared);
+        }
+        Ok(())
+    }
+
+    //get shared lock first then upgrade it to exclusive lock
+    pub fn xlock(&mut self, blk: &BlockId) -> DbResult<()> {
+        if !self.has_xlock(blk) {
+            self.slock(blk)?;
+            self.lock_table.xlock(blk, self.tx_num)?;
+            self.locks.insert(blk.clone(), LockType::Exclusive);
+        }
+        Ok(())
+    }
+
+    pub fn release(&mut self) {
+        for blk in self.locks.keys() {
+            self.lock_table.unlock(blk, self.tx_num);
+        }
+        self.locks.clear();
+    }
+
+    fn has_xlock(&self, blk: &BlockId) -> bool {
+        self.locks.get(blk) == Some(&LockType::Exclusive)
+    }
+}
@@ -0,0 +1,212 @@
+use crate::error::{DbError, DbResult};
+use crate::file_mgr::*;
+use crate::log_mgr::*;
+use crate::tx_mgr::Transaction;
+
+use std::sync::{Arc, Mutex};
+
+pub const CHECKPOINT: i32 = 0;
+pub const START: i32 = 1;
+pub const COMMIT: i32 = 2;
+pub const ROLLBACK: i32 = 3;
+pub const SETINT: i32 = 4;
+pub const SETSTRING: i32 = 5;
+pub const SETBYTES: i32 = 6;
+
+/*
+each log record begins with 4 bytes for its operation code, records
+of a transaction have the transaction number in the following 4 bytes,
+SETINT, SETSTRING and SETBYTES records are layout as:
+
+op | tx_num | file name | block number | offset | old value
+
+the old value is what we need to undo the modification
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogRecord {
+    Checkpoint,
+    Start(i32),
+    Commit(i32),
+    Rollback(i32),
+    SetInt {
+        tx_num: i32,
+        blk: BlockId,
+        offset: usize,
+        val: i32,
+    },
+    SetString {
+        tx_num: i32,
+        blk: BlockId,
+        offset: usize,
+        val: String,
+    },
+    //bytes written without length prefix, the old bytes have the same length
+    SetBytes {
+        tx_num: i32,
+        blk: BlockId,
+        offset: usize,
+        val: Vec<u8>,
+    },
+}
+
+impl LogRecord {
+    pub fn from_bytes(mut bytes: Vec<u8>) -> DbResult<Self> {
+        let mut p = Page::from_buffer(&mut bytes);
+        let op = p.get_int(0)?;
+        let rec = match op {
+            CHECKPOINT => LogRecord::Checkpoint,
+            START => LogRecord::Start(p.get_int(4)?),
+            COMMIT => LogRecord::Commit(p.get_int(4)?),
+            ROLLBACK => LogRecord::Rollback(p.get_int(4)?),
+            SETINT | SETSTRING | SETBYTES => {
+                let tx_num = p.get_int(4)?;
+                let file_name = p.get_string(8)?;
+                let blk_pos = 8 + Page::max_length(file_name.len() as u64);
+                let blk_num = p.get_int(blk_pos)?;
+                let offset = p.get_int(blk_pos + 4)?;
+                let val_pos = blk_pos + 8;
+                let blk = BlockId::new(&file_name, blk_num as u64);
+                match op {
+                    SETINT => LogRecord::SetInt {
+                        tx_num,
+                        blk,
+                        offset: offset as usize,
+                        val: p.get_int(val_pos)?,
+                    },
+                    SETSTRING => LogRecord::SetString {
+                        tx_num,
+                        blk,
+                        offset: offset as usize,
+                        val: p.get_string(val_pos as usize)?,
+                    },
+                    _ => LogRecord::SetBytes {
+                        tx_num,
+                        blk,
+                        offset: offset as usize,
+                        val: p.get_bytes(val_pos as usize)?,
+                    },
+                }
+            }
+            _ => return Err(DbError::CorruptLogRecord(format!("unknown log operation:{}", op))),
+        };
+
+        Ok(rec)
+    }
+
+    pub fn to_bytes(&self) -> DbResult<Vec<u8>> {
+        match self {
+            LogRecord::Checkpoint => {
+                let mut buf = vec![0u8; 4];
+                Page::from_buffer(&mut buf).set_int(0, CHECKPOINT)?;
+                Ok(buf)
+            }
+            LogRecord::Start(tx_num) => Self::tx_bytes(START, *tx_num),
+            LogRecord::Commit(tx_num) => Self::tx_bytes(COMMIT, *tx_num),
+            LogRecord::Rollback(tx_num) => Self::tx_bytes(ROLLBACK, *tx_num),
+            LogRecord::SetInt { tx_num, blk, offset, val } => {
+                let val_pos = Self::update_header_len(blk);
+                let mut buf = vec![0u8; val_pos + 4];
+                let mut p = Page::from_buffer(&mut buf);
+                Self::write_update_header(&mut p, SETINT, *tx_num, blk, *offset)?;
+                p.set_int(val_pos, *val)?;
+                Ok(buf)
+            }
+            LogRecord::SetString { tx_num, blk, offset, val } => {
+                let val_pos = Self::update_header_len(blk);
+                let mut buf = vec![0u8; val_pos + Page::max_length(val.len() as u64) as usize];
+                let mut p = Page::from_buffer(&mut buf);
+                Self::write_update_header(&mut p, SETSTRING, *tx_num, blk, *offset)?;
+                p.set_string(val_pos, val)?;
+                Ok(buf)
+            }
+            LogRecord::SetBytes { tx_num, blk, offset, val } => {
+                let val_pos = Self::update_header_len(blk);
+                let mut buf = vec![0u8; val_pos + Page::max_length(val.len() as u64) as usize];
+                let mut p = Page::from_buffer(&mut buf);
+                Self::write_update_header(&mut p, SETBYTES, *tx_num, blk, *offset)?;
+                p.set_bytes(val_pos, val)?;
+                Ok(buf)
+            }
+        }
+    }
+
+    fn tx_bytes(op: i32, tx_num: i32) -> DbResult<Vec<u8>> {
+        let mut buf = vec![0u8; 8];
+        let mut p = Page::from_buffer(&mut buf);
+        p.set_int(0, op)?;
+        p.set_int(4, tx_num)?;
+        Ok(buf)
+    }
+
+    fn update_header_len(blk: &BlockId) -> usize {
+        //op, tx_num, file name, block number and offset
+        8 + Page::max_length(blk.file_name().len() as u64) as usize + 8
+    }
+
+    fn write_update_header(p: &mut Page, op: i32, tx_num: i32, blk: &BlockId, offset: usize) -> DbResult<()> {
+        p.set_int(0, op)?;
+        p.set_int(4, tx_num)?;
+        p.set_string(8, &blk.file_name())?;
+        let blk_pos = 8 + Page::max_length(blk.file_name().len() as u64) as usize;
+        p.set_int(blk_pos, blk.number() as i32)?;
+        p.set_int(blk_pos + 4, offset as i32)?;
+        Ok(())
+    }
+
+    pub fn op(&self) -> i32 {
+        match self {
+            LogRecord::Checkpoint => CHECKPOINT,
+            LogRecord::Start(_) => START,
+            LogRecord::Commit(_) => COMMIT,
+            LogRecord::Rollback(_) => ROLLBACK,
+            LogRecord::SetInt { .. } => SETINT,
+            LogRecord::SetString { .. } => SETSTRING,
+            LogRecord::SetBytes { .. } => SETBYTES,
+        }
+    }
+
+    //checkpoint record does not belong to any transaction
+    pub fn tx_number(&self) -> i32 {
+        match self {
+            LogRecord::Checkpoint => -1,
+            LogRecord::Start(tx_num) | LogRecord::Commit(tx_num) | LogRecord::Rollback(tx_num) => *tx_num,
+            LogRecord::SetInt { tx_num, .. }
+            | LogRecord::SetString { tx_num, .. }
+            | LogRecord::SetBytes { tx_num, .. } => *tx_num,
+        }
+    }
+
+    pub fn write_to_log(&self, lm: &Arc<Mutex<LogMgr>>) -> DbResult<u64> {
+        let bytes = self.to_bytes()?;
+        lm.lock().unwrap().append(&bytes)
+    }
+
+    /*
+    restore the old value saved in the record, the restoring must not
+    be logged again, otherwise the undo itself would be undone by
+    a later recovery
+    */
+    pub fn undo(&self, tx: &mut Transaction) -> DbResult<()> {
+        match self {
+            LogRecord::SetInt { blk, .. } | LogRecord::SetString { blk, .. } | LogRecord::SetBytes { blk, .. }
+                if tx.is_removed(&blk.file_name()) => {}
+            LogRecord::SetInt { blk, offset, val, .. } => {
+                tx.pin(blk)?;
+                tx.set_int(blk, *offset, *val, false)?;
+                tx.unpin(blk);
+            }
+            LogRecord::SetString { blk, offset, val, .. } => {
+                tx.pin(blk)?;
+                tx.set_string(blk, *offset, val, false)?;
+                tx.unpin(blk);
+            }
+            LogRecord::SetBytes { blk, offset, val, .. } => {
+                tx.pin(blk)?;
+                tx.set_bytes(blk, *offset, val, false)?;
+                tx.unpin(blk);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+pub mod buffer_list;
+pub mod concurrency_mgr;
+pub mod log_record;
+pub mod recovery_mgr;
+#[cfg(test)]
+pub mod test;
+
+use crate::buf_mgr::*;
+use crate::error::{DbError, DbResult};
+use crate::file_mgr::*;
+use crate::log_mgr::*;
+use buffer_list::BufferList;
+use concurrency_mgr::{ConcurrencyMgr, LockTable};
+use recovery_mgr::RecoveryMgr;
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+
+/*
+transaction number 0 and negative numbers are reserved, Buffer treats
+tx_num <= 0 as "not modified", therefore numbering begins from 1
+*/
+static NEXT_TX_NUM: AtomicI32 = AtomicI32::new(1);
+
+fn next_tx_number() -> i32 {
+    NEXT_TX_NUM.fetch_add(1, Ordering::SeqCst)
+}
+
+/*
+dummy block number standing for the end of a file, reading the size of
+a file takes shared lock on it and appending a block takes exclusive lock
+on it, that prevents other transactions from seeing phantom blocks
+*/
+const END_OF_FILE: u64 = u64::MAX;
+
+pub struct Transaction {
+    fm: Arc<Mutex<FileMgr>>,
+    lm: Arc<Mutex<LogMgr>>,
+    bm: Arc<BufferMgr>,
+    lock_table: Arc<LockTable>,
+    recovery_mgr: RecoveryMgr,
+    concur_mgr: ConcurrencyMgr,
+    my_buffers: BufferList,
+    tx_num: i32,
+    //false for a reader of estimates, its reads take no shared locks
+    lock_reads: bool,
+    //files removed by the transaction, rolling back leaves them alone
+    removed_files: HashSet<String>,
+}
+
+impl Transaction {
+    pub fn new(
+        fm: Arc<Mutex<FileMgr>>,
+        lm: Arc<Mutex<LogMgr>>,
+        bm: Arc<BufferMgr>,
+        lock_table: Arc<LockTable>,
+    ) -> DbResult<Self> {
+        let tx_num = next_tx_number();
+        let recovery_mgr = RecoveryMgr::new(tx_num, lm.clone(), bm.clone())?;
+        Ok(Transaction {
+            fm,
+            lm,
+            bm: bm.clone(),
+            lock_table: lock_table.clone(),
+            recovery_mgr,
+            concur_mgr: ConcurrencyMgr::new(tx_num, lock_table),
+            my_buffers: BufferList::new(bm),
+            tx_num,
+            lock_reads: true,
+            removed_files: HashSet::new(),
+        })
+    }
+
+    /*
+    another transaction whose reads take no locks, it may see changes not
+    committed yet. It is for estimates like statistics, which must neither
+    wait for other transactions nor keep them waiting, and is committed as
+    soon as the reading is done
+    */
+    pub fn unlocked_reader(&self) -> DbResult<Transaction> {
+        let mut tx = Transaction::new(self.fm.clone(), self.lm.clone(), self.bm.clone(), self.lock_table.clone())?;
+        tx.lock_reads = false;
+        Ok(tx)
+    }
+
+    fn slock(&mut self, blk: &BlockId) -> DbResult<()> {
+        if self.lock_reads {
+            self.concur_mgr.slock(blk)?;
+        }
+        Ok(())
+    }
+
+    pub fn tx_num(&self) -> i32 {
+        self.tx_num
+    }
+
+    /*
+    flush modified buffers and the COMMIT record onto disk, then release
+    all locks and pinned buffers
+    */
+    pub fn commit(&mut self) -> DbResult<()> {
+        self.recovery_mgr.commit()?;
+        self.concur_mgr.release();
+        self.my_buffers.unpin_all();
+        Ok(())
+    }
+
+    //undo every modification of the transaction from the latest to the oldest
+    pub fn rollback(&mut self) -> DbResult<()> {
+        for rec in self.recovery_mgr.records_to_rollback()? {
+            rec.undo(self)?;
+        }
+        self.recovery_mgr.rollback()?;
+        self.concur_mgr.release();
+        self.my_buffers.unpin_all();
+        Ok(())
+    }
+
+    /*
+    undo modifications of all transactions not finished before the system
+    went down, should be called at start up before any other transaction
+    begins
+    */
+    pub fn recover(&mut self) -> DbResult<()> {
+        for rec in self.recovery_mgr.records_to_recover()? {
+            rec.undo(self)?;
+        }
+        self.recovery_mgr.recover()?;
+        self.concur_mgr.release();
+        self.my_buffers.unpin_all();
+        Ok(())
+    }
+
+    pub fn pin(&mut self, blk: &BlockId) -> DbResult<()> {
+        self.my_buffers.pin(blk)
+    }
+
+    pub fn unpin(&mut self, blk: &BlockId) {
+        self.my_buffers.unpin(blk);
+    }
+
+    fn pinned_buffer(&self, blk: &BlockId) -> DbResult<&PinnedBuffer> {
+        self.my_buffers
+            .get_buffer(blk)
+            .ok_or_else(|| DbError::BlockNotPinned(blk.clone()))
+    }
+
+    pub fn get_int(&mut self, blk: &BlockId, offset: usize) -> DbResult<i32> {
+        self.slock(blk)?;
+        let buff = self.pinned_buffer(blk)?;
+        let mut buff_guard = buff.write().unwrap();
+        buff_guard.contents().get_int(offset as u64)
+    }
+
+    pub fn get_string(&mut self, blk: &BlockId, offset: usize) -> DbResult<String> {
+        self.slock(blk)?;
+        let buff = self.pinned_buffer(blk)?;
+        let mut buff_guard = buff.write().unwrap();
+        buff_guard.contents().get_string(offset)
+    }
+
+    /*
+    ok_to_log is false when the value is written back by undo, otherwise
+    the old value is logged before the buffer is changed
+    */
+    pub fn set_int(&mut self, blk: &BlockId, offset: usize, val: i32, ok_to_log: bool) -> DbResult<()> {
+        self.concur_mgr.xlock(blk)?;
+        let buff = self.pinned_buffer(blk)?;
+        let mut buff_guard = buff.write().unwrap();
+        let mut lsn = None;
+        if ok_to_log {
+            lsn = Some(self.recovery_mgr.set_int(&mut buff_guard, offset)?);
+        }
+        buff_guard.contents().set_int(offset, val)?;
+        buff_guard.set_modified(self.tx_num, lsn);
+        Ok(())
+    }
+
+    pub fn set_string(&mut self, blk: &BlockId, offset: usize, val: &str, ok_to_log: bool) -> DbResult<()> {
+        self.concur_mgr.xlock(blk)?;
+        let buff = self.pinned_buffer(blk)?;
+        let mut buff_guard = buff.write().unwrap();
+        let mut lsn = None;
+        if ok_to_log {
+            lsn = Some(self.recovery_mgr.set_string(&mut buff_guard, offset)?);
+        }
+        buff_guard.contents().set_string(offset, val)?;
+        buff_guard.set_modified(self.tx_num, lsn);
+        Ok(())
+    }
+
+    pub fn get_bytes(&mut self, blk: &BlockId, offset: usize, len: usize) -> DbResult<Vec<u8>> {
+        self.slock(blk)?;
+        let buff = self.pinned_buffer(blk)?;
+        let mut buff_guard = buff.write().unwrap();
+        buff_guard.contents().get_raw_bytes(offset, len)
+    }
+
+    /*
+    overwrite the bytes beginning at offset, no length prefix is written.
+    The old bytes are logged in pieces, a log record must fit in one log
+    block while the bytes may take most of a block
+    */
+    pub fn set_bytes(&mut self, blk: &BlockId, offset: usize, val: &[u8], ok_to_log: bool) -> DbResult<()> {
+        self.concur_mgr.xlock(blk)?;
+        let buff = self.pinned_buffer(blk)?;
+        let mut buff_guard = buff.write().unwrap();
+        let mut lsn = None;
+        if ok_to_log {
+            let piece_size = (self.block_size() as usize / 4).max(1);
+            let mut piece_offset = offset;
+            while piece_offset < offset + val.len() {
+                let len = piece_size.min(offset + val.len() - piece_offset);
+                lsn = Some(self.recovery_mgr.set_bytes(&mut buff_guard, piece_offset, len)?);
+                piece_offset += len;
+            }
+        }
+        buff_guard.contents().set_raw_bytes(offset, val)?;
+        buff_guard.set_modified(self.tx_num, lsn);
+        Ok(())
+    }
+
+    //number of blocks in the given file
+    pub fn size(&mut self, file_name: &str) -> DbResult<u64> {
+        self.slock(&BlockId::new(file_name, END_OF_FILE))?;
+        self.fm.lock().unwrap().length(file_name.to_string())
+    }
+
+    pub fn append(&mut self, file_name: &str) -> DbResult<BlockId> {
+        self.concur_mgr.xlock(&BlockId::new(file_name, END_OF_FILE))?;
+        self.fm.lock().unwrap().append(file_name.to_string())
+    }
+
+    /*
+    delete a file the transaction no longer needs, like a temporary table,
+    its blocks leave the pool without being written back. Undo skips the
+    changes logged for the file, otherwise a rollback would bring it back
+    */
+    pub fn remove_file(&mut self, file_name: &str) -> DbResult<()> {
+        self.bm.remove_file(file_name)?;
+        self.removed_files.insert(file_name.to_string());
+        Ok(())
+    }
+
+    pub fn is_removed(&self, file_name: &str) -> bool {
+        self.removed_files.contains(file_name)
+    }
+
+    pub fn available_buffs(&self) -> u32 {
+        self.bm.available()
+    }
+
+    pub fn block_size(&self) -> u64 {
+        self.fm.lock().unwrap().block_size()
+    }
+}
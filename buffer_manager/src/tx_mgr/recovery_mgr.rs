@@ -0,0 +1,133 @@
+use crate::buf_mgr::*;
+use crate::error::DbResult;
+use crate::log_mgr::*;
+use crate::tx_mgr::log_record::LogRecord;
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+/*
+undo only recovery, every modification writes the old value into the
+log before the buffer is changed, and a transaction flushes all its
+modified buffers before writing the COMMIT record. Therefore on
+recovery we only need to restore the old values of those transactions
+that have no COMMIT or ROLLBACK record in the log
+*/
+pub struct RecoveryMgr {
+    lm: Arc<Mutex<LogMgr>>,
+    bm: Arc<BufferMgr>,
+    tx_num: i32,
+}
+
+impl RecoveryMgr {
+    pub fn new(tx_num: i32, lm: Arc<Mutex<LogMgr>>, bm: Arc<BufferMgr>) -> DbResult<Self> {
+        LogRecord::Start(tx_num).write_to_log(&lm)?;
+        Ok(RecoveryMgr { lm, bm, tx_num })
+    }
+
+    pub fn commit(&self) -> DbResult<()> {
+        self.bm.flush_all(self.tx_num)?;
+        let lsn = LogRecord::Commit(self.tx_num).write_to_log(&self.lm)?;
+        let ticket = self.lm.lock().unwrap().flush(lsn)?;
+        ticket.wait()
+    }
+
+    //called after the records returned by records_to_rollback are undone
+    pub fn rollback(&self) -> DbResult<()> {
+        self.bm.flush_all(self.tx_num)?;
+        let lsn = LogRecord::Rollback(self.tx_num).write_to_log(&self.lm)?;
+        let ticket = self.lm.lock().unwrap().flush(lsn)?;
+        ticket.wait()
+    }
+
+    //called after the records returned by records_to_recover are undone
+    pub fn recover(&self) -> DbResult<()> {
+        self.bm.flush_all(self.tx_num)?;
+        let lsn = LogRecord::Checkpoint.write_to_log(&self.lm)?;
+        let ticket = self.lm.lock().unwrap().flush(lsn)?;
+        ticket.wait()
+    }
+
+    /*
+    write the old value of the given position into log and return the
+    lsn of the log record, the caller then modifies the buffer
+    */
+    pub fn set_int(&self, buff: &mut Buffer, offset: usize) -> DbResult<u64> {
+        let old_val = buff.contents().get_int(offset as u64)?;
+        LogRecord::SetInt {
+            tx_num: self.tx_num,
+            blk: buff.block(),
+            offset,
+            val: old_val,
+        }
+        .write_to_log(&self.lm)
+    }
+
+    pub fn set_string(&self, buff: &mut Buffer, offset: usize) -> DbResult<u64> {
+        let old_val = buff.contents().get_string(offset)?;
+        LogRecord::SetString {
+            tx_num: self.tx_num,
+            blk: buff.block(),
+            offset,
+            val: old_val,
+        }
+        .write_to_log(&self.lm)
+    }
+
+    //the len bytes beginning at offset are going to be overwritten
+    pub fn set_bytes(&self, buff: &mut Buffer, offset: usize, len: usize) -> DbResult<u64> {
+        let old_val = buff.contents().get_raw_bytes(offset, len)?;
+        LogRecord::SetBytes {
+            tx_num: self.tx_num,
+            blk: buff.block(),
+            offset,
+            val: old_val,
+        }
+        .write_to_log(&self.lm)
+    }
+
+    /*
+    records of the current transaction from the latest back to its START
+    record, in the order they should be undone
+    */
+    pub fn records_to_rollback(&self) -> DbResult<Vec<LogRecord>> {
+        let iter = self.lm.lock().unwrap().iterator()?;
+        let mut records = Vec::new();
+        for bytes in iter {
+            let rec = LogRecord::from_bytes(bytes?)?;
+            if rec.tx_number() != self.tx_num {
+                continue;
+            }
+            if let LogRecord::Start(_) = rec {
+                break;
+            }
+            records.push(rec);
+        }
+        Ok(records)
+    }
+
+    /*
+    records of all unfinished transactions from the end of the log back to
+    the latest checkpoint, in the order they should be undone
+    */
+    pub fn records_to_recover(&self) -> DbResult<Vec<LogRecord>> {
+        let iter = self.lm.lock().unwrap().iterator()?;
+        let mut finished_txs = HashSet::new();
+        let mut records = Vec::new();
+        for bytes in iter {
+            let rec = LogRecord::from_bytes(bytes?)?;
+            match rec {
+                LogRecord::Checkpoint => break,
+                LogRecord::Commit(tx_num) | LogRecord::Rollback(tx_num) => {
+                    finished_txs.insert(tx_num);
+                }
+                _ => {
+                    if !finished_txs.contains(&rec.tx_number()) {
+                        records.push(rec);
+                    }
+                }
+            }
+        }
+        Ok(records)
+    }
+}
@@ -0,0 +1,297 @@
+use super::concurrency_mgr::LockTable;
+use super::log_record::LogRecord;
+use crate::error::DbError;
+use crate::file_mgr::*;
+use crate::server::RustDB;
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn remove_dir(directory: &str) {
+    if Path::new(directory).exists() {
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
+
+#[test]
+fn test_log_record_bytes() {
+    let records = vec![
+        LogRecord::Checkpoint,
+        LogRecord::Start(3),
+        LogRecord::Commit(3),
+        LogRecord::Rollback(4),
+        LogRecord::SetInt {
+            tx_num: 5,
+            blk: BlockId::new("testfile", 7),
+            offset: 80,
+            val: -12,
+        },
+        LogRecord::SetString {
+            tx_num: 6,
+            blk: BlockId::new("testfile", 1),
+            offset: 40,
+            val: "hello".to_string(),
+        },
+        LogRecord::SetBytes {
+            tx_num: 7,
+            blk: BlockId::new("testfile", 2),
+            offset: 12,
+            val: vec![0, 1, 2, 255],
+        },
+    ];
+
+    for rec in records {
+        let bytes = rec.to_bytes().unwrap();
+        assert_eq!(LogRecord::from_bytes(bytes).unwrap(), rec);
+    }
+}
+
+#[test]
+fn test_commit_and_rollback() {
+    let directory = "txtest";
+    remove_dir(directory);
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let blk = BlockId::new("testfile", 1);
+
+    let mut tx1 = db.new_tx().unwrap();
+    tx1.pin(&blk).unwrap();
+    //initial values need not to be logged
+    tx1.set_int(&blk, 80, 1, false).unwrap();
+    tx1.set_string(&blk, 40, "one", false).unwrap();
+    tx1.commit().unwrap();
+
+    let mut tx2 = db.new_tx().unwrap();
+    tx2.pin(&blk).unwrap();
+    assert_eq!(tx2.get_int(&blk, 80).unwrap(), 1);
+    assert_eq!(tx2.get_string(&blk, 40).unwrap(), "one");
+    tx2.set_int(&blk, 80, 2, true).unwrap();
+    tx2.set_string(&blk, 40, "one!", true).unwrap();
+    tx2.commit().unwrap();
+
+    let mut tx3 = db.new_tx().unwrap();
+    tx3.pin(&blk).unwrap();
+    tx3.set_int(&blk, 80, 9999, true).unwrap();
+    tx3.set_string(&blk, 40, "changed", true).unwrap();
+    assert_eq!(tx3.get_int(&blk, 80).unwrap(), 9999);
+    tx3.rollback().unwrap();
+
+    let mut tx4 = db.new_tx().unwrap();
+    tx4.pin(&blk).unwrap();
+    assert_eq!(tx4.get_int(&blk, 80).unwrap(), 2);
+    assert_eq!(tx4.get_string(&blk, 40).unwrap(), "one!");
+    tx4.commit().unwrap();
+    //all pins are released after commit and rollback
+    assert_eq!(tx4.available_buffs(), 8);
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_recovery() {
+    let directory = "recoverytest";
+    remove_dir(directory);
+    let blk0 = BlockId::new("testfile", 0);
+    let blk1 = BlockId::new("testfile", 1);
+    {
+        let db = RustDB::new(directory, 400, 8).unwrap();
+        let mut tx1 = db.new_tx().unwrap();
+        tx1.pin(&blk0).unwrap();
+        tx1.pin(&blk1).unwrap();
+        for i in 0..6 {
+            tx1.set_int(&blk0, i * 4, i as i32, false).unwrap();
+            tx1.set_string(&blk1, i * 20, "abc", false).unwrap();
+        }
+        tx1.commit().unwrap();
+
+        let mut tx2 = db.new_tx().unwrap();
+        tx2.pin(&blk0).unwrap();
+        tx2.pin(&blk1).unwrap();
+        for i in 0..6 {
+            tx2.set_int(&blk0, i * 4, (i + 100) as i32, true).unwrap();
+            tx2.set_string(&blk1, i * 20, "uncommitted", true).unwrap();
+        }
+        //the modification reaches disk but tx2 never commits
+        db.buffer_mgr().flush_all(tx2.tx_num()).unwrap();
+    }
+
+    //open the existing directory again, uncommitted changes are undone
+    let db = RustDB::new(directory, 400, 8).unwrap();
+    let mut tx = db.new_tx().unwrap();
+    tx.pin(&blk0).unwrap();
+    tx.pin(&blk1).unwrap();
+    for i in 0..6 {
+        assert_eq!(tx.get_int(&blk0, i * 4).unwrap(), i as i32);
+        assert_eq!(tx.get_string(&blk1, i * 20).unwrap(), "abc");
+    }
+    tx.commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_lock_table_shared_and_exclusive() {
+    let lock_table = LockTable::new(Duration::from_millis(100));
+    let blk = BlockId::new("testfile", 1);
+
+    lock_table.slock(&blk, 1).unwrap();
+    lock_table.slock(&blk, 2).unwrap();
+    assert!(matches!(lock_table.xlock(&blk, 3), Err(DbError::LockAbort(_))));
+    //can't upgrade while another transaction is reading
+    assert!(matches!(lock_table.xlock(&blk, 1), Err(DbError::LockAbort(_))));
+
+    lock_table.unlock(&blk, 2);
+    lock_table.xlock(&blk, 1).unwrap();
+    assert!(matches!(lock_table.slock(&blk, 2), Err(DbError::LockAbort(_))));
+
+    lock_table.unlock(&blk, 1);
+    lock_table.slock(&blk, 2).unwrap();
+}
+
+#[test]
+fn test_lock_upgrade_waits_for_reader() {
+    let lock_table = LockTable::new(Duration::from_secs(5));
+    let blk = BlockId::new("testfile", 1);
+    lock_table.slock(&blk, 1).unwrap();
+    lock_table.slock(&blk, 2).unwrap();
+
+    thread::scope(|s| {
+        let writer = s.spawn(|| {
+            let start = Instant::now();
+            lock_table.xlock(&blk, 1).unwrap();
+            start.elapsed()
+        });
+        thread::sleep(Duration::from_millis(200));
+        lock_table.unlock(&blk, 2);
+        let waited = writer.join().unwrap();
+        assert!(waited >= Duration::from_millis(150));
+    });
+}
+
+#[test]
+fn test_uncommitted_write_is_isolated() {
+    let directory = "locktest";
+    remove_dir(directory);
+    let db = RustDB::with_lock_wait(directory, 400, 8, Duration::from_secs(5)).unwrap();
+    let blk = BlockId::new("testfile", 0);
+
+    let mut tx1 = db.new_tx().unwrap();
+    tx1.pin(&blk).unwrap();
+    tx1.set_int(&blk, 0, 1, false).unwrap();
+    tx1.commit().unwrap();
+
+    let mut writer = db.new_tx().unwrap();
+    writer.pin(&blk).unwrap();
+    writer.set_int(&blk, 0, 100, true).unwrap();
+
+    thread::scope(|s| {
+        let reader = s.spawn(|| {
+            let mut tx = db.new_tx().unwrap();
+            tx.pin(&blk).unwrap();
+            //blocks until the writer releases its exclusive lock
+            let val = tx.get_int(&blk, 0).unwrap();
+            tx.commit().unwrap();
+            val
+        });
+        thread::sleep(Duration::from_millis(200));
+        writer.rollback().unwrap();
+        assert_eq!(reader.join().unwrap(), 1);
+    });
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_lock_wait_timeout_aborts() {
+    let directory = "locktimeouttest";
+    remove_dir(directory);
+    let db = RustDB::with_lock_wait(directory, 400, 8, Duration::from_millis(100)).unwrap();
+    let blk = BlockId::new("testfile", 0);
+
+    let mut tx1 = db.new_tx().unwrap();
+    tx1.pin(&blk).unwrap();
+    tx1.set_int(&blk, 0, 7, true).unwrap();
+
+    let mut tx2 = db.new_tx().unwrap();
+    tx2.pin(&blk).unwrap();
+    match tx2.set_int(&blk, 0, 8, true) {
+        Err(DbError::LockAbort(b)) => assert_eq!(b, blk),
+        other => panic!("expect lock abort, got {:?}", other),
+    }
+    tx2.rollback().unwrap();
+    tx1.commit().unwrap();
+
+    remove_dir(directory);
+}
+
+#[test]
+fn test_deadlock_requester_is_victim() {
+    let lock_table = LockTable::new(Duration::from_secs(10));
+    let blk1 = BlockId::new("testfile", 1);
+    let blk2 = BlockId::new("testfile", 2);
+    //transaction 1 is older than transaction 2
+    lock_table.xlock(&blk1, 1).unwrap();
+    lock_table.xlock(&blk2, 2).unwrap();
+
+    thread::scope(|s| {
+        let older = s.spawn(|| lock_table.xlock(&blk2, 1));
+        thread::sleep(Duration::from_millis(200));
+
+        let start = Instant::now();
+        match lock_table.slock(&blk1, 2) {
+            Err(DbError::Deadlock { blk, cycle }) => {
+                assert_eq!(blk, blk1);
+                assert_eq!(cycle, vec![2, 1]);
+            }
+            other => panic!("expect deadlock, got {:?}", other),
+        }
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        //the victim rolls back and releases its locks
+        lock_table.unlock(&blk2, 2);
+        older.join().unwrap().unwrap();
+    });
+}
+
+#[test]
+fn test_deadlock_waiting_victim_aborts() {
+    let lock_table = LockTable::new(Duration::from_secs(10));
+    let blk1 = BlockId::new("testfile", 1);
+    let blk2 = BlockId::new("testfile", 2);
+    let blk3 = BlockId::new("testfile", 3);
+    lock_table.xlock(&blk1, 1).unwrap();
+    lock_table.xlock(&blk2, 2).unwrap();
+    lock_table.xlock(&blk3, 3).unwrap();
+
+    thread::scope(|s| {
+        //3 waits for 1, the youngest transaction waits before the cycle is closed
+        let youngest = s.spawn(|| {
+            let start = Instant::now();
+            let result = lock_table.xlock(&blk1, 3);
+            lock_table.unlock(&blk3, 3);
+            (result, start.elapsed())
+        });
+        thread::sleep(Duration::from_millis(200));
+        //2 waits for 3
+        let middle = s.spawn(|| lock_table.xlock(&blk3, 2));
+        thread::sleep(Duration::from_millis(200));
+
+        //1 waits for 2 closes the cycle 1 -> 2 -> 3 -> 1
+        let older = s.spawn(|| lock_table.xlock(&blk2, 1));
+
+        let (result, waited) = youngest.join().unwrap();
+        match result {
+            Err(DbError::Deadlock { blk, cycle }) => {
+                assert_eq!(blk, blk1);
+                assert_eq!(cycle, vec![3, 1, 2]);
+            }
+            other => panic!("expect deadlock, got {:?}", other),
+        }
+        assert!(waited < Duration::from_secs(5));
+
+        middle.join().unwrap().unwrap();
+        lock_table.unlock(&blk2, 2);
+        lock_table.unlock(&blk3, 2);
+        older.join().unwrap().unwrap();
+    });
+}